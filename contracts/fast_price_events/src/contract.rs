@@ -0,0 +1,191 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    to_binary, Addr, Binary, Deps, DepsMut, Env, Event, MessageInfo, Response, StdError, StdResult,
+    Storage, Uint256, Uint64,
+};
+use cw2::{get_contract_version, set_contract_version};
+
+use crate::{
+    error::ContractError,
+    msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, TokenPrice},
+    state::{PriceEvent, GOV, IS_PRICE_FEED, PRICE_EVENTS, PRICE_EVENT_COUNTS},
+};
+
+use semver::Version;
+
+// version info
+const CONTRACT_NAME: &str = "crates.io:fast_price_events";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    _: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    GOV.save(deps.storage, &info.sender)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "instantiate")
+        .add_attribute("owner", info.sender))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let version: Version = CONTRACT_VERSION.parse().map_err(from_semver)?;
+
+    // Current contract version
+    let stored_info = get_contract_version(deps.storage)?;
+
+    // Stored contract version
+    let stored_version: Version = stored_info.version.parse().map_err(from_semver)?;
+
+    // version less than stored
+    if stored_version > version {
+        return Err(ContractError::CannotMigrateVersion {
+            previous_version: stored_info.version,
+        });
+    }
+
+    // check contract type
+    if CONTRACT_NAME != stored_info.contract {
+        return Err(ContractError::CannotMigrate {
+            previous_contract: stored_info.contract,
+        });
+    }
+
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::SetIsPriceFeed {
+            price_feed,
+            is_active,
+        } => {
+            let gov = GOV.load(deps.storage)?;
+            if gov != info.sender {
+                return Err(ContractError::Forbidden {});
+            }
+
+            IS_PRICE_FEED.save(deps.storage, &price_feed, &is_active)?;
+            Ok(Response::new()
+                .add_attribute("method", "set_is_price_feed")
+                .add_attribute("price_feed", price_feed)
+                .add_attribute("is_active", is_active.to_string()))
+        }
+        ExecuteMsg::EmitPriceEvent { token, price } => {
+            emit_price_event(deps, env, info.sender, token, price)
+        }
+        ExecuteMsg::EmitPriceEvents { prices } => emit_price_events(deps, env, info.sender, prices),
+    }
+}
+
+fn only_price_feed(store: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
+    if !IS_PRICE_FEED.may_load(store, sender)?.unwrap_or_default() {
+        return Err(ContractError::InvalidSender {});
+    }
+
+    Ok(())
+}
+
+fn emit_price_event(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    token: Addr,
+    price: Uint256,
+) -> Result<Response, ContractError> {
+    only_price_feed(deps.storage, &sender)?;
+
+    PRICE_EVENTS.save(
+        deps.storage,
+        (&sender, &token),
+        &PriceEvent {
+            price,
+            timestamp: Uint64::from(env.block.time.seconds()),
+            block_height: Uint64::from(env.block.height),
+        },
+    )?;
+    let count = PRICE_EVENT_COUNTS.update(deps.storage, &sender, |count| -> StdResult<Uint64> {
+        Ok(count.unwrap_or_default().checked_add(Uint64::one())?)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("method", "price_update")
+        .add_attribute("token", token)
+        .add_attribute("price", price)
+        .add_attribute("price_feed", sender)
+        .add_attribute("count", count))
+}
+
+fn emit_price_events(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    prices: Vec<TokenPrice>,
+) -> Result<Response, ContractError> {
+    only_price_feed(deps.storage, &sender)?;
+
+    let timestamp = Uint64::from(env.block.time.seconds());
+    let block_height = Uint64::from(env.block.height);
+    let mut event = Event::new("price_updates").add_attribute("price_feed", &sender);
+    for TokenPrice { token, price } in prices.iter() {
+        PRICE_EVENTS.save(
+            deps.storage,
+            (&sender, token),
+            &PriceEvent {
+                price: *price,
+                timestamp,
+                block_height,
+            },
+        )?;
+        event = event
+            .add_attribute("token", token)
+            .add_attribute("price", *price);
+    }
+    let count = PRICE_EVENT_COUNTS.update(deps.storage, &sender, |count| -> StdResult<Uint64> {
+        Ok(count
+            .unwrap_or_default()
+            .checked_add(Uint64::from(prices.len() as u64))?)
+    })?;
+
+    Ok(Response::new()
+        .add_event(event)
+        .add_attribute("method", "price_updates")
+        .add_attribute("price_feed", sender)
+        .add_attribute("count", count))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::IsPriceFeed { address } => to_binary(
+            &IS_PRICE_FEED
+                .may_load(deps.storage, &address)?
+                .unwrap_or_default(),
+        ),
+        QueryMsg::PriceEvent { price_feed, token } => {
+            to_binary(&PRICE_EVENTS.may_load(deps.storage, (&price_feed, &token))?)
+        }
+        QueryMsg::PriceEventCount { price_feed } => to_binary(
+            &PRICE_EVENT_COUNTS
+                .may_load(deps.storage, &price_feed)?
+                .unwrap_or_default(),
+        ),
+    }
+}
+
+fn from_semver(err: semver::Error) -> StdError {
+    StdError::generic_err(format!("Semver: {}", err))
+}
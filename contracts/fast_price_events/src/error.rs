@@ -0,0 +1,20 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("FastPriceEvents: cannot migrate from {previous_contract}")]
+    CannotMigrate { previous_contract: String },
+
+    #[error("FastPriceEvents: cannot migrate from {previous_version}")]
+    CannotMigrateVersion { previous_version: String },
+
+    #[error("FastPriceEvents: forbidden")]
+    Forbidden {},
+
+    #[error("FastPriceEvents: invalid sender")]
+    InvalidSender {},
+}
@@ -0,0 +1,44 @@
+use crate::state::PriceEvent;
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, Uint256, Uint64};
+
+#[cw_serde]
+pub struct InstantiateMsg {}
+
+#[cw_serde]
+pub struct MigrateMsg {}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    SetIsPriceFeed {
+        price_feed: Addr,
+        is_active: bool,
+    },
+    EmitPriceEvent {
+        token: Addr,
+        price: Uint256,
+    },
+    /// Records the prices of one batch update with a single message
+    EmitPriceEvents {
+        prices: Vec<TokenPrice>,
+    },
+}
+
+#[cw_serde]
+pub struct TokenPrice {
+    pub token: Addr,
+    pub price: Uint256,
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    #[returns(bool)]
+    IsPriceFeed { address: Addr },
+
+    #[returns(Option<PriceEvent>)]
+    PriceEvent { price_feed: Addr, token: Addr },
+
+    #[returns(Uint64)]
+    PriceEventCount { price_feed: Addr },
+}
@@ -0,0 +1,16 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Uint256, Uint64};
+use cw_storage_plus::{Item, Map};
+
+pub const GOV: Item<Addr> = Item::new("gov");
+pub const IS_PRICE_FEED: Map<&Addr, bool> = Map::new("is_price_feed");
+// latest event per (price feed, token), keyed by the contract that emitted it
+pub const PRICE_EVENTS: Map<(&Addr, &Addr), PriceEvent> = Map::new("price_events");
+pub const PRICE_EVENT_COUNTS: Map<&Addr, Uint64> = Map::new("price_event_counts");
+
+#[cw_serde]
+pub struct PriceEvent {
+    pub price: Uint256,
+    pub timestamp: Uint64,
+    pub block_height: Uint64,
+}
@@ -0,0 +1,207 @@
+use crate::contract::{execute, instantiate, query};
+use crate::error::ContractError;
+use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, TokenPrice};
+use crate::state::PriceEvent;
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_std::{from_binary, Addr, Uint256, Uint64};
+
+#[test]
+fn test_set_is_price_feed() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let price_feed = Addr::unchecked("price_feed");
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("gov", &[]),
+        InstantiateMsg {},
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::SetIsPriceFeed {
+        price_feed: price_feed.clone(),
+        is_active: true,
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("random", &[]),
+        msg.clone(),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Forbidden {});
+
+    execute(deps.as_mut(), env.clone(), mock_info("gov", &[]), msg).unwrap();
+
+    let is_price_feed: bool = from_binary(
+        &query(
+            deps.as_ref(),
+            env,
+            QueryMsg::IsPriceFeed {
+                address: price_feed,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(is_price_feed);
+}
+
+#[test]
+fn test_emit_price_event() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let price_feed = Addr::unchecked("price_feed");
+    let token = Addr::unchecked("token");
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("gov", &[]),
+        InstantiateMsg {},
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::EmitPriceEvent {
+        token: token.clone(),
+        price: Uint256::from(100u64),
+    };
+
+    // only registered price feeds can emit events
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(price_feed.as_str(), &[]),
+        msg.clone(),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::InvalidSender {});
+
+    let set_price_feed = ExecuteMsg::SetIsPriceFeed {
+        price_feed: price_feed.clone(),
+        is_active: true,
+    };
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("gov", &[]),
+        set_price_feed,
+    )
+    .unwrap();
+
+    for _ in 0..2 {
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(price_feed.as_str(), &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(res.attributes[0].value, "price_update");
+    }
+
+    let event: Option<PriceEvent> = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::PriceEvent {
+                price_feed: price_feed.clone(),
+                token,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        event,
+        Some(PriceEvent {
+            price: Uint256::from(100u64),
+            timestamp: Uint64::from(env.block.time.seconds()),
+            block_height: Uint64::from(env.block.height),
+        })
+    );
+
+    let count: Uint64 =
+        from_binary(&query(deps.as_ref(), env, QueryMsg::PriceEventCount { price_feed }).unwrap())
+            .unwrap();
+    assert_eq!(count, Uint64::new(2));
+}
+
+#[test]
+fn test_emit_price_events() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let price_feed = Addr::unchecked("price_feed");
+    let prices = vec![
+        TokenPrice {
+            token: Addr::unchecked("eth"),
+            price: Uint256::from(3000u64),
+        },
+        TokenPrice {
+            token: Addr::unchecked("btc"),
+            price: Uint256::from(60000u64),
+        },
+    ];
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("gov", &[]),
+        InstantiateMsg {},
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::EmitPriceEvents {
+        prices: prices.clone(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(price_feed.as_str(), &[]),
+        msg.clone(),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::InvalidSender {});
+
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("gov", &[]),
+        ExecuteMsg::SetIsPriceFeed {
+            price_feed: price_feed.clone(),
+            is_active: true,
+        },
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(price_feed.as_str(), &[]),
+        msg,
+    )
+    .unwrap();
+
+    // one event carries every price of the batch
+    assert_eq!(res.events.len(), 1);
+    assert_eq!(res.events[0].ty, "price_updates");
+    assert_eq!(res.events[0].attributes.len(), 1 + 2 * prices.len());
+
+    for TokenPrice { token, price } in prices {
+        let event: Option<PriceEvent> = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::PriceEvent {
+                    price_feed: price_feed.clone(),
+                    token,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(event.unwrap().price, price);
+    }
+
+    let count: Uint64 =
+        from_binary(&query(deps.as_ref(), env, QueryMsg::PriceEventCount { price_feed }).unwrap())
+            .unwrap();
+    assert_eq!(count, Uint64::new(2));
+}
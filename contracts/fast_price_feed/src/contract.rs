@@ -1,42 +1,53 @@
 use cosmwasm_std::{
-    entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+    entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
+    Uint256, Uint64,
 };
 
 use crate::{
     errors::ContractError,
     execute::*,
     msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg},
+    multisig::*,
+    pause::*,
+    price_bits::*,
     query::*,
+    rbac::*,
+    timelock::*,
 };
 
 use crate::execute::init;
-use crate::helpers::from_semver;
+use crate::helpers::{from_semver, load_disable_votes, load_token_state};
+use crate::migrations::{
+    apply_migrate_params, latest_storage_version, run_migration_steps, validate_migrate_params,
+    STORAGE_VERSION,
+};
 use crate::msg::ExecuteMsg::*;
 use crate::state::*;
 use cw2::{get_contract_version, set_contract_version};
 use semver::Version;
 
-const CONTRACT_NAME: &str = "crates.io:{{project-name}}";
+const CONTRACT_NAME: &str = "crates.io:fast_price_feed";
+// name stored by deployments instantiated before the name was fixed
+const LEGACY_CONTRACT_NAME: &str = "crates.io:crates.io:{{project-name}}";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
-    _: Env,
-    _: MessageInfo,
+    env: Env,
+    info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
-    set_contract_version(
-        deps.storage,
-        format!("crates.io:{CONTRACT_NAME}"),
-        CONTRACT_VERSION,
-    )?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    STORAGE_VERSION.save(deps.storage, &latest_storage_version())?;
 
-    init(deps, msg.config)
+    // signer proposals are dispatched by the contract itself
+    set_role_member(deps.storage, Role::Gov, &env.contract.address, true)?;
+    init(deps, info.sender, msg)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
     let version: Version = CONTRACT_VERSION.parse().map_err(from_semver)?;
 
     // Current contract version
@@ -53,13 +64,22 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, C
     }
 
     // Check contract type
-    if CONTRACT_NAME != stored_info.contract {
+    if CONTRACT_NAME != stored_info.contract && LEGACY_CONTRACT_NAME != stored_info.contract {
         return Err(ContractError::CannotMigrate {
             previous_contract: stored_info.contract,
         });
     }
 
-    Ok(Response::default())
+    validate_migrate_params(&msg)?;
+
+    let steps = run_migration_steps(deps.storage, &env)?;
+    apply_migrate_params(deps.storage, msg)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("previous_version", stored_info.version)
+        .add_attribute("steps", steps.join(",")))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -84,6 +104,28 @@ pub fn execute(
         SetVaultPriceFeed { vault_price_feed } => {
             set_vault_price_feed(deps, sender, vault_price_feed)
         }
+        SetPrimaryPriceFeed { token, price_feed } => {
+            set_primary_price_feed(deps, sender, token, price_feed)
+        }
+        SetQuorumConfig { quorum_config } => set_quorum_config(deps, sender, quorum_config),
+        SetCircuitBreakerConfig {
+            circuit_breaker_config,
+        } => set_circuit_breaker_config(deps, sender, circuit_breaker_config),
+        SetPriceBitLayout { layout } => set_price_bit_layout(deps, sender, layout),
+        ResetCircuitBreaker { token } => reset_circuit_breaker(deps, sender, token),
+        SetUpdaterPubkey {
+            pubkey,
+            pubkey_type,
+            is_active,
+        } => set_updater_pubkey(deps, sender, pubkey, pubkey_type, is_active),
+        SetPositionRouter {
+            position_router,
+            is_active,
+        } => set_position_router(deps, sender, position_router, is_active),
+        SetPrimaryPriceConfig {
+            price_decimals,
+            max_price_age,
+        } => set_primary_price_config(deps, sender, price_decimals, max_price_age),
         SetMaxTimeDeviation { max_time_deviation } => {
             set_max_time_deviation(deps, sender, max_time_deviation)
         }
@@ -105,8 +147,34 @@ pub fn execute(
         SetIsSpreadEnabled { spread_enabled } => {
             set_is_spread_enabled(deps, sender, spread_enabled)
         }
-        SetLastUpdatedAt { last_updated_at } => set_last_updated_at(deps, sender, last_updated_at),
-        SetTokenManager { token_manager } => set_token_manager(deps, sender, token_manager),
+        SetTimelockDelay { timelock_delay } => set_timelock_delay(deps, sender, timelock_delay),
+        QueueAction { action } => queue_action(deps, env, sender, action),
+        ExecuteAction { id } => execute_action(deps, env, sender, id),
+        CancelAction { id } => cancel_action(deps, sender, id),
+        Pause {} => pause(deps, env, sender),
+        Unpause {} => unpause(deps, sender),
+        SetPauseFallback { ref_price_fallback } => {
+            set_pause_fallback(deps, sender, ref_price_fallback)
+        }
+        SubmitProposal { msg, expires_at } => submit_proposal(deps, env, sender, *msg, expires_at),
+        ApproveProposal { id } => approve_proposal(deps, env, sender, id),
+        ExecuteProposal { id } => execute_proposal(deps, env, sender, id),
+        SetLastUpdatedAt {
+            token,
+            last_updated_at,
+        } => set_last_updated_at(deps, sender, token, last_updated_at),
+        ProposeOwnership {
+            role,
+            new_owner,
+            expires_at,
+        } => propose_ownership(deps, env, sender, role, new_owner, expires_at),
+        AcceptOwnership { role } => accept_ownership(deps, env, sender, role),
+        CancelOwnershipTransfer { role } => cancel_ownership_transfer(deps, sender, role),
+        GrantRole { role, account } => grant_role(deps, sender, role, account),
+        RevokeRole { role, account } => revoke_role(deps, sender, role, account),
+        RenounceRole { role } => renounce_role(deps, sender, role),
+        SetRoleAdmin { role, admin_role } => set_role_admin(deps, sender, role, admin_role),
+        UpdateConfig { update } => update_config(deps, sender, update),
         SetMaxDeviationBasisPoints {
             max_deviation_basis_points,
         } => set_max_deviation_basis_points(deps, sender, max_deviation_basis_points),
@@ -114,31 +182,49 @@ pub fn execute(
             tokens,
             max_cumulative_delta_diffs,
         } => set_max_cumulative_delta_diffs(deps, sender, tokens, max_cumulative_delta_diffs),
+        SetTokenParams { token, params } => set_token_params(deps, sender, token, params),
         SetPriceDataInterval {
             price_data_interval,
         } => set_price_data_interval(deps, sender, price_data_interval),
         SetMinAuthorizations { min_authorizations } => {
             set_min_authorizations(deps, sender, min_authorizations)
         }
-        SetTokens {
-            tokens,
+        AddToken {
+            token,
+            token_precision,
+        } => add_token(deps, sender, token, token_precision),
+        RemoveToken { token } => remove_token(deps, sender, token),
+        UpdateTokenPrecision {
+            token,
             token_precision,
-        } => set_tokens(deps, env, sender, tokens, token_precision),
+        } => update_token_precision(deps, sender, token, token_precision),
         SetPrices {
             tokens,
             prices,
             timestamp,
         } => set_prices(deps, sender, env, tokens, prices, timestamp),
+        SetPricesSigned {
+            tokens,
+            prices,
+            timestamp,
+            signatures,
+        } => set_prices_signed(deps, env, tokens, prices, timestamp, signatures),
+        SubmitObservations {
+            tokens,
+            prices,
+            timestamp,
+        } => submit_observations(deps, env, sender, tokens, prices, timestamp),
         SetCompactedPrices {
             price_bit_array,
             timestamp,
-        } => set_compacted_prices(deps, env, price_bit_array, timestamp),
+            layout,
+        } => set_compacted_prices(deps, env, sender, price_bit_array, timestamp, layout),
         SetPricesWithBits {
             price_bits,
             timestamp,
-        } => set_prices_with_bits(deps, env, price_bits, timestamp),
+            layout,
+        } => set_prices_with_bits(deps, env, sender, price_bits, timestamp, layout),
         SetPricesWithBitsAndExecute {
-            sender,
             position_router_addr,
             price_bits,
             timestamp,
@@ -146,6 +232,7 @@ pub fn execute(
             end_index_for_decrease_positions,
             max_increase_positions,
             max_decrease_positions,
+            layout,
         } => set_prices_with_bits_and_execute(
             deps,
             env,
@@ -157,43 +244,155 @@ pub fn execute(
             end_index_for_decrease_positions,
             max_increase_positions,
             max_decrease_positions,
+            layout,
         ),
-        DisableFastPrice => disable_fast_price(deps, env, sender),
-        EnableFastPrice => enable_fast_price(deps, env, sender),
+        DisableFastPrice { token, expires_at } => {
+            disable_fast_price(deps, env, sender, token, expires_at)
+        }
+        StartDisableVoteEpoch {} => start_disable_vote_epoch(deps, sender),
+        EnableFastPrice { token } => enable_fast_price(deps, env, sender, token),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetPrice {
             token,
-            block_timestamp,
             ref_price,
             maximise,
-        } => {
-            let response = get_price(deps, token, block_timestamp, ref_price, maximise)?;
-            to_binary(&response)
-        }
-        QueryMsg::FavorFastPrice { token } => to_binary(&favor_fast_price(deps, token)?),
+        } => to_binary(&get_price(
+            deps,
+            token,
+            Uint64::from(env.block.time.seconds()),
+            ref_price,
+            maximise,
+        )?),
+        QueryMsg::FavorFastPrice { token } => to_binary(&favor_fast_price(
+            deps,
+            token,
+            Uint64::from(env.block.time.seconds()),
+        )?),
         QueryMsg::GetPriceData { token } => to_binary(&get_price_data(deps, token)?),
-        QueryMsg::GetConfig => to_binary(&CONFIG.load(deps.storage)?),
-        QueryMsg::IsUpdater { address } => to_binary(&IS_UPDATER.load(deps.storage, &address)?),
-        QueryMsg::Prices { address } => to_binary(&PRICES.load(deps.storage, &address)?),
-        QueryMsg::PriceData { address } => to_binary(&PRICE_DATA.load(deps.storage, &address)?),
+        QueryMsg::GetConfig => to_binary(&GLOBAL_STATE.load(deps.storage)?.config),
+        QueryMsg::IsUpdater { address } => {
+            to_binary(&has_role(deps.storage, Role::Updater, &address))
+        }
+        QueryMsg::Prices { address } => to_binary(&load_token_state(deps.storage, &address)?.price),
+        QueryMsg::PriceData { address } => to_binary(&get_price_data(deps, address)?),
         QueryMsg::MaxCumulativeDeltaDiffs { address } => {
-            to_binary(&MAX_CUMULATIVE_DELTA_DIFFS.load(deps.storage, &address)?)
+            to_binary(&load_token_state(deps.storage, &address)?.max_cumulative_delta_diff)
+        }
+        QueryMsg::IsPositionRouter { address } => {
+            to_binary(&has_role(deps.storage, Role::PositionRouter, &address))
+        }
+        QueryMsg::QuorumConfig => {
+            to_binary(&QUORUM_CONFIG.may_load(deps.storage)?.unwrap_or_default())
+        }
+        QueryMsg::PendingRound { token } => {
+            to_binary(&PENDING_ROUNDS.may_load(deps.storage, &token)?)
+        }
+        QueryMsg::CircuitBreakerConfig => to_binary(
+            &CIRCUIT_BREAKER_CONFIG
+                .may_load(deps.storage)?
+                .unwrap_or_default(),
+        ),
+        QueryMsg::PriceBitLayout => to_binary(&price_bit_layout(deps.storage)?),
+        QueryMsg::CircuitBreaker { token } => {
+            to_binary(&load_token_state(deps.storage, &token)?.circuit_breaker)
+        }
+        QueryMsg::UpdaterPubkey { pubkey } => {
+            to_binary(&UPDATER_PUBKEYS.may_load(deps.storage, pubkey.as_slice())?)
+        }
+        QueryMsg::UpdaterNonce { pubkey } => to_binary(&Uint64::from(
+            UPDATER_NONCES
+                .may_load(deps.storage, pubkey.as_slice())?
+                .unwrap_or_default(),
+        )),
+        QueryMsg::IsSigner { address } => {
+            to_binary(&has_role(deps.storage, Role::Signer, &address))
         }
-        QueryMsg::IsSigner { address } => to_binary(&IS_SIGNER.load(deps.storage, &address)?),
-        QueryMsg::DisableFastPriceVotes { address } => {
-            to_binary(&DISABLE_FAST_PRICE_VOTES.load(deps.storage, &address)?)
+        QueryMsg::DisableFastPriceVotes { address, token } => {
+            let votes = load_disable_votes(
+                deps.storage,
+                token.as_ref(),
+                Uint64::from(env.block.time.seconds()),
+            )?;
+            to_binary(&votes.iter().any(|(signer, _)| signer == address))
         }
-        QueryMsg::MinAuthorizations => to_binary(&MIN_AUTH.load(deps.storage)?),
+        QueryMsg::HasRole { role, address } => to_binary(&has_role(deps.storage, role, &address)),
+        QueryMsg::RoleMembers {
+            role,
+            start_after,
+            limit,
+        } => to_binary(&role_members(deps, role, start_after, limit)?),
+        QueryMsg::RoleAdmin { role } => to_binary(&role_admin(deps.storage, role)?),
+        QueryMsg::PendingOwner { role } => {
+            to_binary(&PENDING_OWNERS.may_load(deps.storage, role.key())?)
+        }
+        QueryMsg::TimelockDelay => to_binary(&Uint64::from(timelock_delay(deps.storage)?)),
+        QueryMsg::PendingAction { id } => to_binary(&PENDING_ACTIONS.may_load(deps.storage, id)?),
+        QueryMsg::PendingActions { start_after, limit } => {
+            to_binary(&pending_actions(deps, start_after, limit)?)
+        }
+        QueryMsg::PauseState => to_binary(&pause_state(deps.storage)?),
+        QueryMsg::Proposal { id } => to_binary(&SIGNER_PROPOSALS.may_load(deps.storage, id)?),
+        QueryMsg::Proposals { start_after, limit } => {
+            to_binary(&signer_proposals(deps, start_after, limit)?)
+        }
+        QueryMsg::MinAuthorizations => to_binary(
+            &GLOBAL_STATE
+                .load(deps.storage)?
+                .min_authorizations
+                .ok_or_else(|| StdError::not_found("min_authorizations"))?,
+        ),
+        QueryMsg::SignerCount => to_binary(&role_member_count(deps.storage, Role::Signer)?),
+        QueryMsg::LastUpdated { token } => to_binary(&get_last_updated(deps, token)?),
         QueryMsg::MaxTimeDeviation => to_binary(&MAX_TIME_DEVIATION.load(deps.storage)?),
-        QueryMsg::SpreadBasisPoint => to_binary(&SPREAD_BASIS_POINT_STATE.load(deps.storage)?),
-        QueryMsg::TokenData => to_binary(&TOKEN_DATA.load(deps.storage)?),
-        QueryMsg::DisableFastPriceVoteCount => {
-            to_binary(&DISABLE_FAST_PRICE_VOTE_COUNT.load(deps.storage)?)
+        QueryMsg::SpreadBasisPoint => {
+            to_binary(&GLOBAL_STATE.load(deps.storage)?.spread_basis_points)
+        }
+        QueryMsg::GlobalState => to_binary(&GLOBAL_STATE.load(deps.storage)?),
+        QueryMsg::TokenState { token } => to_binary(&load_token_state(deps.storage, &token)?),
+        QueryMsg::TokenParams { token } => to_binary(&get_token_params(deps, token)?),
+        QueryMsg::EffectiveTokenParams { token } => {
+            to_binary(&get_effective_token_params(deps, token)?)
+        }
+        QueryMsg::Token { token } => to_binary(&get_token(deps, token)?),
+        QueryMsg::Tokens { start_after, limit } => {
+            to_binary(&get_tokens(deps, start_after, limit)?)
         }
+        QueryMsg::TokenIndexes { start_after, limit } => {
+            to_binary(&get_token_indexes(deps, start_after, limit)?)
+        }
+        QueryMsg::PrimaryPriceConfig => to_binary(
+            &PRIMARY_PRICE_CONFIG
+                .may_load(deps.storage)?
+                .unwrap_or_default(),
+        ),
+        QueryMsg::PrimaryPriceFeed { token } => {
+            to_binary(&match PRIMARY_PRICE_FEEDS.may_load(deps.storage, &token)? {
+                Some(price_feed) => Some(price_feed),
+                None => VAULT_ADDRESS.may_load(deps.storage)?,
+            })
+        }
+        QueryMsg::DisableFastPriceVoteCount { token } => {
+            let votes = load_disable_votes(
+                deps.storage,
+                token.as_ref(),
+                Uint64::from(env.block.time.seconds()),
+            )?;
+            to_binary(&Uint256::from(votes.len() as u64))
+        }
+        QueryMsg::DisableFastPriceVoters { token } => to_binary(&get_disable_fast_price_voters(
+            deps,
+            token,
+            Uint64::from(env.block.time.seconds()),
+        )?),
+        QueryMsg::DisableVoteEpoch => to_binary(&Uint64::from(
+            DISABLE_VOTE_EPOCH
+                .may_load(deps.storage)?
+                .unwrap_or_default(),
+        )),
     }
 }
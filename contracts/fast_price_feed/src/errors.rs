@@ -1,4 +1,7 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{
+    CheckedMultiplyRatioError, ConversionOverflowError, DivideByZeroError, OverflowError, StdError,
+    Uint256, Uint64,
+};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -6,12 +9,30 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("FastPriceFeed: {0}")]
+    Overflow(#[from] OverflowError),
+
+    #[error("FastPriceFeed: {0}")]
+    DivideByZero(#[from] DivideByZeroError),
+
+    #[error("FastPriceFeed: {0}")]
+    ConversionOverflow(#[from] ConversionOverflowError),
+
+    #[error("FastPriceFeed: {0}")]
+    MultiplyRatio(#[from] CheckedMultiplyRatioError),
+
+    #[error("FastPriceFeed: {item} is not initialized")]
+    Uninitialized { item: String },
+
     #[error("FastPriceFeed: cannot migrate from {previous_contract}")]
     CannotMigrate { previous_contract: String },
 
     #[error("FastPriceFeed: cannot migrate from {previous_version}")]
     CannotMigrateVersion { previous_version: String },
 
+    #[error("FastPriceFeed: stored {field} {value} is out of range for seconds")]
+    TimeOutOfRange { field: String, value: Uint64 },
+
     #[error("FastPriceFeed: invalid lengths")]
     InvalidLength {},
 
@@ -21,6 +42,15 @@ pub enum ContractError {
     #[error("FastPriceFeed: invalid priceDuration")]
     InvalidPriceDuration {},
 
+    #[error("FastPriceFeed: priceDuration must be below maxPriceUpdateDelay")]
+    InvalidMaxPriceUpdateDelay {},
+
+    #[error("FastPriceFeed: priceDataInterval must be nonzero")]
+    InvalidPriceDataInterval {},
+
+    #[error("FastPriceFeed: invalid spread basis points")]
+    InvalidSpreadBasisPoints {},
+
     #[error("FastPriceFeed: already initialized")]
     AlreadyInitialized {},
 
@@ -38,4 +68,139 @@ pub enum ContractError {
 
     #[error("FastPriceFeed: minBlockInterval not yet passeds")]
     MinblockInterval {},
+
+    #[error("FastPriceFeed: invalid position router {position_router}")]
+    InvalidPositionRouter { position_router: String },
+
+    #[error("FastPriceFeed: token {token} already exists")]
+    TokenAlreadyExists { token: String },
+
+    #[error("FastPriceFeed: token {token} not found")]
+    TokenNotFound { token: String },
+
+    #[error("FastPriceFeed: invalid token precision")]
+    InvalidTokenPrecision {},
+
+    #[error("FastPriceFeed: missing signatures")]
+    MissingSignatures {},
+
+    #[error("FastPriceFeed: unknown updater key {pubkey}")]
+    UnknownPubkey { pubkey: String },
+
+    #[error("FastPriceFeed: duplicate updater key {pubkey}")]
+    DuplicatePubkey { pubkey: String },
+
+    #[error("FastPriceFeed: invalid signature for updater key {pubkey}")]
+    InvalidSignature { pubkey: String },
+
+    #[error("FastPriceFeed: nonce must be greater than {last_nonce}")]
+    InvalidNonce { last_nonce: u64 },
+
+    #[error("FastPriceFeed: prices must be submitted as observations in quorum mode")]
+    QuorumModeEnabled {},
+
+    #[error("FastPriceFeed: quorum mode is disabled")]
+    QuorumModeDisabled {},
+
+    #[error("FastPriceFeed: invalid min observations")]
+    InvalidMinObservations {},
+
+    #[error("FastPriceFeed: {min_observations} observations need at least as many updaters")]
+    InsufficientUpdaters { min_observations: Uint256 },
+
+    #[error("FastPriceFeed: observation is older than the last committed price")]
+    ObservationTooOld {},
+
+    #[error(
+        "FastPriceFeed: min authorizations {min_authorizations} outside of 1..={signer_count}"
+    )]
+    InvalidMinAuthorizations {
+        min_authorizations: Uint256,
+        signer_count: u32,
+    },
+
+    #[error("FastPriceFeed: removing the signer leaves fewer than {min_authorizations} signers")]
+    InsufficientSigners { min_authorizations: Uint256 },
+
+    #[error("FastPriceFeed: no pending ownership transfer")]
+    NoPendingOwner {},
+
+    #[error("FastPriceFeed: ownership transfer expired")]
+    OwnershipTransferExpired {},
+
+    #[error("FastPriceFeed: ownership transfer expiry must be in the future")]
+    InvalidOwnershipTransferExpiry {},
+
+    #[error("FastPriceFeed: vote expiry must be in the future")]
+    InvalidVoteExpiry {},
+
+    #[error("FastPriceFeed: change has to be queued in the timelock")]
+    TimelockRequired {},
+
+    #[error("FastPriceFeed: timelock action {id} not found")]
+    TimelockActionNotFound { id: u64 },
+
+    #[error("FastPriceFeed: timelock action not ready before {eta}")]
+    TimelockNotReady { eta: Uint64 },
+
+    #[error("FastPriceFeed: invalid timelock delay")]
+    InvalidTimelockDelay {},
+
+    #[error("FastPriceFeed: only gov messages can be proposed")]
+    InvalidProposalMsg {},
+
+    #[error("FastPriceFeed: proposal expiry must be in the future")]
+    InvalidProposalExpiry {},
+
+    #[error("FastPriceFeed: proposal {id} not found")]
+    ProposalNotFound { id: u64 },
+
+    #[error("FastPriceFeed: proposal {id} expired")]
+    ProposalExpired { id: u64 },
+
+    #[error("FastPriceFeed: proposal {id} already executed")]
+    ProposalAlreadyExecuted { id: u64 },
+
+    #[error("FastPriceFeed: proposal needs {min_authorizations} approvals")]
+    InsufficientApprovals { min_authorizations: Uint256 },
+
+    #[error("FastPriceFeed: paused")]
+    Paused {},
+
+    #[error("FastPriceFeed: not paused")]
+    NotPaused {},
+
+    #[error("FastPriceFeed: circuit breaker is not tripped")]
+    CircuitBreakerNotTripped {},
+
+    #[error("FastPriceFeed: invalid circuit breaker config")]
+    InvalidCircuitBreakerConfig {},
+
+    #[error("FastPriceFeed: primary price is missing")]
+    PrimaryPriceMissing {},
+
+    #[error("FastPriceFeed: primary price is negative")]
+    PrimaryPriceNegative {},
+
+    #[error("FastPriceFeed: primary price is stale, updated at {updated_at}")]
+    PrimaryPriceStale { updated_at: Uint64 },
+
+    #[error("FastPriceFeed: invalid price decimals")]
+    InvalidPriceDecimals {},
+
+    #[error("FastPriceFeed: invalid price bit layout")]
+    InvalidPriceBitLayout {},
+
+    #[error("FastPriceFeed: price {price} exceeds the bit width")]
+    PriceExceedsBitWidth { price: Uint256 },
+}
+
+// queries answer with a StdError
+impl From<ContractError> for StdError {
+    fn from(err: ContractError) -> Self {
+        match err {
+            ContractError::Std(err) => err,
+            err => StdError::generic_err(err.to_string()),
+        }
+    }
 }
@@ -1,25 +1,117 @@
 use crate::errors::ContractError;
+use crate::msg::{ConfigUpdate, InstantiateMsg, PositionRouterExecuteMsg, PriceSignature};
 use crate::state::*;
 use cosmwasm_std::{
-    to_binary, Addr, BlockInfo, CosmosMsg, DepsMut, Env, Response, StdError, StdResult, Storage,
-    Uint256, Uint64, WasmMsg,
+    to_binary, Addr, Binary, BlockInfo, CosmosMsg, DepsMut, Env, Event, Order, OverflowError,
+    OverflowOperation, QuerierWrapper, Response, StdError, StdResult, Storage, Uint256, Uint64,
+    WasmMsg,
 };
 
 use crate::helpers::*;
+use crate::pause::when_not_paused;
+use crate::price_bits::{decode_prices, resolve_price_bit_layout, PriceBitLayout};
+use crate::rbac::*;
+use crate::timelock::{apply_immediately, check_immediate, TimelockAction};
+use fast_price_events::msg::{ExecuteMsg as FastPriceEventsExecuteMsg, TokenPrice};
+use std::collections::BTreeMap;
 
 const CUMULATIVE_DELTA_PRECISION: Uint256 = Uint256::from_u128(10 * 1000 * 1000u128);
 pub const PRICE_PRECISION: Uint256 = Uint256::from_u128(10u128.pow(30));
 
-// 30 mins * 60 seconds (max price duration in seconds)
-const MAX_PRICE_DURATION: Uint64 = Uint64::new(30 * 60 * 1_000_000_000u64);
+// 30 mins * 60 seconds
+pub(crate) const MAX_PRICE_DURATION: Uint64 = Uint64::new(30 * 60);
+
+// defaults used at instantiate, in seconds
+const DEFAULT_MAX_TIME_DEVIATION: u64 = 60 * 60;
+const DEFAULT_PRICE_DATA_INTERVAL: u64 = 60;
+
+pub fn init(deps: DepsMut, sender: Addr, msg: InstantiateMsg) -> Result<Response, ContractError> {
+    let config = msg.config;
+    let spread_basis_points = msg.spread_basis_points.unwrap_or_default();
+    let price_data_interval = msg
+        .price_data_interval
+        .unwrap_or(Uint64::new(DEFAULT_PRICE_DATA_INTERVAL));
+    validate_config(&config, &spread_basis_points, price_data_interval)?;
+
+    let mut signers: Vec<&Addr> = vec![];
+    for signer in msg.signers.iter() {
+        if !signers.contains(&signer) {
+            signers.push(signer);
+        }
+    }
+    // signers can also be added later through `Initialize`
+    let min_authorizations = if signers.is_empty() {
+        None
+    } else {
+        let min_authorizations = msg
+            .min_authorizations
+            .unwrap_or(Uint256::from(signers.len() as u64 / 2 + 1));
+        validate_min_authorizations(min_authorizations, signers.len() as u32)?;
+        Some(min_authorizations)
+    };
+
+    for (i, initial_token) in msg.tokens.iter().enumerate() {
+        if initial_token.token_precision.is_zero() {
+            return Err(ContractError::InvalidTokenPrecision {});
+        }
+        if msg.tokens[..i]
+            .iter()
+            .any(|other| other.token == initial_token.token)
+        {
+            return Err(ContractError::TokenAlreadyExists {
+                token: initial_token.token.to_string(),
+            });
+        }
+    }
 
-pub fn init(deps: DepsMut, config: Config) -> Result<Response, ContractError> {
-    if config.price_duration <= Uint64::zero() {
-        return Err(ContractError::InvalidPriceDuration {});
+    let gov = msg.gov.unwrap_or(sender);
+    set_role_member(deps.storage, Role::Gov, &gov, true)?;
+    set_role_member(deps.storage, Role::TokenManager, &msg.token_manager, true)?;
+    for signer in signers {
+        set_role_member(deps.storage, Role::Signer, signer, true)?;
+    }
+    for updater in msg.updaters.iter() {
+        set_role_member(deps.storage, Role::Updater, updater, true)?;
+    }
+    if min_authorizations.is_some() {
+        IS_INITIALIZED.save(deps.storage, &true)?;
     }
 
-    CONFIG.save(deps.storage, &config)?;
-    Ok(Response::new().add_attribute("method", "init"))
+    for initial_token in msg.tokens {
+        register_token(
+            deps.storage,
+            initial_token.token,
+            initial_token.token_precision,
+        )?;
+    }
+
+    if let Some(vault_price_feed) = msg.vault_price_feed {
+        VAULT_ADDRESS.save(deps.storage, &vault_price_feed)?;
+    }
+    GLOBAL_STATE.save(
+        deps.storage,
+        &GlobalState {
+            config,
+            spread_basis_points,
+            spread_enabled: msg.spread_enabled.unwrap_or_default(),
+            min_authorizations,
+            disable_votes: DisableVoteTally::default(),
+        },
+    )?;
+    MAX_TIME_DEVIATION.save(
+        deps.storage,
+        &msg.max_time_deviation
+            .unwrap_or(Uint64::new(DEFAULT_MAX_TIME_DEVIATION)),
+    )?;
+    PRICE_DATA_INTERVAL.save(deps.storage, &price_data_interval)?;
+    let primary_price_config = msg.primary_price_config.unwrap_or_default();
+    validate_primary_price_config(&primary_price_config)?;
+    PRIMARY_PRICE_CONFIG.save(deps.storage, &primary_price_config)?;
+    LAST_UPDATED.save(deps.storage, &LastUpdated::default())?;
+
+    Ok(Response::new()
+        .add_attribute("method", "init")
+        .add_attribute("gov", gov))
 }
 
 pub fn initialize(
@@ -35,15 +127,26 @@ pub fn initialize(
         return Err(ContractError::AlreadyInitialized {});
     }
 
+    let mut new_signers: Vec<&Addr> = vec![];
     for signer in signers.iter() {
-        IS_SIGNER.save(deps.storage, signer, &true)?;
+        if !new_signers.contains(&signer) && !has_role(deps.storage, Role::Signer, signer) {
+            new_signers.push(signer);
+        }
+    }
+    let signer_count = role_member_count(deps.storage, Role::Signer)? + new_signers.len() as u32;
+    validate_min_authorizations(min_auth, signer_count)?;
+
+    for signer in new_signers {
+        set_role_member(deps.storage, Role::Signer, signer, true)?;
     }
 
     for updater in updaters.iter() {
-        IS_UPDATER.save(deps.storage, updater, &true)?;
+        set_role_member(deps.storage, Role::Updater, updater, true)?;
     }
 
-    MIN_AUTH.save(deps.storage, &min_auth)?;
+    update_global_state(deps.storage, |global| {
+        global.min_authorizations = Some(min_auth)
+    })?;
 
     IS_INITIALIZED.save(deps.storage, &true)?;
 
@@ -58,7 +161,7 @@ pub fn set_signer(
 ) -> Result<Response, ContractError> {
     is_gov(deps.as_ref(), &sender)?;
 
-    IS_SIGNER.save(deps.storage, &account, &is_active)?;
+    set_role_member(deps.storage, Role::Signer, &account, is_active)?;
 
     Ok(Response::new()
         .add_attribute("method", "set_signer")
@@ -74,7 +177,7 @@ pub fn set_updater(
 ) -> Result<Response, ContractError> {
     is_gov(deps.as_ref(), &sender)?;
 
-    IS_UPDATER.save(deps.storage, &account, &is_active)?;
+    set_role_member(deps.storage, Role::Updater, &account, is_active)?;
 
     Ok(Response::new()
         .add_attribute("method", "set_updater")
@@ -88,14 +191,9 @@ pub fn set_fast_price_events(
     fast_price_events: Addr,
 ) -> Result<Response, ContractError> {
     is_gov(deps.as_ref(), &sender)?;
-    CONFIG.update(
-        deps.storage,
-        |mut config| -> Result<Config, ContractError> {
-            config.fast_price_events = fast_price_events.clone();
-
-            Ok(config)
-        },
-    )?;
+    update_global_state(deps.storage, |global| {
+        global.config.fast_price_events = fast_price_events.clone()
+    })?;
 
     Ok(Response::new()
         .add_attribute("method", "set_fast_price_event")
@@ -115,13 +213,96 @@ pub fn set_vault_price_feed(
         .add_attribute("vault_price_feed", vault_price_feed))
 }
 
+pub fn set_primary_price_feed(
+    deps: DepsMut,
+    sender: Addr,
+    token: Addr,
+    price_feed: Option<Addr>,
+) -> Result<Response, ContractError> {
+    is_gov(deps.as_ref(), &sender)?;
+    load_token(deps.storage, &token)?;
+    match &price_feed {
+        Some(price_feed) => PRIMARY_PRICE_FEEDS.save(deps.storage, &token, price_feed)?,
+        None => PRIMARY_PRICE_FEEDS.remove(deps.storage, &token),
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "set_primary_price_feed")
+        .add_attribute("token", token)
+        .add_attribute(
+            "price_feed",
+            price_feed.map(String::from).unwrap_or_default(),
+        ))
+}
+
+pub fn set_updater_pubkey(
+    deps: DepsMut,
+    sender: Addr,
+    pubkey: Binary,
+    pubkey_type: PubkeyType,
+    is_active: bool,
+) -> Result<Response, ContractError> {
+    is_gov(deps.as_ref(), &sender)?;
+
+    if is_active {
+        UPDATER_PUBKEYS.save(deps.storage, pubkey.as_slice(), &pubkey_type)?;
+    } else {
+        UPDATER_PUBKEYS.remove(deps.storage, pubkey.as_slice());
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "set_updater_pubkey")
+        .add_attribute("pubkey", pubkey.to_base64())
+        .add_attribute("is_active", is_active.to_string()))
+}
+
+pub fn set_position_router(
+    deps: DepsMut,
+    sender: Addr,
+    position_router: Addr,
+    is_active: bool,
+) -> Result<Response, ContractError> {
+    is_gov(deps.as_ref(), &sender)?;
+    set_role_member(
+        deps.storage,
+        Role::PositionRouter,
+        &position_router,
+        is_active,
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("method", "set_position_router")
+        .add_attribute("position_router", position_router)
+        .add_attribute("is_active", is_active.to_string()))
+}
+
+pub fn set_primary_price_config(
+    deps: DepsMut,
+    sender: Addr,
+    price_decimals: u32,
+    max_price_age: Uint64,
+) -> Result<Response, ContractError> {
+    is_gov(deps.as_ref(), &sender)?;
+    let primary_price_config = PrimaryPriceConfig {
+        price_decimals,
+        max_price_age,
+    };
+    validate_primary_price_config(&primary_price_config)?;
+    PRIMARY_PRICE_CONFIG.save(deps.storage, &primary_price_config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "set_primary_price_config")
+        .add_attribute("price_decimals", price_decimals.to_string())
+        .add_attribute("max_price_age", max_price_age))
+}
+
 pub fn set_max_time_deviation(
     deps: DepsMut,
     sender: Addr,
     max_time_deviation: Uint64,
 ) -> Result<Response, ContractError> {
     is_gov(deps.as_ref(), &sender)?;
-    MAX_TIME_DEVIATION.save(deps.storage, &max_time_deviation.u64())?;
+    MAX_TIME_DEVIATION.save(deps.storage, &max_time_deviation)?;
 
     Ok(Response::new()
         .add_attribute("method", "set_max_time_deviation")
@@ -134,16 +315,9 @@ pub fn set_price_duration(
     price_duration: Uint64,
 ) -> Result<Response, ContractError> {
     is_gov(deps.as_ref(), &sender)?;
-    if price_duration.ge(&MAX_PRICE_DURATION) {
-        return Err(ContractError::InvalidPriceDuration {});
-    }
-
-    CONFIG.update(
+    apply_immediately(
         deps.storage,
-        |mut config| -> Result<Config, ContractError> {
-            config.price_duration = price_duration;
-            Ok(config)
-        },
+        &TimelockAction::SetPriceDuration { price_duration },
     )?;
 
     Ok(Response::new()
@@ -157,11 +331,10 @@ pub fn set_max_price_update_delay(
     max_price_update_delay: Uint64,
 ) -> Result<Response, ContractError> {
     is_gov(deps.as_ref(), &sender)?;
-    CONFIG.update(
+    apply_immediately(
         deps.storage,
-        |mut config| -> Result<Config, ContractError> {
-            config.max_price_update_delay = max_price_update_delay;
-            Ok(config)
+        &TimelockAction::SetMaxPriceUpdateDelay {
+            max_price_update_delay,
         },
     )?;
 
@@ -176,11 +349,12 @@ pub fn set_spread_basis_points_if_inactive(
     spread_basis_points_if_inactive: Uint256,
 ) -> Result<Response, ContractError> {
     is_gov(deps.as_ref(), &sender)?;
-    let mut spread_basis_point = SPREAD_BASIS_POINT_STATE
-        .may_load(deps.storage)?
-        .unwrap_or_default();
-    spread_basis_point.spread_basis_points_if_inactive = spread_basis_points_if_inactive;
-    SPREAD_BASIS_POINT_STATE.save(deps.storage, &spread_basis_point)?;
+    apply_immediately(
+        deps.storage,
+        &TimelockAction::SetSpreadBasisPointsIfInactive {
+            spread_basis_points_if_inactive,
+        },
+    )?;
 
     Ok(Response::new()
         .add_attribute("method", "set_spread_basis_points_if_inactive")
@@ -196,11 +370,12 @@ pub fn set_spread_basis_points_if_chain_error(
     spread_basis_points_if_chain_error: Uint256,
 ) -> Result<Response, ContractError> {
     is_gov(deps.as_ref(), &sender)?;
-    let mut spread_basis_point = SPREAD_BASIS_POINT_STATE
-        .may_load(deps.storage)?
-        .unwrap_or_default();
-    spread_basis_point.spread_basis_points_if_chain_error = spread_basis_points_if_chain_error;
-    SPREAD_BASIS_POINT_STATE.save(deps.storage, &spread_basis_point)?;
+    apply_immediately(
+        deps.storage,
+        &TimelockAction::SetSpreadBasisPointsIfChainError {
+            spread_basis_points_if_chain_error,
+        },
+    )?;
 
     Ok(Response::new()
         .add_attribute("method", "set_spread_basis_points_if_chain_error")
@@ -216,13 +391,9 @@ pub fn set_min_block_interval(
     min_block_interval: Uint64,
 ) -> Result<Response, ContractError> {
     is_gov(deps.as_ref(), &sender)?;
-    CONFIG.update(
-        deps.storage,
-        |mut config| -> Result<Config, ContractError> {
-            config.min_block_interval = min_block_interval;
-            Ok(config)
-        },
-    )?;
+    update_global_state(deps.storage, |global| {
+        global.config.min_block_interval = min_block_interval
+    })?;
 
     Ok(Response::new()
         .add_attribute("method", "set_min_block_interval")
@@ -235,7 +406,10 @@ pub fn set_is_spread_enabled(
     spread_enabled: bool,
 ) -> Result<Response, ContractError> {
     is_gov(deps.as_ref(), &sender)?;
-    SPREAD_ENABLED.save(deps.storage, &spread_enabled)?;
+    apply_immediately(
+        deps.storage,
+        &TimelockAction::SetIsSpreadEnabled { spread_enabled },
+    )?;
 
     Ok(Response::new()
         .add_attribute("method", "set_is_spread_enabled")
@@ -245,47 +419,310 @@ pub fn set_is_spread_enabled(
 pub fn set_last_updated_at(
     deps: DepsMut,
     sender: Addr,
+    token: Option<Addr>,
     last_updated_at: Uint64,
 ) -> Result<Response, ContractError> {
     is_gov(deps.as_ref(), &sender)?;
 
-    let mut last_updated = LAST_UPDATED.may_load(deps.storage)?.unwrap_or_default();
-    last_updated.last_updated_at = last_updated_at.u64();
-    LAST_UPDATED.save(deps.storage, &last_updated)?;
+    match &token {
+        Some(token) => {
+            update_token_state(deps.storage, token, |state| {
+                state.last_updated.last_updated_at = last_updated_at
+            })?;
+        }
+        None => {
+            let mut last_updated = LAST_UPDATED.may_load(deps.storage)?.unwrap_or_default();
+            last_updated.last_updated_at = last_updated_at;
+            LAST_UPDATED.save(deps.storage, &last_updated)?;
+        }
+    }
 
-    Ok(Response::new()
+    let mut response = Response::new()
         .add_attribute("method", "set_last_updated_at")
-        .add_attribute("last_updated_at", last_updated_at))
+        .add_attribute("last_updated_at", last_updated_at);
+    if let Some(token) = token {
+        response = response.add_attribute("token", token);
+    }
+
+    Ok(response)
 }
 
-pub fn set_token_manager(
+pub fn propose_ownership(
     deps: DepsMut,
+    env: Env,
     sender: Addr,
-    token_manager: Addr,
+    role: Role,
+    new_owner: Addr,
+    expires_at: Option<Uint64>,
 ) -> Result<Response, ContractError> {
-    only_token_manager(deps.as_ref(), &sender)?;
+    only_role(deps.as_ref(), role, &sender)?;
+    if expires_at.is_some_and(|expires_at| expires_at.u64() <= env.block.time.seconds()) {
+        return Err(ContractError::InvalidOwnershipTransferExpiry {});
+    }
 
-    TOKEN_MANAGER.save(deps.storage, &token_manager)?;
+    PENDING_OWNERS.save(
+        deps.storage,
+        role.key(),
+        &PendingOwner {
+            proposer: sender,
+            new_owner: new_owner.clone(),
+            expires_at,
+        },
+    )?;
 
     Ok(Response::new()
-        .add_attribute("method", "set_token_manager")
-        .add_attribute("token_manager", token_manager.to_string()))
+        .add_attribute("method", "propose_ownership")
+        .add_attribute("role", role.key())
+        .add_attribute("new_owner", new_owner))
 }
 
-pub fn set_max_deviation_basis_points(
+pub fn accept_ownership(
     deps: DepsMut,
+    env: Env,
     sender: Addr,
-    max_deviation_basis_points: Uint256,
+    role: Role,
 ) -> Result<Response, ContractError> {
-    only_token_manager(deps.as_ref(), &sender)?;
+    let pending_owner = PENDING_OWNERS
+        .may_load(deps.storage, role.key())?
+        .ok_or(ContractError::NoPendingOwner {})?;
+    if pending_owner.new_owner != sender {
+        return Err(ContractError::Forbidden {});
+    }
+    if pending_owner
+        .expires_at
+        .is_some_and(|expires_at| expires_at.u64() <= env.block.time.seconds())
+    {
+        return Err(ContractError::OwnershipTransferExpired {});
+    }
+
+    // the proposer hands its role over to the new owner
+    set_role_member(deps.storage, role, &sender, true)?;
+    if pending_owner.proposer != sender {
+        set_role_member(deps.storage, role, &pending_owner.proposer, false)?;
+    }
+    PENDING_OWNERS.remove(deps.storage, role.key());
+
+    Ok(Response::new()
+        .add_attribute("method", "accept_ownership")
+        .add_attribute("role", role.key())
+        .add_attribute("owner", sender))
+}
+
+pub fn cancel_ownership_transfer(
+    deps: DepsMut,
+    sender: Addr,
+    role: Role,
+) -> Result<Response, ContractError> {
+    only_role(deps.as_ref(), role, &sender)?;
+    if !PENDING_OWNERS.has(deps.storage, role.key()) {
+        return Err(ContractError::NoPendingOwner {});
+    }
+
+    PENDING_OWNERS.remove(deps.storage, role.key());
+
+    Ok(Response::new()
+        .add_attribute("method", "cancel_ownership_transfer")
+        .add_attribute("role", role.key()))
+}
+
+pub fn update_config(
+    deps: DepsMut,
+    sender: Addr,
+    update: ConfigUpdate,
+) -> Result<Response, ContractError> {
+    // the token manager fields are the only ones open to the token manager
+    let gov_update = ConfigUpdate {
+        max_deviation_basis_points: None,
+        price_data_interval: None,
+        ..update.clone()
+    };
+    if gov_update != ConfigUpdate::default() {
+        is_gov(deps.as_ref(), &sender)?;
+    } else if !has_role(deps.storage, Role::Gov, &sender) {
+        only_token_manager(deps.as_ref(), &sender)?;
+    }
+
+    let global = load_global_state(deps.storage)?;
+    let old_config = global.config.clone();
+    let old_spread = global.spread_basis_points.clone();
+    let old_spread_enabled = global.spread_enabled;
+    let old_max_time_deviation = MAX_TIME_DEVIATION
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    let old_price_data_interval = PRICE_DATA_INTERVAL
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+
+    // timelocked parameters follow the same rules as their own setters
+    let timelocked = [
+        update
+            .price_duration
+            .map(|price_duration| TimelockAction::SetPriceDuration { price_duration }),
+        update.max_price_update_delay.map(|max_price_update_delay| {
+            TimelockAction::SetMaxPriceUpdateDelay {
+                max_price_update_delay,
+            }
+        }),
+        update
+            .spread_basis_points_if_inactive
+            .map(|spread_basis_points_if_inactive| {
+                TimelockAction::SetSpreadBasisPointsIfInactive {
+                    spread_basis_points_if_inactive,
+                }
+            }),
+        update
+            .spread_basis_points_if_chain_error
+            .map(|spread_basis_points_if_chain_error| {
+                TimelockAction::SetSpreadBasisPointsIfChainError {
+                    spread_basis_points_if_chain_error,
+                }
+            }),
+        update
+            .spread_enabled
+            .map(|spread_enabled| TimelockAction::SetIsSpreadEnabled { spread_enabled }),
+    ];
+    for action in timelocked.iter().flatten() {
+        check_immediate(deps.storage, action)?;
+    }
+
+    let config = Config {
+        price_duration: update.price_duration.unwrap_or(old_config.price_duration),
+        max_price_update_delay: update
+            .max_price_update_delay
+            .unwrap_or(old_config.max_price_update_delay),
+        min_block_interval: update
+            .min_block_interval
+            .unwrap_or(old_config.min_block_interval),
+        max_deviation_basis_points: update
+            .max_deviation_basis_points
+            .unwrap_or(old_config.max_deviation_basis_points),
+        fast_price_events: update
+            .fast_price_events
+            .clone()
+            .unwrap_or(old_config.fast_price_events.clone()),
+    };
+    let spread = SpreadBasisPoint {
+        spread_basis_points_if_inactive: update
+            .spread_basis_points_if_inactive
+            .unwrap_or(old_spread.spread_basis_points_if_inactive),
+        spread_basis_points_if_chain_error: update
+            .spread_basis_points_if_chain_error
+            .unwrap_or(old_spread.spread_basis_points_if_chain_error),
+    };
+    let spread_enabled = update.spread_enabled.unwrap_or(old_spread_enabled);
+    let max_time_deviation = update.max_time_deviation.unwrap_or(old_max_time_deviation);
+    let price_data_interval = update
+        .price_data_interval
+        .unwrap_or(old_price_data_interval);
+
+    // the rules of `validate_config`, limited to the fields being set
+    if update.price_duration.is_some() || update.max_price_update_delay.is_some() {
+        validate_price_durations(config.price_duration, config.max_price_update_delay)?;
+    }
+    validate_spread_basis_points(&spread)?;
+    if update.price_data_interval.is_some() && price_data_interval.is_zero() {
+        return Err(ContractError::InvalidPriceDataInterval {});
+    }
 
-    CONFIG.update(
+    GLOBAL_STATE.save(
         deps.storage,
-        |mut config| -> Result<Config, ContractError> {
-            config.max_deviation_basis_points = max_deviation_basis_points;
-            Ok(config)
+        &GlobalState {
+            config: config.clone(),
+            spread_basis_points: spread.clone(),
+            spread_enabled,
+            ..global
         },
     )?;
+    MAX_TIME_DEVIATION.save(deps.storage, &max_time_deviation)?;
+    PRICE_DATA_INTERVAL.save(deps.storage, &price_data_interval)?;
+
+    // one event listing the old and new value of every field in the update
+    let changes = [
+        (
+            "price_duration",
+            update.price_duration.is_some(),
+            old_config.price_duration.to_string(),
+            config.price_duration.to_string(),
+        ),
+        (
+            "max_price_update_delay",
+            update.max_price_update_delay.is_some(),
+            old_config.max_price_update_delay.to_string(),
+            config.max_price_update_delay.to_string(),
+        ),
+        (
+            "min_block_interval",
+            update.min_block_interval.is_some(),
+            old_config.min_block_interval.to_string(),
+            config.min_block_interval.to_string(),
+        ),
+        (
+            "max_deviation_basis_points",
+            update.max_deviation_basis_points.is_some(),
+            old_config.max_deviation_basis_points.to_string(),
+            config.max_deviation_basis_points.to_string(),
+        ),
+        (
+            "fast_price_events",
+            update.fast_price_events.is_some(),
+            old_config.fast_price_events.to_string(),
+            config.fast_price_events.to_string(),
+        ),
+        (
+            "spread_basis_points_if_inactive",
+            update.spread_basis_points_if_inactive.is_some(),
+            old_spread.spread_basis_points_if_inactive.to_string(),
+            spread.spread_basis_points_if_inactive.to_string(),
+        ),
+        (
+            "spread_basis_points_if_chain_error",
+            update.spread_basis_points_if_chain_error.is_some(),
+            old_spread.spread_basis_points_if_chain_error.to_string(),
+            spread.spread_basis_points_if_chain_error.to_string(),
+        ),
+        (
+            "spread_enabled",
+            update.spread_enabled.is_some(),
+            old_spread_enabled.to_string(),
+            spread_enabled.to_string(),
+        ),
+        (
+            "max_time_deviation",
+            update.max_time_deviation.is_some(),
+            old_max_time_deviation.to_string(),
+            max_time_deviation.to_string(),
+        ),
+        (
+            "price_data_interval",
+            update.price_data_interval.is_some(),
+            old_price_data_interval.to_string(),
+            price_data_interval.to_string(),
+        ),
+    ];
+    let event = changes
+        .into_iter()
+        .filter(|(_, is_set, _, _)| *is_set)
+        .fold(Event::new("config_updated"), |event, (key, _, old, new)| {
+            event
+                .add_attribute(format!("old_{key}"), old)
+                .add_attribute(format!("new_{key}"), new)
+        });
+
+    Ok(Response::new()
+        .add_event(event)
+        .add_attribute("method", "update_config"))
+}
+
+pub fn set_max_deviation_basis_points(
+    deps: DepsMut,
+    sender: Addr,
+    max_deviation_basis_points: Uint256,
+) -> Result<Response, ContractError> {
+    only_token_manager(deps.as_ref(), &sender)?;
+
+    update_global_state(deps.storage, |global| {
+        global.config.max_deviation_basis_points = max_deviation_basis_points
+    })?;
 
     Ok(Response::new()
         .add_attribute("method", "set_max_deviation_basis_points")
@@ -302,11 +739,15 @@ pub fn set_max_cumulative_delta_diffs(
     max_cumulative_delta_diffs: Vec<Uint256>,
 ) -> Result<Response, ContractError> {
     only_token_manager(deps.as_ref(), &sender)?;
+    if tokens.len() != max_cumulative_delta_diffs.len() {
+        return Err(ContractError::InvalidLength {});
+    }
 
     // Iterate and update the values
-    for (i, token) in tokens.iter().enumerate() {
-        let diff = max_cumulative_delta_diffs[i];
-        MAX_CUMULATIVE_DELTA_DIFFS.save(deps.storage, token, &diff)?;
+    for (token, diff) in tokens.iter().zip(max_cumulative_delta_diffs) {
+        update_token_state(deps.storage, token, |state| {
+            state.max_cumulative_delta_diff = diff
+        })?;
     }
 
     Ok(Response::new()
@@ -314,12 +755,35 @@ pub fn set_max_cumulative_delta_diffs(
         .add_attribute("num_tokens_updated", tokens.len().to_string()))
 }
 
+pub fn set_token_params(
+    deps: DepsMut,
+    sender: Addr,
+    token: Addr,
+    params: TokenParams,
+) -> Result<Response, ContractError> {
+    only_token_manager(deps.as_ref(), &sender)?;
+    apply_immediately(
+        deps.storage,
+        &TimelockAction::SetTokenParams {
+            token: token.clone(),
+            params,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("method", "set_token_params")
+        .add_attribute("token", token))
+}
+
 pub fn set_price_data_interval(
     deps: DepsMut,
     sender: Addr,
     price_data_interval: Uint64,
 ) -> Result<Response, ContractError> {
     only_token_manager(deps.as_ref(), &sender)?;
+    if price_data_interval.is_zero() {
+        return Err(ContractError::InvalidPriceDataInterval {});
+    }
 
     PRICE_DATA_INTERVAL.save(deps.storage, &price_data_interval)?;
 
@@ -335,31 +799,78 @@ pub fn set_min_authorizations(
 ) -> Result<Response, ContractError> {
     only_token_manager(deps.as_ref(), &sender)?;
 
-    MIN_AUTH.save(deps.storage, &min_authorizations)?;
+    let signer_count = role_member_count(deps.storage, Role::Signer)?;
+    validate_min_authorizations(min_authorizations, signer_count)?;
+    update_global_state(deps.storage, |global| {
+        global.min_authorizations = Some(min_authorizations)
+    })?;
 
     Ok(Response::new()
         .add_attribute("method", "set_min_authorizations")
         .add_attribute("min_authorizations", min_authorizations.to_string()))
 }
 
-pub fn set_tokens(
+pub fn add_token(
     deps: DepsMut,
-    _env: Env,
     sender: Addr,
-    tokens: Vec<Addr>,
-    token_precision: Vec<Uint256>,
+    token: Addr,
+    token_precision: Uint256,
 ) -> Result<Response, ContractError> {
-    only_updater(deps.as_ref(), &sender)?;
-    if tokens.len() != token_precision.len() {
-        return Err(ContractError::InvalidLength {});
+    only_token_manager(deps.as_ref(), &sender)?;
+    if token_precision.is_zero() {
+        return Err(ContractError::InvalidTokenPrecision {});
     }
-    let mut token_data: Vec<TokenData> = Vec::with_capacity(tokens.len());
-    for (i, token) in tokens.iter().enumerate() {
-        token_data[i] = TokenData::new(token.clone(), token_precision[i]);
+    if TOKENS.has(deps.storage, &token) {
+        return Err(ContractError::TokenAlreadyExists {
+            token: token.to_string(),
+        });
+    }
+
+    let index = register_token(deps.storage, token.clone(), token_precision)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "add_token")
+        .add_attribute("token", token)
+        .add_attribute("token_precision", token_precision)
+        .add_attribute("index", index.to_string()))
+}
+
+pub fn remove_token(deps: DepsMut, sender: Addr, token: Addr) -> Result<Response, ContractError> {
+    only_token_manager(deps.as_ref(), &sender)?;
+    let token_data = load_token(deps.storage, &token)?;
+
+    // the index is not reused so keepers never write into a delisted slot
+    TOKENS.remove(deps.storage, &token);
+    TOKEN_INDEXES.remove(deps.storage, token_data.index);
+    TOKEN_STATES.remove(deps.storage, &token);
+    clear_circuit_breaker_reset_votes(deps.storage, &token)?;
+    clear_token_disable_votes(deps.storage, &token)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "remove_token")
+        .add_attribute("token", token)
+        .add_attribute("index", token_data.index.to_string()))
+}
+
+pub fn update_token_precision(
+    deps: DepsMut,
+    sender: Addr,
+    token: Addr,
+    token_precision: Uint256,
+) -> Result<Response, ContractError> {
+    only_token_manager(deps.as_ref(), &sender)?;
+    if token_precision.is_zero() {
+        return Err(ContractError::InvalidTokenPrecision {});
     }
 
-    TOKEN_DATA.save(deps.storage, &token_data)?;
-    Ok(Response::new().add_attribute("method", "set_tokens"))
+    let mut token_data = load_token(deps.storage, &token)?;
+    token_data.token_precision = token_precision;
+    TOKENS.save(deps.storage, &token, &token_data)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "update_token_precision")
+        .add_attribute("token", token)
+        .add_attribute("token_precision", token_precision))
 }
 
 pub fn set_prices(
@@ -371,73 +882,350 @@ pub fn set_prices(
     timestamp: Uint64,
 ) -> Result<Response, ContractError> {
     only_updater(deps.as_ref(), &sender)?;
+    when_not_paused(deps.storage)?;
+    let response = _set_prices(deps, env, &tokens, &prices, timestamp)?;
 
-    let should_update = set_last_updated_values(deps.storage, &env.block, timestamp.u64())?;
-    let vault_address = VAULT_ADDRESS.load(deps.storage)?;
-    let fast_price_event = CONFIG.load(deps.storage)?.fast_price_events;
-    if should_update {
-        for (i, token) in tokens.iter().enumerate() {
-            set_price(
-                deps.storage,
-                Uint64::from(env.block.time.seconds()),
-                token,
-                prices[i],
-                &vault_address,
-                &fast_price_event,
-            )?;
+    Ok(response.add_attribute("method", "set_prices"))
+}
+
+pub fn set_prices_signed(
+    deps: DepsMut,
+    env: Env,
+    tokens: Vec<Addr>,
+    prices: Vec<Uint256>,
+    timestamp: Uint64,
+    signatures: Vec<PriceSignature>,
+) -> Result<Response, ContractError> {
+    when_not_paused(deps.storage)?;
+    if signatures.is_empty() {
+        return Err(ContractError::MissingSignatures {});
+    }
+    if tokens.len() != prices.len() {
+        return Err(ContractError::InvalidLength {});
+    }
+
+    let mut signers: Vec<&[u8]> = Vec::with_capacity(signatures.len());
+    for PriceSignature {
+        pubkey,
+        signature,
+        nonce,
+    } in signatures.iter()
+    {
+        let pubkey = pubkey.as_slice();
+        if signers.contains(&pubkey) {
+            return Err(ContractError::DuplicatePubkey {
+                pubkey: Binary::from(pubkey).to_base64(),
+            });
+        }
+        signers.push(pubkey);
+
+        let pubkey_type = UPDATER_PUBKEYS.may_load(deps.storage, pubkey)?.ok_or(
+            ContractError::UnknownPubkey {
+                pubkey: Binary::from(pubkey).to_base64(),
+            },
+        )?;
+
+        // nonces only move forward so a signature can be used once
+        let last_nonce = UPDATER_NONCES
+            .may_load(deps.storage, pubkey)?
+            .unwrap_or_default();
+        if nonce.u64() <= last_nonce {
+            return Err(ContractError::InvalidNonce { last_nonce });
+        }
+
+        let hash = price_payload_hash(
+            &env.block.chain_id,
+            &env.contract.address,
+            nonce.u64(),
+            timestamp.u64(),
+            &tokens,
+            &prices,
+        );
+        let is_valid = match pubkey_type {
+            PubkeyType::Secp256k1 => deps
+                .api
+                .secp256k1_verify(&hash, signature, pubkey)
+                .map_err(StdError::from)?,
+            PubkeyType::Ed25519 => deps
+                .api
+                .ed25519_verify(&hash, signature, pubkey)
+                .map_err(StdError::from)?,
+        };
+        if !is_valid {
+            return Err(ContractError::InvalidSignature {
+                pubkey: Binary::from(pubkey).to_base64(),
+            });
         }
+
+        UPDATER_NONCES.save(deps.storage, pubkey, &nonce.u64())?;
     }
 
-    Ok(Response::new().add_attribute("method", "set_prices"))
+    let response = _set_prices(deps, env, &tokens, &prices, timestamp)?;
+
+    Ok(response
+        .add_attribute("method", "set_prices_signed")
+        .add_attribute("signatures", signatures.len().to_string()))
 }
 
-pub fn set_compacted_prices(
+fn _set_prices(
     deps: DepsMut,
     env: Env,
-    price_bit_array: Vec<Uint256>,
+    tokens: &[Addr],
+    prices: &[Uint256],
     timestamp: Uint64,
 ) -> Result<Response, ContractError> {
-    let should_update = set_last_updated_values(deps.storage, &env.block, timestamp.u64())?;
-    let vault_address = VAULT_ADDRESS.load(deps.storage)?;
-    let fast_price_event = CONFIG.load(deps.storage)?.fast_price_events;
-    if should_update {
-        let tokens = TOKEN_DATA.load(deps.storage)?;
-        for (i, &price_bits) in price_bit_array.iter().enumerate() {
-            let prices = price_bits.to_le_bytes();
-            for j in 0..4 {
-                let index = i * 4 + j;
-                if index >= TOKEN_DATA.load(deps.storage)?.len() {
-                    return Ok(Response::new());
-                }
+    if tokens.len() != prices.len() {
+        return Err(ContractError::InvalidLength {});
+    }
+    // unregistered and delisted tokens have no slot to write into
+    for token in tokens {
+        load_token(deps.storage, token)?;
+    }
 
-                let token = &tokens[index];
-                let price = Uint256::from(u64::from_le_bytes(
-                    prices[j * 8..j * 8 + 8].try_into().unwrap(),
-                ));
-                let adjusted_price = price.multiply_ratio(PRICE_PRECISION, token.token_precision);
-                set_price(
-                    deps.storage,
-                    Uint64::from(env.block.time.seconds()),
-                    &token.token,
-                    adjusted_price,
-                    &vault_address,
-                    &fast_price_event,
-                )?;
+    let Some(last_updated) = set_last_updated_values(deps.storage, &env.block, timestamp)? else {
+        return Ok(Response::new());
+    };
+    let mut batch = PriceBatch::load(
+        deps.storage,
+        deps.querier,
+        Uint64::from(env.block.time.seconds()),
+    )?;
+    for (token, price) in tokens.iter().zip(prices) {
+        batch.set_price(deps.storage, token, *price, &last_updated)?;
+    }
+
+    batch.commit(deps.storage)
+}
+
+pub fn set_quorum_config(
+    deps: DepsMut,
+    sender: Addr,
+    quorum_config: QuorumConfig,
+) -> Result<Response, ContractError> {
+    is_gov(deps.as_ref(), &sender)?;
+    if quorum_config.min_observations == Some(0) {
+        return Err(ContractError::InvalidMinObservations {});
+    }
+    // rounds have to be able to commit with the current updaters
+    if quorum_config.enabled {
+        let min_observations = quorum_min_observations(deps.storage, &quorum_config)?;
+        if min_observations > Uint256::from(role_member_count(deps.storage, Role::Updater)?) {
+            return Err(ContractError::InsufficientUpdaters { min_observations });
+        }
+    }
+
+    QUORUM_CONFIG.save(deps.storage, &quorum_config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "set_quorum_config")
+        .add_attribute("enabled", quorum_config.enabled.to_string()))
+}
+
+pub fn set_circuit_breaker_config(
+    deps: DepsMut,
+    sender: Addr,
+    circuit_breaker_config: CircuitBreakerConfig,
+) -> Result<Response, ContractError> {
+    is_gov(deps.as_ref(), &sender)?;
+    if circuit_breaker_config.max_consecutive_deviations == 0 {
+        return Err(ContractError::InvalidCircuitBreakerConfig {});
+    }
+
+    CIRCUIT_BREAKER_CONFIG.save(deps.storage, &circuit_breaker_config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "set_circuit_breaker_config")
+        .add_attribute("enabled", circuit_breaker_config.enabled.to_string()))
+}
+
+pub fn reset_circuit_breaker(
+    deps: DepsMut,
+    sender: Addr,
+    token: Addr,
+) -> Result<Response, ContractError> {
+    let is_gov = is_gov(deps.as_ref(), &sender).is_ok();
+    if !is_gov {
+        only_signer(deps.as_ref(), &sender)?;
+    }
+
+    let mut breaker = load_token_state(deps.storage, &token)?.circuit_breaker;
+    if !breaker.tripped {
+        return Err(ContractError::CircuitBreakerNotTripped {});
+    }
+
+    let response = Response::new()
+        .add_attribute("method", "reset_circuit_breaker")
+        .add_attribute("token", &token)
+        .add_attribute("sender", &sender);
+
+    if !is_gov {
+        if CIRCUIT_BREAKER_RESET_VOTES.has(deps.storage, (&token, &sender)) {
+            return Err(ContractError::AlreadyVoted {});
+        }
+        CIRCUIT_BREAKER_RESET_VOTES.save(deps.storage, (&token, &sender), &true)?;
+        breaker.reset_votes += 1;
+
+        let min_authorizations = load_min_authorizations(deps.storage)?;
+        if Uint256::from(breaker.reset_votes) < min_authorizations {
+            update_token_state(deps.storage, &token, |state| {
+                state.circuit_breaker = breaker.clone()
+            })?;
+            return Ok(response.add_attribute("reset_votes", breaker.reset_votes.to_string()));
+        }
+    }
+
+    clear_circuit_breaker_reset_votes(deps.storage, &token)?;
+    update_token_state(deps.storage, &token, |state| {
+        state.circuit_breaker = CircuitBreaker::default()
+    })?;
+
+    Ok(response.add_event(
+        Event::new("circuit_breaker_reset")
+            .add_attribute("token", &token)
+            .add_attribute("tripped_at", breaker.tripped_at)
+            .add_attribute("by_gov", is_gov.to_string()),
+    ))
+}
+
+pub fn submit_observations(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    tokens: Vec<Addr>,
+    prices: Vec<Uint256>,
+    timestamp: Uint64,
+) -> Result<Response, ContractError> {
+    only_updater(deps.as_ref(), &sender)?;
+    when_not_paused(deps.storage)?;
+    if tokens.len() != prices.len() {
+        return Err(ContractError::InvalidLength {});
+    }
+    for token in tokens.iter() {
+        load_token(deps.storage, token)?;
+    }
+
+    let quorum_config = QUORUM_CONFIG.may_load(deps.storage)?.unwrap_or_default();
+    if !quorum_config.enabled {
+        return Err(ContractError::QuorumModeDisabled {});
+    }
+    check_timestamp_bounds(deps.storage, &env.block, timestamp)?;
+
+    let min_observations = quorum_min_observations(deps.storage, &quorum_config)?;
+    let min_block_interval = load_global_state(deps.storage)?.config.min_block_interval;
+    let now = Uint64::from(env.block.time.seconds());
+    let mut batch = PriceBatch::load(deps.storage, deps.querier, now)?;
+
+    let mut events = vec![];
+    for (token, price) in tokens.iter().zip(prices) {
+        // committed prices move forward in time and respect the min block interval
+        let last_updated = load_token_state(deps.storage, token)?.last_updated;
+        if timestamp < last_updated.last_updated_at {
+            return Err(ContractError::ObservationTooOld {});
+        }
+        check_min_block_interval(
+            min_block_interval,
+            &env.block,
+            last_updated.last_updated_block,
+        )?;
+
+        let mut round = match PENDING_ROUNDS.may_load(deps.storage, token)? {
+            // stale rounds are dropped instead of mixing old and new observations
+            Some(round) if round.started_at.checked_add(quorum_config.round_duration)? >= now => {
+                round
             }
+            _ => PendingRound {
+                started_at: now,
+                observations: vec![],
+            },
+        };
+
+        // a new submission replaces the updater's previous observation
+        round
+            .observations
+            .retain(|observation| observation.updater != sender);
+        round.observations.push(Observation {
+            updater: sender.clone(),
+            price,
+            timestamp,
+        });
+
+        if Uint256::from(round.observations.len() as u64) < min_observations {
+            PENDING_ROUNDS.save(deps.storage, token, &round)?;
+            continue;
         }
+
+        let observed_prices: Vec<Uint256> = round
+            .observations
+            .iter()
+            .map(|observation| observation.price)
+            .collect();
+        let median = median_price(observed_prices);
+        let last_updated_at = round
+            .observations
+            .iter()
+            .map(|observation| observation.timestamp)
+            .max()
+            .unwrap_or_default();
+        batch.set_price(
+            deps.storage,
+            token,
+            median,
+            &LastUpdated {
+                last_updated_at,
+                last_updated_block: env.block.height,
+            },
+        )?;
+        PENDING_ROUNDS.remove(deps.storage, token);
+
+        let mut event = Event::new("quorum_price_committed")
+            .add_attribute("token", token)
+            .add_attribute("price", median)
+            .add_attribute("observations", round.observations.len().to_string());
+        for observation in round.observations.iter() {
+            // a deviation too large to compute is an outlier as well
+            if deviation_basis_points(observation.price, median).map_or(true, |deviation| {
+                deviation > quorum_config.outlier_basis_points
+            }) {
+                event = event.add_attribute("outlier", &observation.updater);
+            }
+        }
+        events.push(event);
     }
 
-    Ok(Response::new().add_attribute("method", "set_compacted_prices"))
+    Ok(batch
+        .commit(deps.storage)?
+        .add_events(events)
+        .add_attribute("method", "submit_observations")
+        .add_attribute("updater", sender))
+}
+
+pub fn set_compacted_prices(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    price_bit_array: Vec<Uint256>,
+    timestamp: Uint64,
+    layout: Option<PriceBitLayout>,
+) -> Result<Response, ContractError> {
+    only_updater(deps.as_ref(), &sender)?;
+    when_not_paused(deps.storage)?;
+    let response = _set_prices_with_bits(deps, env, &price_bit_array, timestamp, layout)?;
+
+    Ok(response.add_attribute("method", "set_compacted_prices"))
 }
 
 pub fn set_prices_with_bits(
     deps: DepsMut,
     env: Env,
+    sender: Addr,
     price_bits: Uint256,
     timestamp: Uint64,
+    layout: Option<PriceBitLayout>,
 ) -> Result<Response, ContractError> {
-    _set_prices_with_bits(deps, env, price_bits, timestamp)?;
-    Ok(Response::new().add_attribute("method", "set_prices_with_bits"))
+    only_updater(deps.as_ref(), &sender)?;
+    when_not_paused(deps.storage)?;
+    let response = _set_prices_with_bits(deps, env, &[price_bits], timestamp, layout)?;
+    Ok(response.add_attribute("method", "set_prices_with_bits"))
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -452,257 +1240,582 @@ pub fn set_prices_with_bits_and_execute(
     end_index_for_decrease_positions: Uint256,
     max_increase_positions: Uint256,
     max_decrease_positions: Uint256,
+    layout: Option<PriceBitLayout>,
 ) -> Result<Response, ContractError> {
     only_updater(deps.as_ref(), &sender)?;
-    _set_prices_with_bits(deps, env, price_bits, timestamp)?;
-
-    let position_router = load_position_router(&position_router_addr);
-    let max_end_index_for_increase =
-        position_router.increase_position_request_keys_start + max_increase_positions;
-    let max_end_index_for_decrease =
-        position_router.decrease_position_request_keys_start + max_decrease_positions;
-
-    let _adjusted_end_index_for_increase_positions =
+    when_not_paused(deps.storage)?;
+    only_position_router(deps.as_ref(), &position_router_addr)?;
+    let position_router = load_position_router(&deps.querier, &position_router_addr)?;
+    let response = _set_prices_with_bits(deps, env, &[price_bits], timestamp, layout)?;
+
+    let max_end_index_for_increase = position_router
+        .increase_position_request_keys_start
+        .checked_add(max_increase_positions)?;
+    let max_end_index_for_decrease = position_router
+        .decrease_position_request_keys_start
+        .checked_add(max_decrease_positions)?;
+
+    let adjusted_end_index_for_increase_positions =
         if end_index_for_increase_positions > max_end_index_for_increase {
             max_end_index_for_increase
         } else {
             end_index_for_increase_positions
         };
 
-    let _adjusted_end_index_for_decrease_positions =
+    let adjusted_end_index_for_decrease_positions =
         if end_index_for_decrease_positions > max_end_index_for_decrease {
             max_end_index_for_decrease
         } else {
             end_index_for_decrease_positions
         };
 
-    //TODO: change msg for interface
     let execute_increase_positions_msg = CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: position_router_addr.to_string().clone(),
-        msg: to_binary("sample msg for the interface")?,
+        contract_addr: position_router_addr.to_string(),
+        msg: to_binary(&PositionRouterExecuteMsg::ExecuteIncreasePositions {
+            end_index: adjusted_end_index_for_increase_positions,
+            execution_fee_receiver: sender.clone(),
+        })?,
         funds: vec![],
     });
 
     let execute_decrease_positions_msg = CosmosMsg::Wasm(WasmMsg::Execute {
         contract_addr: position_router_addr.to_string(),
-        msg: to_binary("smaple msg required")?,
+        msg: to_binary(&PositionRouterExecuteMsg::ExecuteDecreasePositions {
+            end_index: adjusted_end_index_for_decrease_positions,
+            execution_fee_receiver: sender,
+        })?,
         funds: vec![],
     });
 
-    Ok(Response::new()
+    Ok(response
         .add_attribute("method", "set_prices_with_bits_and_execute")
+        .add_attribute("position_router", position_router_addr)
+        .add_attribute(
+            "end_index_for_increase_positions",
+            adjusted_end_index_for_increase_positions,
+        )
+        .add_attribute(
+            "end_index_for_decrease_positions",
+            adjusted_end_index_for_decrease_positions,
+        )
         .add_message(execute_increase_positions_msg)
         .add_message(execute_decrease_positions_msg))
 }
 
 pub fn disable_fast_price(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     sender: Addr,
+    token: Option<Addr>,
+    expires_at: Option<Uint64>,
 ) -> Result<Response, ContractError> {
     only_signer(deps.as_ref(), &sender)?;
 
-    // Check if the signer has already voted
-    if DISABLE_FAST_PRICE_VOTES
-        .may_load(deps.storage, &sender)?
-        .unwrap_or(false)
-    {
-        return Err(ContractError::AlreadyVoted {});
+    let now = Uint64::from(env.block.time.seconds());
+    if expires_at.is_some_and(|expires_at| expires_at <= now) {
+        return Err(ContractError::InvalidVoteExpiry {});
     }
 
-    DISABLE_FAST_PRICE_VOTES.save(deps.storage, &sender, &true)?;
-    let current_vote_count = DISABLE_FAST_PRICE_VOTE_COUNT
-        .load(deps.storage)
+    let epoch = DISABLE_VOTE_EPOCH
+        .may_load(deps.storage)?
         .unwrap_or_default();
+    let vote = DisableVote {
+        epoch,
+        voted_at: now,
+        expires_at,
+    };
 
-    DISABLE_FAST_PRICE_VOTE_COUNT.save(
-        deps.storage,
-        &(current_vote_count.checked_add(Uint256::one()).unwrap()),
-    )?;
-
-    Ok(Response::new()
+    let mut response = Response::new()
         .add_attribute("method", "disable_fast_price")
-        .add_attribute("sender", sender))
+        .add_attribute("sender", &sender);
+
+    match token {
+        Some(token) => {
+            load_token(deps.storage, &token)?;
+
+            // Check if the signer has already voted
+            if TOKEN_DISABLE_FAST_PRICE_VOTES
+                .may_load(deps.storage, (&token, &sender))?
+                .is_some_and(|vote| vote.is_active(epoch, now))
+            {
+                return Err(ContractError::AlreadyVoted {});
+            }
+
+            TOKEN_DISABLE_FAST_PRICE_VOTES.save(deps.storage, (&token, &sender), &vote)?;
+            update_token_state(deps.storage, &token, |state| {
+                state.disable_votes.add(expires_at, now)
+            })?;
+            response = response.add_attribute("token", token);
+        }
+        None => {
+            // Check if the signer has already voted
+            if DISABLE_FAST_PRICE_VOTES
+                .may_load(deps.storage, &sender)?
+                .is_some_and(|vote| vote.is_active(epoch, now))
+            {
+                return Err(ContractError::AlreadyVoted {});
+            }
+
+            DISABLE_FAST_PRICE_VOTES.save(deps.storage, &sender, &vote)?;
+            update_global_state(deps.storage, |global| {
+                global.disable_votes.add(expires_at, now)
+            })?;
+        }
+    }
+
+    Ok(response)
 }
 
 pub fn enable_fast_price(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     sender: Addr,
+    token: Option<Addr>,
 ) -> Result<Response, ContractError> {
     only_signer(deps.as_ref(), &sender)?;
 
-    // Check if the signer has already voted
-    if !DISABLE_FAST_PRICE_VOTES
-        .may_load(deps.storage, &sender)?
-        .unwrap_or(false)
-    {
-        return Err(ContractError::AlreadyEnabled {});
+    let now = Uint64::from(env.block.time.seconds());
+    let epoch = DISABLE_VOTE_EPOCH
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+
+    let mut response = Response::new()
+        .add_attribute("method", "enable_fast_price")
+        .add_attribute("sender", &sender);
+
+    match token {
+        Some(token) => {
+            // Check if the signer has already voted
+            let vote = TOKEN_DISABLE_FAST_PRICE_VOTES
+                .may_load(deps.storage, (&token, &sender))?
+                .filter(|vote| vote.is_active(epoch, now))
+                .ok_or(ContractError::AlreadyEnabled {})?;
+
+            TOKEN_DISABLE_FAST_PRICE_VOTES.remove(deps.storage, (&token, &sender));
+            update_token_state(deps.storage, &token, |state| {
+                state.disable_votes.remove(vote.expires_at)
+            })?;
+            response = response.add_attribute("token", token);
+        }
+        None => {
+            // Check if the signer has already voted
+            let vote = DISABLE_FAST_PRICE_VOTES
+                .may_load(deps.storage, &sender)?
+                .filter(|vote| vote.is_active(epoch, now))
+                .ok_or(ContractError::AlreadyEnabled {})?;
+
+            DISABLE_FAST_PRICE_VOTES.remove(deps.storage, &sender);
+            update_global_state(deps.storage, |global| {
+                global.disable_votes.remove(vote.expires_at)
+            })?;
+        }
     }
 
-    DISABLE_FAST_PRICE_VOTES.save(deps.storage, &sender, &false)?;
-    let current_vote_count = DISABLE_FAST_PRICE_VOTE_COUNT
-        .load(deps.storage)
-        .unwrap_or_default();
+    Ok(response)
+}
 
-    DISABLE_FAST_PRICE_VOTE_COUNT.save(
-        deps.storage,
-        &(current_vote_count.checked_sub(Uint256::one()).unwrap()),
-    )?;
+/// Voids every disable vote cast so far
+pub fn start_disable_vote_epoch(deps: DepsMut, sender: Addr) -> Result<Response, ContractError> {
+    is_gov(deps.as_ref(), &sender)?;
+
+    let epoch = DISABLE_VOTE_EPOCH
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        + 1;
+    DISABLE_VOTE_EPOCH.save(deps.storage, &epoch)?;
+
+    // the tallies only hold votes of the current epoch
+    update_global_state(deps.storage, |global| {
+        global.disable_votes = DisableVoteTally::default()
+    })?;
+    let tokens = TOKENS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for token in tokens.iter() {
+        let mut state = load_token_state(deps.storage, token)?;
+        if !state.disable_votes.expiries.is_empty() {
+            state.disable_votes = DisableVoteTally::default();
+            TOKEN_STATES.save(deps.storage, token, &state)?;
+        }
+    }
 
     Ok(Response::new()
-        .add_attribute("method", "enable_fast_price")
-        .add_attribute("sender", sender))
+        .add_attribute("method", "start_disable_vote_epoch")
+        .add_attribute("epoch", epoch.to_string()))
 }
 
-fn set_price(
-    store: &mut dyn Storage,
+// values shared by every price of a batch update, loaded once per batch
+struct PriceBatch<'a> {
+    querier: QuerierWrapper<'a>,
     block_timestamp: Uint64,
-    token: &Addr,
-    _price: Uint256,
-    vault_price_feed: &Addr,
-    _fast_price_events: &Addr,
-) -> Result<Response, ContractError> {
-    // Assuming we have an equivalent query for getLatestPrimaryPrice
-    let ref_price = get_latest_primary_price(vault_price_feed, token)?;
-
-    let fast_price = PRICES.load(store, token).unwrap_or_default();
-    let price_data_interval = PRICE_DATA_INTERVAL.load(store)?;
-
-    let PriceDataItem {
-        ref_price: prev_ref_price,
-        ref_time,
-        mut cumulative_ref_delta,
-        mut cumulative_fast_delta,
-    } = PRICE_DATA.load(store, token).unwrap_or_default();
-
-    if !ref_price.is_zero() {
-        let (ref_delta_amount, fast_delta_amount) = if prev_ref_price > Uint256::zero() {
-            let ref_delta_amount = if ref_price > prev_ref_price {
-                ref_price.checked_sub(prev_ref_price).unwrap()
-            } else {
-                prev_ref_price.checked_sub(ref_price).unwrap()
-            };
+    primary_price_config: PrimaryPriceConfig,
+    default_price_feed: Option<Addr>,
+    price_data_interval: Uint64,
+    fast_price_events: Addr,
+    circuit_breaker_config: CircuitBreakerConfig,
+    // written once the whole batch is applied
+    states: BTreeMap<Addr, TokenState>,
+    prices: Vec<TokenPrice>,
+    events: Vec<Event>,
+}
 
-            let fast_delta_amount = if fast_price > _price {
-                fast_price.checked_sub(_price).unwrap()
-            } else {
-                _price.checked_sub(fast_price).unwrap()
-            };
+impl<'a> PriceBatch<'a> {
+    fn load(
+        store: &dyn Storage,
+        querier: QuerierWrapper<'a>,
+        block_timestamp: Uint64,
+    ) -> Result<Self, ContractError> {
+        Ok(Self {
+            querier,
+            block_timestamp,
+            primary_price_config: PRIMARY_PRICE_CONFIG.may_load(store)?.unwrap_or_default(),
+            default_price_feed: VAULT_ADDRESS.may_load(store)?,
+            price_data_interval: load_item(store, &PRICE_DATA_INTERVAL)?,
+            fast_price_events: load_global_state(store)?.config.fast_price_events,
+            circuit_breaker_config: CIRCUIT_BREAKER_CONFIG.may_load(store)?.unwrap_or_default(),
+            states: BTreeMap::new(),
+            prices: vec![],
+            events: vec![],
+        })
+    }
 
-            (ref_delta_amount, fast_delta_amount)
-        } else {
-            (Uint256::zero(), Uint256::zero())
+    fn set_price(
+        &mut self,
+        store: &dyn Storage,
+        token: &Addr,
+        _price: Uint256,
+        last_updated: &LastUpdated,
+    ) -> Result<(), ContractError> {
+        // each token is compared against its own feed or the vault price feed, without a
+        // feed or with a stale round the price is applied without reference data
+        let price_feed = match PRIMARY_PRICE_FEEDS.may_load(store, token)? {
+            Some(price_feed) => Some(price_feed),
+            None => self.default_price_feed.clone(),
         };
+        let ref_price = match price_feed {
+            Some(price_feed) => match get_latest_primary_price(
+                &self.querier,
+                &price_feed,
+                &self.primary_price_config,
+                self.block_timestamp,
+            ) {
+                Ok(ref_price) => Some(ref_price),
+                Err(ContractError::PrimaryPriceStale { updated_at }) => {
+                    self.events.push(
+                        Event::new("primary_price_stale")
+                            .add_attribute("token", token)
+                            .add_attribute("price_feed", price_feed)
+                            .add_attribute("updated_at", updated_at),
+                    );
+                    None
+                }
+                Err(err) => return Err(err),
+            },
+            None => None,
+        };
+        let block_timestamp = self.block_timestamp;
+        let price_data_interval = self.price_data_interval;
 
-        if ref_time.checked_div(price_data_interval).unwrap()
-            != block_timestamp.checked_div(price_data_interval).unwrap()
-        {
-            cumulative_ref_delta = Uint256::zero();
-            cumulative_fast_delta = Uint256::zero();
+        let mut state = match self.states.remove(token) {
+            Some(state) => state,
+            None => load_token_state(store, token)?,
+        };
+        let fast_price = state.price;
+
+        if let Some(ref_price) = ref_price {
+            let PriceDataItem {
+                ref_price: prev_ref_price,
+                ref_time,
+                mut cumulative_ref_delta,
+                mut cumulative_fast_delta,
+            } = state.price_data;
+
+            if !ref_price.is_zero() && prev_ref_price > Uint256::zero() {
+                let ref_delta_amount = if ref_price > prev_ref_price {
+                    ref_price.checked_sub(prev_ref_price)?
+                } else {
+                    prev_ref_price.checked_sub(ref_price)?
+                };
+
+                let fast_delta_amount = if fast_price > _price {
+                    fast_price.checked_sub(_price)?
+                } else {
+                    _price.checked_sub(fast_price)?
+                };
+
+                // reset cumulative delta values if it is a new time window
+                if window(ref_time, price_data_interval)?
+                    != window(block_timestamp, price_data_interval)?
+                {
+                    cumulative_ref_delta = Uint256::zero();
+                    cumulative_fast_delta = Uint256::zero();
+                }
+
+                cumulative_ref_delta = cumulative_ref_delta.checked_add(
+                    ref_delta_amount
+                        .checked_mul(CUMULATIVE_DELTA_PRECISION)?
+                        .checked_div(prev_ref_price)?,
+                )?;
+                // without a previous fast price there is no fast delta to accumulate
+                if !fast_price.is_zero() {
+                    cumulative_fast_delta = cumulative_fast_delta.checked_add(
+                        fast_delta_amount
+                            .checked_mul(CUMULATIVE_DELTA_PRECISION)?
+                            .checked_div(fast_price)?,
+                    )?;
+                }
+            }
+
+            state.price_data = PriceDataItem {
+                ref_price,
+                ref_time: block_timestamp,
+                cumulative_ref_delta,
+                cumulative_fast_delta,
+            };
+        }
+        state.price = _price;
+        state.last_updated = last_updated.clone();
+
+        if self.circuit_breaker_config.enabled {
+            if let Some(event) = update_circuit_breaker(
+                &self.circuit_breaker_config,
+                &mut state.circuit_breaker,
+                block_timestamp,
+                token,
+                _price,
+                &state.price_data,
+            )? {
+                self.events.push(event);
+            }
         }
 
-        cumulative_ref_delta = cumulative_ref_delta
-            .checked_add(
-                ref_delta_amount
-                    .checked_mul(CUMULATIVE_DELTA_PRECISION)
-                    .unwrap()
-                    .checked_div(prev_ref_price)
-                    .unwrap(),
-            )
-            .unwrap();
-        cumulative_fast_delta = cumulative_fast_delta
-            .checked_add(
-                fast_delta_amount
-                    .checked_mul(CUMULATIVE_DELTA_PRECISION)
-                    .unwrap()
-                    .checked_div(fast_price)
-                    .unwrap(),
-            )
-            .unwrap();
-    }
-
-    let new_price_data_item = PriceDataItem {
-        ref_price,
-        ref_time: block_timestamp,
-        cumulative_ref_delta,
-        cumulative_fast_delta,
-    };
+        self.states.insert(token.clone(), state);
+        self.prices.push(TokenPrice {
+            token: token.clone(),
+            price: _price,
+        });
 
-    PRICE_DATA.save(store, token, &new_price_data_item)?;
-    PRICES.save(store, token, &_price)?;
+        Ok(())
+    }
 
-    //TODO: Call the emit price event function...
-    let msg = emit_price_event(_fast_price_events, token, Uint256::one())?;
+    // writes every touched token once and reports the batch with one message and one event
+    fn commit(self, store: &mut dyn Storage) -> Result<Response, ContractError> {
+        if self.prices.is_empty() {
+            return Ok(Response::new());
+        }
 
-    Ok(Response::new()
-        .add_attribute("method", "set_price")
-        .add_message(msg))
+        for (token, state) in self.states.iter() {
+            TOKEN_STATES.save(store, token, state)?;
+        }
+
+        let event = self.prices.iter().fold(
+            Event::new("prices_updated").add_attribute("count", self.prices.len().to_string()),
+            |event, TokenPrice { token, price }| {
+                event
+                    .add_attribute("token", token)
+                    .add_attribute("price", *price)
+            },
+        );
+        let msg = emit_price_events(&self.fast_price_events, self.prices)?;
+
+        Ok(Response::new()
+            .add_message(msg)
+            .add_event(event)
+            .add_events(self.events))
+    }
+}
+
+// index of the price data window the timestamp falls in
+fn window(timestamp: Uint64, price_data_interval: Uint64) -> Result<Uint64, ContractError> {
+    timestamp
+        .checked_div(price_data_interval)
+        .map_err(|_| ContractError::InvalidPriceDataInterval {})
+}
+
+// counts abnormal updates of the token and trips its breaker, returns the trip event
+fn update_circuit_breaker(
+    config: &CircuitBreakerConfig,
+    breaker: &mut CircuitBreaker,
+    block_timestamp: Uint64,
+    token: &Addr,
+    price: Uint256,
+    price_data: &PriceDataItem,
+) -> Result<Option<Event>, ContractError> {
+    if breaker.tripped {
+        return Ok(None);
+    }
+
+    let deviation = deviation_basis_points(price, price_data.ref_price)?;
+    if deviation > config.max_deviation_basis_points {
+        breaker.consecutive_deviations += 1;
+    } else {
+        breaker.consecutive_deviations = 0;
+    }
+
+    let cumulative_delta_excess = price_data
+        .cumulative_fast_delta
+        .saturating_sub(price_data.cumulative_ref_delta);
+    let reason = if breaker.consecutive_deviations >= config.max_consecutive_deviations {
+        Some("deviation")
+    } else if !config.max_cumulative_delta_excess.is_zero()
+        && cumulative_delta_excess > config.max_cumulative_delta_excess
+    {
+        Some("cumulative_delta")
+    } else {
+        None
+    };
+
+    Ok(reason.map(|reason| {
+        breaker.tripped = true;
+        breaker.tripped_at = block_timestamp;
+        Event::new("circuit_breaker_tripped")
+            .add_attribute("token", token)
+            .add_attribute("reason", reason)
+            .add_attribute("deviation_basis_points", deviation)
+            .add_attribute("cumulative_delta_excess", cumulative_delta_excess)
+    }))
 }
 
 pub fn _set_prices_with_bits(
     deps: DepsMut,
     env: Env,
-    price_bits: Uint256,
+    price_bit_array: &[Uint256],
     _timestamp: Uint64,
+    layout: Option<PriceBitLayout>,
 ) -> Result<Response, ContractError> {
-    let should_update = set_last_updated_values(deps.storage, &env.block, _timestamp.u64())?;
-
-    if should_update {
-        let _fast_price_events: Addr = CONFIG.load(deps.storage)?.fast_price_events;
-        let _vault_price_feed: Addr = VAULT_ADDRESS.load(deps.storage)?;
-        let tokens: Vec<TokenData> = TOKEN_DATA.load(deps.storage)?;
-        let price_array = price_bits.to_le_bytes();
-        for j in 0..4 {
-            if j >= tokens.len() {
-                break;
-            }
-
-            let price = Uint256::from(u64::from_le_bytes(
-                price_array[j * 8..j * 8 + 8].try_into().unwrap(),
-            ));
-            let token = &tokens[j].token;
-            let token_precision = tokens[j].token_precision;
-            let adjusted_price = price.multiply_ratio(PRICE_PRECISION, token_precision);
+    let layout = resolve_price_bit_layout(deps.storage, layout)?;
+    let last_updated = set_last_updated_values(deps.storage, &env.block, _timestamp)?;
+
+    let mut response = Response::new();
+    if let Some(last_updated) = last_updated {
+        let mut batch = PriceBatch::load(
+            deps.storage,
+            deps.querier,
+            Uint64::from(env.block.time.seconds()),
+        )?;
+        let tokens = load_token_index_list(deps.storage)?;
+        let prices = decode_prices(&layout, price_bit_array)?;
+        // slots past the last token are padding
+        for (token_data, price) in tokens.iter().zip(prices) {
+            // skip slots of delisted tokens
+            let Some(token_data) = token_data else {
+                continue;
+            };
 
-            set_price(
+            let adjusted_price = price.adjusted_price(token_data.token_precision)?;
+            batch.set_price(
                 deps.storage,
-                Uint64::from(env.block.time.seconds()),
-                token,
+                &token_data.token,
                 adjusted_price,
-                &_vault_price_feed,
-                &_fast_price_events,
+                &last_updated,
             )?;
         }
+        response = batch.commit(deps.storage)?;
     }
 
-    Ok(Response::new().add_attribute("method", "set_prices_with_bits"))
+    Ok(response)
 }
 
-// for similicity we are considering timestamp represented in seconds instead of nano seconds
+// returns the values to record for the written tokens, None if the prices should be skipped
 fn set_last_updated_values(
     store: &mut dyn Storage,
     block: &BlockInfo,
-    timestamp: u64,
-) -> Result<bool, ContractError> {
-    let min_block_interval = CONFIG.load(store)?.min_block_interval;
+    timestamp: Uint64,
+) -> Result<Option<LastUpdated>, ContractError> {
+    // in quorum mode prices are only committed through observation rounds
+    if QUORUM_CONFIG.may_load(store)?.unwrap_or_default().enabled {
+        return Err(ContractError::QuorumModeEnabled {});
+    }
+
+    let min_block_interval = load_global_state(store)?.config.min_block_interval;
     let mut last_updated = LAST_UPDATED.may_load(store)?.unwrap_or_default();
+    check_min_block_interval(min_block_interval, block, last_updated.last_updated_block)?;
+
+    check_timestamp_bounds(store, block, timestamp)?;
+
+    // Do not update prices if _timestamp is before the current lastUpdatedAt value
+    if timestamp < last_updated.last_updated_at {
+        return Ok(None);
+    }
+
+    last_updated.last_updated_at = timestamp;
+    last_updated.last_updated_block = block.height;
+
+    LAST_UPDATED.save(store, &last_updated)?;
+    Ok(Some(last_updated))
+}
 
+/// Stores `token` under the next compacted price index and returns that index
+pub(crate) fn register_token(
+    store: &mut dyn Storage,
+    token: Addr,
+    token_precision: Uint256,
+) -> StdResult<u32> {
+    let index = NEXT_TOKEN_INDEX.may_load(store)?.unwrap_or_default();
+    TOKENS.save(
+        store,
+        &token,
+        &TokenData::new(token.clone(), token_precision, index),
+    )?;
+    TOKEN_INDEXES.save(store, index, &token)?;
+    NEXT_TOKEN_INDEX.save(store, &(index + 1))?;
+
+    Ok(index)
+}
+
+fn clear_circuit_breaker_reset_votes(store: &mut dyn Storage, token: &Addr) -> StdResult<()> {
+    let voters = CIRCUIT_BREAKER_RESET_VOTES
+        .prefix(token)
+        .keys(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for voter in voters.iter() {
+        CIRCUIT_BREAKER_RESET_VOTES.remove(store, (token, voter));
+    }
+
+    Ok(())
+}
+
+fn clear_token_disable_votes(store: &mut dyn Storage, token: &Addr) -> StdResult<()> {
+    let voters = TOKEN_DISABLE_FAST_PRICE_VOTES
+        .prefix(token)
+        .keys(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for voter in voters.iter() {
+        TOKEN_DISABLE_FAST_PRICE_VOTES.remove(store, (token, voter));
+    }
+
+    Ok(())
+}
+
+fn check_min_block_interval(
+    min_block_interval: Uint64,
+    block: &BlockInfo,
+    last_updated_block: u64,
+) -> Result<(), ContractError> {
     if min_block_interval > Uint64::zero() {
         let blocks_passed = block
             .height
-            .checked_sub(last_updated.last_updated_block)
-            .ok_or(StdError::generic_err(
-                "Overflow while calculating blocks passed",
-            ))?;
+            .checked_sub(last_updated_block)
+            .ok_or_else(|| {
+                OverflowError::new(OverflowOperation::Sub, block.height, last_updated_block)
+            })?;
         if blocks_passed < min_block_interval.u64() {
             return Err(ContractError::MinblockInterval {});
         }
     }
 
-    let max_time_deviation = MAX_TIME_DEVIATION.load(store)?;
+    Ok(())
+}
+
+fn check_timestamp_bounds(
+    store: &dyn Storage,
+    block: &BlockInfo,
+    timestamp: Uint64,
+) -> Result<(), ContractError> {
+    let max_time_deviation = load_item(store, &MAX_TIME_DEVIATION)?.u64();
     let lower_bound = block.time.minus_seconds(max_time_deviation);
     let upper_bound = block.time.plus_seconds(max_time_deviation);
+    let timestamp = timestamp.u64();
     if timestamp <= lower_bound.seconds() {
         return Err(ContractError::TimestampBelowAllowedRange {});
     }
@@ -711,23 +1824,13 @@ fn set_last_updated_values(
         return Err(ContractError::TimestamExceedsAllowedRange {});
     }
 
-    // Do not update prices if _timestamp is before the current lastUpdatedAt value
-    if timestamp < last_updated.last_updated_at {
-        return Ok(false);
-    }
-
-    last_updated.last_updated_at = timestamp;
-    last_updated.last_updated_block = block.height;
-
-    LAST_UPDATED.save(store, &last_updated)?;
-    Ok(true)
+    Ok(())
 }
 
-//TODO: actual contract call with price event emission
-fn emit_price_event(fast_price_events: &Addr, _: &Addr, _: Uint256) -> StdResult<CosmosMsg> {
+fn emit_price_events(fast_price_events: &Addr, prices: Vec<TokenPrice>) -> StdResult<CosmosMsg> {
     let msg = CosmosMsg::Wasm(WasmMsg::Execute {
         contract_addr: fast_price_events.to_string(),
-        msg: to_binary("required price")?,
+        msg: to_binary(&FastPriceEventsExecuteMsg::EmitPriceEvents { prices })?,
         funds: vec![],
     });
 
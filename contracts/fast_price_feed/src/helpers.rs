@@ -1,65 +1,334 @@
 use crate::errors::ContractError;
-use crate::state::{GOV, IS_SIGNER, IS_UPDATER, TOKEN_MANAGER};
-use cosmwasm_std::{Addr, Deps, StdError, Uint256};
+use crate::execute::{MAX_PRICE_DURATION, PRICE_PRECISION};
+use crate::msg::{PositionRequestKeysStartResponse, PositionRouterQueryMsg};
+use crate::query::BASIS_POINTS_DIVISOR;
+use crate::state::{
+    Config, DisableVote, EffectiveTokenParams, GlobalState, PrimaryPriceConfig, QuorumConfig,
+    SpreadBasisPoint, TokenData, TokenState, DISABLE_FAST_PRICE_VOTES, DISABLE_VOTE_EPOCH,
+    GLOBAL_STATE, NEXT_TOKEN_INDEX, TOKENS, TOKEN_DISABLE_FAST_PRICE_VOTES, TOKEN_STATES,
+};
+use cosmwasm_schema::serde::{de::DeserializeOwned, Serialize};
+use cosmwasm_std::{
+    Addr, Int256, Order, QuerierWrapper, StdError, StdResult, Storage, Uint128, Uint256, Uint64,
+};
+use cw_storage_plus::Item;
+use price_feed::msg::QueryMsg as PriceFeedQueryMsg;
+use price_feed::query::GetRoundDataResult;
+use sha2::{Digest, Sha256};
 
-pub struct PositionRouterState {
-    pub increase_position_request_keys_start: Uint256,
-    pub decrease_position_request_keys_start: Uint256,
-}
+pub fn load_position_router(
+    querier: &QuerierWrapper,
+    position_router: &Addr,
+) -> Result<PositionRequestKeysStartResponse, ContractError> {
+    let state = querier.query_wasm_smart(
+        position_router,
+        &PositionRouterQueryMsg::PositionRequestKeysStart {},
+    )?;
 
-//TODO: cosmwasm query msg
-pub fn load_position_router(_: &Addr) -> PositionRouterState {
-    PositionRouterState {
-        increase_position_request_keys_start: Uint256::zero(),
-        decrease_position_request_keys_start: Uint256::zero(),
-    }
+    Ok(state)
 }
 
-pub fn is_gov(deps: Deps, address: &Addr) -> Result<(), ContractError> {
-    if !(address.eq(&GOV.load(deps.storage).unwrap())) {
-        return Err(ContractError::Forbidden {});
+// highest power of ten the primary price is scaled from
+const MAX_PRICE_DECIMALS: u32 = 30;
+
+/// A fast price has to expire before the chain error spread applies
+pub fn validate_price_durations(
+    price_duration: Uint64,
+    max_price_update_delay: Uint64,
+) -> Result<(), ContractError> {
+    if price_duration.is_zero() || price_duration.ge(&MAX_PRICE_DURATION) {
+        return Err(ContractError::InvalidPriceDuration {});
+    }
+    if price_duration >= max_price_update_delay {
+        return Err(ContractError::InvalidMaxPriceUpdateDelay {});
     }
 
     Ok(())
 }
 
-pub fn only_updater(deps: Deps, address: &Addr) -> Result<(), ContractError> {
-    if !IS_UPDATER
-        .may_load(deps.storage, address)?
-        .unwrap_or_default()
+pub fn validate_spread_basis_points(spread: &SpreadBasisPoint) -> Result<(), ContractError> {
+    if spread.spread_basis_points_if_inactive > BASIS_POINTS_DIVISOR
+        || spread.spread_basis_points_if_chain_error > BASIS_POINTS_DIVISOR
     {
-        return Err(ContractError::Forbidden {});
+        return Err(ContractError::InvalidSpreadBasisPoints {});
     }
 
     Ok(())
 }
 
-pub fn only_signer(deps: Deps, address: &Addr) -> Result<(), ContractError> {
-    if !IS_SIGNER
-        .may_load(deps.storage, address)?
-        .unwrap_or_default()
-    {
-        return Err(ContractError::Forbidden {});
+/// Rules of the values set at instantiate, `UpdateConfig` applies them to the fields it sets
+pub fn validate_config(
+    config: &Config,
+    spread: &SpreadBasisPoint,
+    price_data_interval: Uint64,
+) -> Result<(), ContractError> {
+    validate_price_durations(config.price_duration, config.max_price_update_delay)?;
+    validate_spread_basis_points(spread)?;
+    if price_data_interval.is_zero() {
+        return Err(ContractError::InvalidPriceDataInterval {});
     }
 
     Ok(())
 }
 
-pub fn only_token_manager(deps: Deps, address: &Addr) -> Result<(), ContractError> {
-    if !(address.eq(&TOKEN_MANAGER.load(deps.storage).unwrap())) {
-        return Err(ContractError::Forbidden {});
+pub fn validate_primary_price_config(config: &PrimaryPriceConfig) -> Result<(), ContractError> {
+    if config.price_decimals > MAX_PRICE_DECIMALS {
+        return Err(ContractError::InvalidPriceDecimals {});
     }
 
     Ok(())
 }
 
-// Assuming you have an equivalent of getLatestPrimaryPrice
+// Queries the latest round of the token's primary price feed and scales it to PRICE_PRECISION
 pub fn get_latest_primary_price(
-    _vault_price_feed: &Addr,
-    _token: &Addr,
+    querier: &QuerierWrapper,
+    primary_price_feed: &Addr,
+    primary_price_config: &PrimaryPriceConfig,
+    block_timestamp: Uint64,
+) -> Result<Uint256, ContractError> {
+    let round_id: Uint128 =
+        querier.query_wasm_smart(primary_price_feed, &PriceFeedQueryMsg::GetLatestRound)?;
+    if round_id.is_zero() {
+        return Err(ContractError::PrimaryPriceMissing {});
+    }
+
+    let round: GetRoundDataResult = querier.query_wasm_smart(
+        primary_price_feed,
+        &PriceFeedQueryMsg::GetRoundData { round_id },
+    )?;
+    if round.answer < Int256::zero() {
+        return Err(ContractError::PrimaryPriceNegative {});
+    }
+    if round.answer.is_zero() || round.answered_in_round.is_zero() {
+        return Err(ContractError::PrimaryPriceMissing {});
+    }
+
+    let max_price_age = primary_price_config.max_price_age.u64();
+    let updated_at = Uint64::try_from(round.updated_at)?;
+    if max_price_age > 0 && updated_at.u64().saturating_add(max_price_age) < block_timestamp.u64() {
+        return Err(ContractError::PrimaryPriceStale { updated_at });
+    }
+
+    let price = round.answer.abs_diff(Int256::zero());
+    let price_decimals = Uint256::from(10u8).checked_pow(primary_price_config.price_decimals)?;
+    Ok(price.checked_multiply_ratio(PRICE_PRECISION, price_decimals)?)
+}
+
+/// Loads an item written at instantiate, missing when the contract was never set up
+pub fn load_item<T>(store: &dyn Storage, item: &Item<T>) -> Result<T, ContractError>
+where
+    T: Serialize + DeserializeOwned,
+{
+    item.may_load(store)?
+        .ok_or_else(|| ContractError::Uninitialized {
+            item: String::from_utf8_lossy(item.as_slice()).into_owned(),
+        })
+}
+
+pub fn load_token(store: &dyn Storage, token: &Addr) -> Result<TokenData, ContractError> {
+    TOKENS
+        .may_load(store, token)?
+        .ok_or(ContractError::TokenNotFound {
+            token: token.to_string(),
+        })
+}
+
+// Tokens ordered by index, delisted slots are left as None
+pub fn load_token_index_list(store: &dyn Storage) -> StdResult<Vec<Option<TokenData>>> {
+    let next_token_index = NEXT_TOKEN_INDEX.may_load(store)?.unwrap_or_default();
+    let mut tokens = vec![None; next_token_index as usize];
+    for item in TOKENS.range(store, None, None, Order::Ascending) {
+        let (_, token_data) = item?;
+        let index = token_data.index as usize;
+        tokens[index] = Some(token_data);
+    }
+
+    Ok(tokens)
+}
+
+pub fn load_global_state(store: &dyn Storage) -> Result<GlobalState, ContractError> {
+    load_item(store, &GLOBAL_STATE)
+}
+
+// a token that was never written has an empty state
+pub fn load_token_state(store: &dyn Storage, token: &Addr) -> StdResult<TokenState> {
+    Ok(TOKEN_STATES.may_load(store, token)?.unwrap_or_default())
+}
+
+pub fn load_min_authorizations(store: &dyn Storage) -> Result<Uint256, ContractError> {
+    load_global_state(store)?
+        .min_authorizations
+        .ok_or_else(|| ContractError::Uninitialized {
+            item: "min_authorizations".to_string(),
+        })
+}
+
+/// Observations needed to commit a quorum round
+pub fn quorum_min_observations(
+    store: &dyn Storage,
+    quorum_config: &QuorumConfig,
+) -> Result<Uint256, ContractError> {
+    match quorum_config.min_observations {
+        Some(min_observations) => Ok(Uint256::from(min_observations)),
+        None => load_min_authorizations(store),
+    }
+}
+
+pub fn update_global_state(
+    store: &mut dyn Storage,
+    action: impl FnOnce(&mut GlobalState),
+) -> Result<GlobalState, ContractError> {
+    let mut global = load_global_state(store)?;
+    action(&mut global);
+    GLOBAL_STATE.save(store, &global)?;
+
+    Ok(global)
+}
+
+pub fn update_token_state(
+    store: &mut dyn Storage,
+    token: &Addr,
+    action: impl FnOnce(&mut TokenState),
+) -> StdResult<TokenState> {
+    let mut state = load_token_state(store, token)?;
+    action(&mut state);
+    TOKEN_STATES.save(store, token, &state)?;
+
+    Ok(state)
+}
+
+// Resolves the token overrides against the global config and spreads
+pub fn effective_token_params(global: &GlobalState, state: &TokenState) -> EffectiveTokenParams {
+    let config = &global.config;
+    let spread = &global.spread_basis_points;
+    let params = &state.params;
+
+    EffectiveTokenParams {
+        price_duration: params.price_duration.unwrap_or(config.price_duration),
+        max_price_update_delay: params
+            .max_price_update_delay
+            .unwrap_or(config.max_price_update_delay),
+        max_deviation_basis_points: params
+            .max_deviation_basis_points
+            .unwrap_or(config.max_deviation_basis_points),
+        spread_basis_points_if_inactive: params
+            .spread_basis_points_if_inactive
+            .unwrap_or(spread.spread_basis_points_if_inactive),
+        spread_basis_points_if_chain_error: params
+            .spread_basis_points_if_chain_error
+            .unwrap_or(spread.spread_basis_points_if_chain_error),
+        max_cumulative_delta_diff: state.max_cumulative_delta_diff,
+    }
+}
+
+pub fn load_effective_token_params(
+    store: &dyn Storage,
+    token: &Addr,
+) -> StdResult<EffectiveTokenParams> {
+    let global = GLOBAL_STATE.load(store)?;
+    let state = load_token_state(store, token)?;
+
+    Ok(effective_token_params(&global, &state))
+}
+
+/// min_authorizations has to be reachable by the active signers and can not be zero.
+pub fn validate_min_authorizations(
+    min_authorizations: Uint256,
+    signer_count: u32,
+) -> Result<(), ContractError> {
+    if min_authorizations.is_zero() || min_authorizations > Uint256::from(signer_count) {
+        return Err(ContractError::InvalidMinAuthorizations {
+            min_authorizations,
+            signer_count,
+        });
+    }
+
+    Ok(())
+}
+
+/// Live disable votes for `token`, or the global ones when no token is given.
+pub fn load_disable_votes(
+    store: &dyn Storage,
+    token: Option<&Addr>,
+    now: Uint64,
+) -> StdResult<Vec<(Addr, DisableVote)>> {
+    let epoch = DISABLE_VOTE_EPOCH.may_load(store)?.unwrap_or_default();
+    let votes = match token {
+        Some(token) => TOKEN_DISABLE_FAST_PRICE_VOTES
+            .prefix(token)
+            .range(store, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?,
+        None => DISABLE_FAST_PRICE_VOTES
+            .range(store, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?,
+    };
+
+    Ok(votes
+        .into_iter()
+        .filter(|(_, vote)| vote.is_active(epoch, now))
+        .collect())
+}
+
+/// Hash signed by an updater key for `SetPricesSigned`.
+///
+/// The payload is domain separated by the chain id and the contract address and every
+/// variable length field is length prefixed, so it cannot be replayed on another deployment.
+pub fn price_payload_hash(
+    chain_id: &str,
+    contract: &Addr,
+    nonce: u64,
+    timestamp: u64,
+    tokens: &[Addr],
+    prices: &[Uint256],
+) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"fast_price_feed:set_prices_signed");
+    for field in [chain_id.as_bytes(), contract.as_bytes()] {
+        hasher.update((field.len() as u64).to_be_bytes());
+        hasher.update(field);
+    }
+    hasher.update(nonce.to_be_bytes());
+    hasher.update(timestamp.to_be_bytes());
+    hasher.update((tokens.len() as u64).to_be_bytes());
+    for (token, price) in tokens.iter().zip(prices.iter()) {
+        hasher.update((token.as_bytes().len() as u64).to_be_bytes());
+        hasher.update(token.as_bytes());
+        hasher.update(price.to_be_bytes());
+    }
+
+    hasher.finalize().into()
+}
+
+// Median of the observed prices, the two middle prices are averaged for an even count
+pub fn median_price(mut prices: Vec<Uint256>) -> Uint256 {
+    if prices.is_empty() {
+        return Uint256::zero();
+    }
+
+    prices.sort();
+    let middle = prices.len() / 2;
+    if prices.len() % 2 == 1 {
+        prices[middle]
+    } else {
+        let (low, high) = (prices[middle - 1], prices[middle]);
+        low + (high - low) / Uint256::from(2u8)
+    }
+}
+
+pub fn deviation_basis_points(
+    price: Uint256,
+    reference: Uint256,
 ) -> Result<Uint256, ContractError> {
-    // Logic to get the latest primary price from vault_price_feed...
-    Ok(Uint256::zero()) // Just a placeholder for now
+    if reference.is_zero() {
+        return Ok(Uint256::zero());
+    }
+
+    let diff = if price > reference {
+        price - reference
+    } else {
+        reference - price
+    };
+    Ok(diff.checked_multiply_ratio(BASIS_POINTS_DIVISOR, reference)?)
 }
 
 pub fn from_semver(err: semver::Error) -> StdError {
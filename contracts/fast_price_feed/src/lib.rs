@@ -2,8 +2,16 @@ pub mod contract;
 pub mod errors;
 pub mod execute;
 pub mod helpers;
+pub mod migrations;
 pub mod msg;
+pub mod multisig;
+#[cfg(test)]
+mod multitest;
+pub mod pause;
+pub mod price_bits;
 pub mod query;
+pub mod rbac;
 pub mod state;
 #[cfg(test)]
 pub mod tests;
+pub mod timelock;
@@ -0,0 +1,487 @@
+use crate::errors::ContractError;
+use crate::execute::{register_token, MAX_PRICE_DURATION};
+use crate::helpers::{update_token_state, validate_primary_price_config};
+use crate::msg::MigrateMsg;
+use crate::rbac::{has_role, migrate_legacy_roles, set_role_member, Role};
+use crate::state::{
+    CircuitBreaker, Config, DisableVote, DisableVoteTally, GlobalState, LastUpdated, PriceDataItem,
+    SpreadBasisPoint, TokenParams, TokenState, DISABLE_FAST_PRICE_VOTES, DISABLE_VOTE_EPOCH,
+    GLOBAL_STATE, LAST_UPDATED, MAX_TIME_DEVIATION, PRIMARY_PRICE_CONFIG, TOKENS,
+    TOKEN_DISABLE_FAST_PRICE_VOTES, TOKEN_STATES,
+};
+use crate::timelock::{MAX_TIMELOCK_DELAY, TIMELOCK_DELAY};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_schema::serde::{de::DeserializeOwned, Serialize};
+use cosmwasm_std::{Addr, Env, Order, StdResult, Storage, Uint256, Uint64};
+use cw_storage_plus::{Item, Map};
+use std::collections::BTreeSet;
+
+/// Layout version of the stored data, bumped by every step that reshapes storage
+pub const STORAGE_VERSION: Item<u32> = Item::new("storage_version");
+
+/// Storage reshaping of one release, applied once on the way to `version`
+pub struct MigrationStep {
+    pub version: u32,
+    pub name: &'static str,
+    pub run: fn(&mut dyn Storage, &Env) -> Result<(), ContractError>,
+}
+
+// ordered by version, deployments without a stored version start before the first step
+pub const MIGRATION_STEPS: &[MigrationStep] = &[
+    MigrationStep {
+        version: 1,
+        name: "token_registry",
+        run: migrate_legacy_tokens,
+    },
+    MigrationStep {
+        version: 2,
+        name: "roles",
+        run: migrate_roles,
+    },
+    MigrationStep {
+        version: 3,
+        name: "time_units",
+        run: migrate_time_units,
+    },
+    MigrationStep {
+        version: 4,
+        name: "state_records",
+        run: |store, _| Ok(migrate_state_records(store)?),
+    },
+    MigrationStep {
+        version: 5,
+        name: "disable_votes",
+        run: migrate_legacy_disable_votes,
+    },
+    MigrationStep {
+        version: 6,
+        name: "config_token_manager",
+        run: |store, _| Ok(migrate_config_token_manager(store)?),
+    },
+    MigrationStep {
+        version: 7,
+        name: "circuit_breakers",
+        run: |store, _| Ok(migrate_circuit_breakers(store)?),
+    },
+];
+
+pub fn latest_storage_version() -> u32 {
+    MIGRATION_STEPS.last().map_or(0, |step| step.version)
+}
+
+/// Runs the steps newer than the stored version and returns their names
+pub fn run_migration_steps(
+    store: &mut dyn Storage,
+    env: &Env,
+) -> Result<Vec<&'static str>, ContractError> {
+    let storage_version = STORAGE_VERSION.may_load(store)?.unwrap_or_default();
+    let mut applied = vec![];
+    for step in MIGRATION_STEPS
+        .iter()
+        .filter(|step| step.version > storage_version)
+    {
+        (step.run)(store, env)?;
+        STORAGE_VERSION.save(store, &step.version)?;
+        applied.push(step.name);
+    }
+
+    Ok(applied)
+}
+
+pub fn validate_migrate_params(params: &MigrateMsg) -> Result<(), ContractError> {
+    if let Some(primary_price_config) = &params.primary_price_config {
+        validate_primary_price_config(primary_price_config)?;
+    }
+    if params
+        .timelock_delay
+        .is_some_and(|delay| delay.u64() > MAX_TIMELOCK_DELAY)
+    {
+        return Err(ContractError::InvalidTimelockDelay {});
+    }
+
+    Ok(())
+}
+
+/// Sets the parameters of features the migrated deployment predates
+pub fn apply_migrate_params(
+    store: &mut dyn Storage,
+    params: MigrateMsg,
+) -> Result<(), ContractError> {
+    if let Some(primary_price_config) = params.primary_price_config {
+        PRIMARY_PRICE_CONFIG.save(store, &primary_price_config)?;
+    }
+    if let Some(timelock_delay) = params.timelock_delay {
+        TIMELOCK_DELAY.save(store, &timelock_delay.u64())?;
+    }
+    for guardian in params.guardians.iter() {
+        set_role_member(store, Role::Guardian, guardian, true)?;
+    }
+    if let Some(gov) = params.gov {
+        set_role_member(store, Role::Gov, &gov, true)?;
+    }
+
+    Ok(())
+}
+
+// the token list was a single vector before tokens got their own map
+const LEGACY_TOKEN_DATA: Item<Vec<LegacyTokenData>> = Item::new("token_data");
+
+#[cw_serde]
+struct LegacyTokenData {
+    token: Addr,
+    token_precision: Uint256,
+}
+
+/// Moves the legacy token vector into the token registry, keeping the vector order as indexes
+pub fn migrate_legacy_tokens(store: &mut dyn Storage, _env: &Env) -> Result<(), ContractError> {
+    let Some(tokens) = LEGACY_TOKEN_DATA.may_load(store)? else {
+        return Ok(());
+    };
+
+    for token_data in tokens {
+        if !TOKENS.has(store, &token_data.token) {
+            register_token(store, token_data.token, token_data.token_precision)?;
+        }
+    }
+    LEGACY_TOKEN_DATA.remove(store);
+
+    Ok(())
+}
+
+// signer proposals are dispatched by the contract itself, which needs the gov role
+fn migrate_roles(store: &mut dyn Storage, env: &Env) -> Result<(), ContractError> {
+    migrate_legacy_roles(store)?;
+    set_role_member(store, Role::Gov, &env.contract.address, true)?;
+    // the config named the token manager before it became a role
+    if let Some(config) = SPLIT_CONFIG.may_load(store)? {
+        if let Some(token_manager) = config.token_manager {
+            if !token_manager.as_str().is_empty() {
+                set_role_member(store, Role::TokenManager, &token_manager, true)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// same keys as the current items, written before the values became Uint64 seconds
+const LEGACY_MAX_TIME_DEVIATION: Item<u64> = Item::new("max_time_deviation");
+const LEGACY_LAST_UPDATED: Item<LegacyLastUpdated> = Item::new("last_updated");
+const LEGACY_TOKEN_LAST_UPDATED: Map<&Addr, LegacyLastUpdated> = Map::new("token_last_updated");
+
+#[cw_serde]
+struct LegacyLastUpdated {
+    last_updated_at: u64,
+    last_updated_block: u64,
+}
+
+// Every time value written before storage version 3 is in seconds: timestamps came from
+// `env.block.time.seconds()` and the durations were added to them, only the
+// `MAX_PRICE_DURATION` bound was written in nanoseconds. Values that cannot be seconds
+// stop the migration so they get fixed instead of silently rewritten.
+
+fn legacy_timestamp(field: &str, timestamp: u64, now: u64) -> Result<Uint64, ContractError> {
+    if timestamp > now {
+        return Err(ContractError::TimeOutOfRange {
+            field: field.to_string(),
+            value: Uint64::new(timestamp),
+        });
+    }
+
+    Ok(Uint64::new(timestamp))
+}
+
+// `bound` is exclusive
+fn legacy_duration(field: &str, duration: u64, bound: u64) -> Result<Uint64, ContractError> {
+    if duration >= bound {
+        return Err(ContractError::TimeOutOfRange {
+            field: field.to_string(),
+            value: Uint64::new(duration),
+        });
+    }
+
+    Ok(Uint64::new(duration))
+}
+
+fn legacy_last_updated(
+    field: &str,
+    legacy: LegacyLastUpdated,
+    now: u64,
+) -> Result<LastUpdated, ContractError> {
+    Ok(LastUpdated {
+        last_updated_at: legacy_timestamp(field, legacy.last_updated_at, now)?,
+        last_updated_block: legacy.last_updated_block,
+    })
+}
+
+/// Rewrites the times stored as raw u64 as Uint64 seconds and checks every stored time is
+/// in range for seconds, values already migrated are left untouched
+pub fn migrate_time_units(store: &mut dyn Storage, env: &Env) -> Result<(), ContractError> {
+    let now = env.block.time.seconds();
+    // durations added to the block time must not overflow it
+    let max_delay = u64::MAX - now;
+
+    if MAX_TIME_DEVIATION.load(store).is_err() {
+        if let Ok(max_time_deviation) = LEGACY_MAX_TIME_DEVIATION.load(store) {
+            let max_time_deviation =
+                legacy_duration("max_time_deviation", max_time_deviation, max_delay)?;
+            MAX_TIME_DEVIATION.save(store, &max_time_deviation)?;
+        }
+    }
+
+    if LAST_UPDATED.load(store).is_err() {
+        if let Ok(last_updated) = LEGACY_LAST_UPDATED.load(store) {
+            let last_updated = legacy_last_updated("last_updated_at", last_updated, now)?;
+            LAST_UPDATED.save(store, &last_updated)?;
+        }
+    }
+
+    for token in token_keys(store, &SPLIT_TOKEN_LAST_UPDATED)? {
+        if let Ok(last_updated) = LEGACY_TOKEN_LAST_UPDATED.load(store, &token) {
+            let last_updated = legacy_last_updated("last_updated_at", last_updated, now)?;
+            SPLIT_TOKEN_LAST_UPDATED.save(store, &token, &last_updated)?;
+        }
+    }
+
+    if let Some(mut config) = SPLIT_CONFIG.may_load(store)? {
+        config.price_duration = legacy_duration(
+            "price_duration",
+            config.price_duration.u64(),
+            MAX_PRICE_DURATION.u64(),
+        )?;
+        config.max_price_update_delay = legacy_duration(
+            "max_price_update_delay",
+            config.max_price_update_delay.u64(),
+            max_delay,
+        )?;
+        SPLIT_CONFIG.save(store, &config)?;
+    }
+
+    for token in token_keys(store, &SPLIT_TOKEN_PARAMS)? {
+        let params = SPLIT_TOKEN_PARAMS.load(store, &token)?;
+        if let Some(price_duration) = params.price_duration {
+            legacy_duration(
+                "price_duration",
+                price_duration.u64(),
+                MAX_PRICE_DURATION.u64(),
+            )?;
+        }
+    }
+
+    for token in token_keys(store, &SPLIT_PRICE_DATA)? {
+        let price_data = SPLIT_PRICE_DATA.load(store, &token)?;
+        legacy_timestamp("ref_time", price_data.ref_time.u64(), now)?;
+    }
+
+    Ok(())
+}
+
+/// Config as stored while it named the token manager, which is now only the role
+#[cw_serde]
+struct LegacyConfig {
+    price_duration: Uint64,
+    max_price_update_delay: Uint64,
+    min_block_interval: Uint64,
+    max_deviation_basis_points: Uint256,
+    fast_price_events: Addr,
+    // absent from configs already migrated, and kept absent when rewritten
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    token_manager: Option<Addr>,
+}
+
+impl From<LegacyConfig> for Config {
+    fn from(config: LegacyConfig) -> Self {
+        Config {
+            price_duration: config.price_duration,
+            max_price_update_delay: config.max_price_update_delay,
+            min_block_interval: config.min_block_interval,
+            max_deviation_basis_points: config.max_deviation_basis_points,
+            fast_price_events: config.fast_price_events,
+        }
+    }
+}
+
+#[cw_serde]
+struct LegacyGlobalState {
+    config: LegacyConfig,
+    spread_basis_points: SpreadBasisPoint,
+    spread_enabled: bool,
+    min_authorizations: Option<Uint256>,
+    #[serde(default)]
+    disable_votes: DisableVoteTally,
+}
+
+// same key as GLOBAL_STATE, the config may still carry the token manager
+const LEGACY_GLOBAL_STATE: Item<LegacyGlobalState> = Item::new("global_state");
+
+/// Drops the token manager from the stored config, the role alone names it
+pub fn migrate_config_token_manager(store: &mut dyn Storage) -> StdResult<()> {
+    if let Some(global) = LEGACY_GLOBAL_STATE.may_load(store)? {
+        let global = GlobalState {
+            config: global.config.into(),
+            spread_basis_points: global.spread_basis_points,
+            spread_enabled: global.spread_enabled,
+            min_authorizations: global.min_authorizations,
+            disable_votes: global.disable_votes,
+        };
+        GLOBAL_STATE.save(store, &global)?;
+    }
+
+    Ok(())
+}
+
+// breakers kept next to the token states before price queries read them from there
+const SPLIT_CIRCUIT_BREAKERS: Map<&Addr, CircuitBreaker> = Map::new("circuit_breakers");
+
+/// Moves the circuit breakers into `TOKEN_STATES`
+pub fn migrate_circuit_breakers(store: &mut dyn Storage) -> StdResult<()> {
+    let breakers = SPLIT_CIRCUIT_BREAKERS
+        .range(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (token, breaker) in breakers {
+        // breakers of delisted tokens go with them
+        if TOKENS.has(store, &token) {
+            update_token_state(store, &token, |state| state.circuit_breaker = breaker)?;
+        }
+        SPLIT_CIRCUIT_BREAKERS.remove(store, &token);
+    }
+
+    Ok(())
+}
+
+// records split across items and maps before GLOBAL_STATE and TOKEN_STATES
+const SPLIT_CONFIG: Item<LegacyConfig> = Item::new("config");
+const SPLIT_SPREAD_BASIS_POINTS: Item<SpreadBasisPoint> = Item::new("spread_basis_point");
+const SPLIT_SPREAD_ENABLED: Item<bool> = Item::new("spread_enabled");
+const SPLIT_MIN_AUTH: Item<Uint256> = Item::new("MIN_AUTH");
+const SPLIT_PRICES: Map<&Addr, Uint256> = Map::new("prices");
+const SPLIT_PRICE_DATA: Map<&Addr, PriceDataItem> = Map::new("priceData");
+const SPLIT_TOKEN_LAST_UPDATED: Map<&Addr, LastUpdated> = Map::new("token_last_updated");
+const SPLIT_MAX_CUMULATIVE_DELTA_DIFFS: Map<&Addr, Uint256> = Map::new("maxCumulativeDeltaDiffs");
+const SPLIT_TOKEN_PARAMS: Map<&Addr, TokenParams> = Map::new("token_params");
+
+// raw keys, the values may not parse as `T` yet
+fn token_keys<T>(store: &dyn Storage, map: &Map<&Addr, T>) -> StdResult<Vec<Addr>>
+where
+    T: Serialize + DeserializeOwned,
+{
+    map.keys_raw(store, None, None, Order::Ascending)
+        .map(|key| Ok(Addr::unchecked(String::from_utf8(key)?)))
+        .collect()
+}
+
+/// Folds the split global items into `GLOBAL_STATE` and the per-token maps into `TOKEN_STATES`
+pub fn migrate_state_records(store: &mut dyn Storage) -> StdResult<()> {
+    if let Some(config) = SPLIT_CONFIG.may_load(store)? {
+        let global = GlobalState {
+            config: config.into(),
+            spread_basis_points: SPLIT_SPREAD_BASIS_POINTS
+                .may_load(store)?
+                .unwrap_or_default(),
+            spread_enabled: SPLIT_SPREAD_ENABLED.may_load(store)?.unwrap_or_default(),
+            min_authorizations: SPLIT_MIN_AUTH.may_load(store)?,
+            disable_votes: DisableVoteTally::default(),
+        };
+        GLOBAL_STATE.save(store, &global)?;
+        SPLIT_CONFIG.remove(store);
+        SPLIT_SPREAD_BASIS_POINTS.remove(store);
+        SPLIT_SPREAD_ENABLED.remove(store);
+        SPLIT_MIN_AUTH.remove(store);
+    }
+
+    let mut tokens = BTreeSet::new();
+    tokens.extend(token_keys(store, &SPLIT_PRICES)?);
+    tokens.extend(token_keys(store, &SPLIT_PRICE_DATA)?);
+    tokens.extend(token_keys(store, &SPLIT_TOKEN_LAST_UPDATED)?);
+    tokens.extend(token_keys(store, &SPLIT_MAX_CUMULATIVE_DELTA_DIFFS)?);
+    tokens.extend(token_keys(store, &SPLIT_TOKEN_PARAMS)?);
+    for token in tokens {
+        let state = TokenState {
+            price: SPLIT_PRICES.may_load(store, &token)?.unwrap_or_default(),
+            price_data: SPLIT_PRICE_DATA
+                .may_load(store, &token)?
+                .unwrap_or_default(),
+            last_updated: SPLIT_TOKEN_LAST_UPDATED
+                .may_load(store, &token)?
+                .unwrap_or_default(),
+            max_cumulative_delta_diff: SPLIT_MAX_CUMULATIVE_DELTA_DIFFS
+                .may_load(store, &token)?
+                .unwrap_or_default(),
+            params: SPLIT_TOKEN_PARAMS
+                .may_load(store, &token)?
+                .unwrap_or_default(),
+            disable_votes: DisableVoteTally::default(),
+            circuit_breaker: CircuitBreaker::default(),
+        };
+        TOKEN_STATES.save(store, &token, &state)?;
+        SPLIT_PRICES.remove(store, &token);
+        SPLIT_PRICE_DATA.remove(store, &token);
+        SPLIT_TOKEN_LAST_UPDATED.remove(store, &token);
+        SPLIT_MAX_CUMULATIVE_DELTA_DIFFS.remove(store, &token);
+        SPLIT_TOKEN_PARAMS.remove(store, &token);
+    }
+
+    tally_disable_votes(store)
+}
+
+// global disable votes before they carried a timestamp, the count was kept separately
+const LEGACY_DISABLE_FAST_PRICE_VOTES: Map<&Addr, bool> = Map::new("disableFastPriceVotes");
+const LEGACY_DISABLE_FAST_PRICE_VOTE_COUNT: Item<Uint256> = Item::new("disableFastPriceVotecount");
+
+/// Turns the legacy votes of current signers into votes of the current epoch without expiry
+pub fn migrate_legacy_disable_votes(
+    store: &mut dyn Storage,
+    env: &Env,
+) -> Result<(), ContractError> {
+    let epoch = DISABLE_VOTE_EPOCH.may_load(store)?.unwrap_or_default();
+    let votes = LEGACY_DISABLE_FAST_PRICE_VOTES
+        .range(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (signer, voted) in votes {
+        // removed signers no longer back their votes
+        if voted
+            && has_role(store, Role::Signer, &signer)
+            && !DISABLE_FAST_PRICE_VOTES.has(store, &signer)
+        {
+            let vote = DisableVote {
+                epoch,
+                voted_at: Uint64::from(env.block.time.seconds()),
+                expires_at: None,
+            };
+            DISABLE_FAST_PRICE_VOTES.save(store, &signer, &vote)?;
+        }
+        LEGACY_DISABLE_FAST_PRICE_VOTES.remove(store, &signer);
+    }
+    LEGACY_DISABLE_FAST_PRICE_VOTE_COUNT.remove(store);
+
+    Ok(tally_disable_votes(store)?)
+}
+
+// counts the disable votes of the current epoch into the records read by price queries
+fn tally_disable_votes(store: &mut dyn Storage) -> StdResult<()> {
+    let epoch = DISABLE_VOTE_EPOCH.may_load(store)?.unwrap_or_default();
+
+    if let Some(mut global) = GLOBAL_STATE.may_load(store)? {
+        global.disable_votes = DisableVoteTally::default();
+        for item in DISABLE_FAST_PRICE_VOTES.range(store, None, None, Order::Ascending) {
+            let (_, vote) = item?;
+            if vote.epoch == epoch {
+                global.disable_votes.expiries.push(vote.expires_at);
+            }
+        }
+        GLOBAL_STATE.save(store, &global)?;
+    }
+
+    let votes = TOKEN_DISABLE_FAST_PRICE_VOTES
+        .range(store, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for ((token, _), vote) in votes {
+        if vote.epoch == epoch {
+            let mut state = TOKEN_STATES.may_load(store, &token)?.unwrap_or_default();
+            state.disable_votes.expiries.push(vote.expires_at);
+            TOKEN_STATES.save(store, &token, &state)?;
+        }
+    }
+
+    Ok(())
+}
@@ -1,14 +1,61 @@
-use crate::state::{Config, PriceDataItem, SpreadBasisPoint, TokenData};
+use crate::multisig::SignerProposal;
+use crate::pause::PauseState;
+use crate::price_bits::PriceBitLayout;
+use crate::rbac::Role;
+use crate::state::{
+    CircuitBreaker, CircuitBreakerConfig, Config, EffectiveTokenParams, GlobalState, LastUpdated,
+    PendingOwner, PendingRound, PriceDataItem, PrimaryPriceConfig, PubkeyType, QuorumConfig,
+    SpreadBasisPoint, TokenData, TokenParams, TokenState,
+};
+use crate::timelock::{PendingAction, TimelockAction};
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, Uint256, Uint64};
+use cosmwasm_std::{Addr, Binary, Uint256, Uint64};
 
 #[cw_serde]
 pub struct InstantiateMsg {
     pub config: Config,
+    /// defaults to the sender
+    pub gov: Option<Addr>,
+    /// granted the token manager role
+    pub token_manager: Addr,
+    #[serde(default)]
+    pub signers: Vec<Addr>,
+    #[serde(default)]
+    pub updaters: Vec<Addr>,
+    /// defaults to a majority of `signers`
+    pub min_authorizations: Option<Uint256>,
+    #[serde(default)]
+    pub tokens: Vec<InitialToken>,
+    /// primary price feed of the tokens without a feed of their own
+    pub vault_price_feed: Option<Addr>,
+    /// zero spreads by default
+    pub spread_basis_points: Option<SpreadBasisPoint>,
+    pub spread_enabled: Option<bool>,
+    /// seconds, one hour by default
+    pub max_time_deviation: Option<Uint64>,
+    /// seconds, one minute by default
+    pub price_data_interval: Option<Uint64>,
+    pub primary_price_config: Option<PrimaryPriceConfig>,
 }
 
 #[cw_serde]
-pub struct MigrateMsg {}
+pub struct InitialToken {
+    pub token: Addr,
+    pub token_precision: Uint256,
+}
+
+/// Runs the pending storage steps, then sets the given parameters older deployments lack,
+/// `{}` only runs the steps
+#[cw_serde]
+#[derive(Default)]
+pub struct MigrateMsg {
+    pub primary_price_config: Option<PrimaryPriceConfig>,
+    pub timelock_delay: Option<Uint64>,
+    #[serde(default)]
+    pub guardians: Vec<Addr>,
+    /// granted the gov role, for deployments whose gov key is lost
+    pub gov: Option<Addr>,
+}
 
 #[cw_serde]
 pub enum ExecuteMsg {
@@ -32,10 +79,51 @@ pub enum ExecuteMsg {
         fast_price_events: Addr,
     },
 
+    /// Primary price feed of the tokens without a feed of their own
     SetVaultPriceFeed {
         vault_price_feed: Addr,
     },
 
+    /// Feed the token's fast price is compared against, None falls back to the vault price feed
+    SetPrimaryPriceFeed {
+        token: Addr,
+        price_feed: Option<Addr>,
+    },
+
+    SetQuorumConfig {
+        quorum_config: QuorumConfig,
+    },
+
+    SetCircuitBreakerConfig {
+        circuit_breaker_config: CircuitBreakerConfig,
+    },
+
+    /// Layout of the compacted price messages that come without one
+    SetPriceBitLayout {
+        layout: PriceBitLayout,
+    },
+
+    /// Resets a tripped breaker, immediately for gov or once enough signers voted
+    ResetCircuitBreaker {
+        token: Addr,
+    },
+
+    SetUpdaterPubkey {
+        pubkey: Binary,
+        pubkey_type: PubkeyType,
+        is_active: bool,
+    },
+
+    SetPositionRouter {
+        position_router: Addr,
+        is_active: bool,
+    },
+
+    SetPrimaryPriceConfig {
+        price_decimals: u32,
+        max_price_age: Uint64,
+    },
+
     SetMaxTimeDeviation {
         max_time_deviation: Uint64,
     },
@@ -58,11 +146,57 @@ pub enum ExecuteMsg {
     SetIsSpreadEnabled {
         spread_enabled: bool,
     },
+    /// Raises the timelock delay, lowering it has to go through `QueueAction`
+    SetTimelockDelay {
+        timelock_delay: Uint64,
+    },
+    /// Queues a parameter change executable once the timelock delay has passed
+    QueueAction {
+        action: TimelockAction,
+    },
+    ExecuteAction {
+        id: u64,
+    },
+    CancelAction {
+        id: u64,
+    },
+    /// Overrides the last update of `token`, or the global one when no token is given
     SetLastUpdatedAt {
+        token: Option<Addr>,
         last_updated_at: Uint64,
     },
-    SetTokenManager {
-        token_manager: Addr,
+    /// Offers `role` to `new_owner`, the transfer completes once accepted
+    ProposeOwnership {
+        role: Role,
+        new_owner: Addr,
+        expires_at: Option<Uint64>,
+    },
+    AcceptOwnership {
+        role: Role,
+    },
+    CancelOwnershipTransfer {
+        role: Role,
+    },
+    /// Adds `account` to `role`, the sender must hold the admin role of `role`
+    GrantRole {
+        role: Role,
+        account: Addr,
+    },
+    RevokeRole {
+        role: Role,
+        account: Addr,
+    },
+    /// Gives up a role of the sender, a role without members is disabled for good
+    RenounceRole {
+        role: Role,
+    },
+    SetRoleAdmin {
+        role: Role,
+        admin_role: Role,
+    },
+    /// Applies all given fields at once after validating them together
+    UpdateConfig {
+        update: ConfigUpdate,
     },
     SetMaxDeviationBasisPoints {
         max_deviation_basis_points: Uint256,
@@ -71,31 +205,59 @@ pub enum ExecuteMsg {
         tokens: Vec<Addr>,
         max_cumulative_delta_diffs: Vec<Uint256>,
     },
+    /// Overrides loosening the values in effect have to go through `QueueAction`
+    SetTokenParams {
+        token: Addr,
+        params: TokenParams,
+    },
     SetPriceDataInterval {
         price_data_interval: Uint64,
     },
     SetMinAuthorizations {
         min_authorizations: Uint256,
     },
-    SetTokens {
-        tokens: Vec<Addr>,
-        token_precision: Vec<Uint256>,
+    AddToken {
+        token: Addr,
+        token_precision: Uint256,
+    },
+    RemoveToken {
+        token: Addr,
+    },
+    UpdateTokenPrecision {
+        token: Addr,
+        token_precision: Uint256,
     },
     SetPrices {
         tokens: Vec<Addr>,
         prices: Vec<Uint256>,
         timestamp: Uint64,
     },
+    /// Prices signed by registered updater keys, can be relayed by anyone
+    SetPricesSigned {
+        tokens: Vec<Addr>,
+        prices: Vec<Uint256>,
+        timestamp: Uint64,
+        signatures: Vec<PriceSignature>,
+    },
+    /// Observations of an updater for the pending quorum rounds
+    SubmitObservations {
+        tokens: Vec<Addr>,
+        prices: Vec<Uint256>,
+        timestamp: Uint64,
+    },
     SetCompactedPrices {
         price_bit_array: Vec<Uint256>,
         timestamp: Uint64,
+        /// stored layout when unset
+        layout: Option<PriceBitLayout>,
     },
     SetPricesWithBits {
         price_bits: Uint256,
         timestamp: Uint64,
+        /// stored layout when unset
+        layout: Option<PriceBitLayout>,
     },
     SetPricesWithBitsAndExecute {
-        sender: Addr,
         position_router_addr: Addr,
         price_bits: Uint256,
         timestamp: Uint64,
@@ -103,18 +265,47 @@ pub enum ExecuteMsg {
         end_index_for_decrease_positions: Uint256,
         max_increase_positions: Uint256,
         max_decrease_positions: Uint256,
+        /// stored layout when unset
+        layout: Option<PriceBitLayout>,
+    },
+    /// Votes to disable fast prices for `token`, or for every token when no token is given
+    DisableFastPrice {
+        token: Option<Addr>,
+        expires_at: Option<Uint64>,
+    },
+    EnableFastPrice {
+        token: Option<Addr>,
+    },
+    /// Starts a new voting epoch, voiding all disable votes
+    StartDisableVoteEpoch {},
+    /// Stops price writes, callable by guardians and gov
+    Pause {},
+    Unpause {},
+    /// Makes `GetPrice` return the ref price while paused
+    SetPauseFallback {
+        ref_price_fallback: bool,
+    },
+    /// Proposes a gov message, the proposer counts as the first approval
+    SubmitProposal {
+        msg: Box<ExecuteMsg>,
+        expires_at: Option<Uint64>,
+    },
+    ApproveProposal {
+        id: u64,
+    },
+    /// Executes a proposal approved by at least MinAuthorizations signers
+    ExecuteProposal {
+        id: u64,
     },
-    DisableFastPrice,
-    EnableFastPrice,
 }
 
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
     #[returns(Uint256)]
+    /// Price at the current block time
     GetPrice {
         token: Addr,
-        block_timestamp: Uint64,
         ref_price: Uint256,
         maximise: bool,
     },
@@ -140,24 +331,207 @@ pub enum QueryMsg {
     #[returns(Uint256)]
     MaxCumulativeDeltaDiffs { address: Addr },
 
+    #[returns(bool)]
+    IsPositionRouter { address: Addr },
+
+    #[returns(QuorumConfig)]
+    QuorumConfig,
+
+    #[returns(Option<PendingRound>)]
+    PendingRound { token: Addr },
+
+    #[returns(CircuitBreakerConfig)]
+    CircuitBreakerConfig,
+
+    #[returns(PriceBitLayout)]
+    PriceBitLayout,
+
+    #[returns(CircuitBreaker)]
+    CircuitBreaker { token: Addr },
+
+    #[returns(Option<PubkeyType>)]
+    UpdaterPubkey { pubkey: Binary },
+
+    /// Last nonce used by the updater key, the next signature must use a greater one
+    #[returns(Uint64)]
+    UpdaterNonce { pubkey: Binary },
+
     #[returns(bool)]
     IsSigner { address: Addr },
 
     #[returns(bool)]
-    DisableFastPriceVotes { address: Addr },
+    DisableFastPriceVotes { address: Addr, token: Option<Addr> },
 
+    /// Disable votes for `token`, or the global ones when no token is given
     #[returns(Uint256)]
-    DisableFastPriceVoteCount,
+    DisableFastPriceVoteCount { token: Option<Addr> },
+
+    /// Signers whose disable vote currently counts
+    #[returns(Vec<DisableFastPriceVoter>)]
+    DisableFastPriceVoters { token: Option<Addr> },
+
+    #[returns(Uint64)]
+    DisableVoteEpoch,
+
+    #[returns(bool)]
+    HasRole { role: Role, address: Addr },
+
+    /// Members of `role` ordered by address
+    #[returns(Vec<Addr>)]
+    RoleMembers {
+        role: Role,
+        start_after: Option<Addr>,
+        limit: Option<u32>,
+    },
+
+    /// Role allowed to grant and revoke `role`
+    #[returns(Role)]
+    RoleAdmin { role: Role },
+
+    #[returns(Option<PendingOwner>)]
+    PendingOwner { role: Role },
+
+    /// Seconds a queued action waits before it can be executed
+    #[returns(Uint64)]
+    TimelockDelay,
+
+    #[returns(Option<PendingAction>)]
+    PendingAction { id: u64 },
+
+    /// Queued actions ordered by id
+    #[returns(Vec<PendingAction>)]
+    PendingActions {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    #[returns(PauseState)]
+    PauseState,
+
+    #[returns(Option<SignerProposal>)]
+    Proposal { id: u64 },
+
+    /// Signer proposals ordered by id
+    #[returns(Vec<SignerProposal>)]
+    Proposals {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
 
     #[returns(Uint256)]
     MinAuthorizations,
 
-    #[returns(Uint256)]
+    /// Number of active signers, the upper bound of MinAuthorizations
+    #[returns(u32)]
+    SignerCount,
+
+    /// Last update of `token`, or of any token when no token is given
+    #[returns(LastUpdated)]
+    LastUpdated { token: Option<Addr> },
+
+    /// Seconds a submitted timestamp may differ from the block time
+    #[returns(Uint64)]
     MaxTimeDeviation,
 
     #[returns(SpreadBasisPoint)]
     SpreadBasisPoint,
 
+    /// Config, spreads and min authorizations in one record
+    #[returns(GlobalState)]
+    GlobalState,
+
+    /// Everything stored for the token's price, empty when never written
+    #[returns(TokenState)]
+    TokenState { token: Addr },
+
+    #[returns(TokenParams)]
+    TokenParams { token: Addr },
+
+    /// Token overrides resolved against the global config
+    #[returns(EffectiveTokenParams)]
+    EffectiveTokenParams { token: Addr },
+
+    #[returns(TokenData)]
+    Token { token: Addr },
+
+    /// Registered tokens ordered by address
+    #[returns(Vec< TokenData >)]
+    Tokens {
+        start_after: Option<Addr>,
+        limit: Option<u32>,
+    },
+
+    /// Registered tokens ordered by their compacted price index
     #[returns(Vec< TokenData >)]
-    TokenData,
+    TokenIndexes {
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+
+    #[returns(PrimaryPriceConfig)]
+    PrimaryPriceConfig,
+
+    /// Feed the token's fast price is compared against, its own or the vault price feed
+    #[returns(Option<Addr>)]
+    PrimaryPriceFeed { token: Addr },
+}
+
+/// Fields left unset keep their current value
+#[cw_serde]
+#[derive(Default)]
+pub struct ConfigUpdate {
+    pub price_duration: Option<Uint64>,
+    pub max_price_update_delay: Option<Uint64>,
+    pub min_block_interval: Option<Uint64>,
+    pub spread_basis_points_if_inactive: Option<Uint256>,
+    pub spread_basis_points_if_chain_error: Option<Uint256>,
+    pub spread_enabled: Option<bool>,
+    pub max_time_deviation: Option<Uint64>,
+    pub fast_price_events: Option<Addr>,
+    /// can also be updated by the token manager
+    pub max_deviation_basis_points: Option<Uint256>,
+    /// can also be updated by the token manager
+    pub price_data_interval: Option<Uint64>,
+}
+
+#[cw_serde]
+pub struct DisableFastPriceVoter {
+    pub signer: Addr,
+    pub voted_at: Uint64,
+    pub expires_at: Option<Uint64>,
+}
+
+/// Signature of a `SetPricesSigned` payload by a registered updater key
+#[cw_serde]
+pub struct PriceSignature {
+    pub pubkey: Binary,
+    pub signature: Binary,
+    pub nonce: Uint64,
+}
+
+/// Execute interface of the position router driven by `SetPricesWithBitsAndExecute`
+#[cw_serde]
+pub enum PositionRouterExecuteMsg {
+    ExecuteIncreasePositions {
+        end_index: Uint256,
+        execution_fee_receiver: Addr,
+    },
+    ExecuteDecreasePositions {
+        end_index: Uint256,
+        execution_fee_receiver: Addr,
+    },
+}
+
+/// Query interface of the position router driven by `SetPricesWithBitsAndExecute`
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum PositionRouterQueryMsg {
+    #[returns(PositionRequestKeysStartResponse)]
+    PositionRequestKeysStart {},
+}
+
+#[cw_serde]
+pub struct PositionRequestKeysStartResponse {
+    pub increase_position_request_keys_start: Uint256,
+    pub decrease_position_request_keys_start: Uint256,
 }
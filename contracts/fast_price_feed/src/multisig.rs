@@ -0,0 +1,203 @@
+use crate::errors::ContractError;
+use crate::helpers::load_min_authorizations;
+use crate::msg::ExecuteMsg;
+use crate::query::{DEFAULT_LIMIT, MAX_LIMIT};
+use crate::rbac::{has_role, only_signer, Role};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    to_binary, Addr, Deps, DepsMut, Env, Order, Response, StdResult, Uint256, Uint64, WasmMsg,
+};
+use cw_storage_plus::{Bound, Item, Map};
+
+// 3 days in seconds
+pub const DEFAULT_PROPOSAL_DURATION: u64 = 3 * 24 * 60 * 60;
+
+pub const NEXT_PROPOSAL_ID: Item<u64> = Item::new("next_proposal_id");
+pub const SIGNER_PROPOSALS: Map<u64, SignerProposal> = Map::new("signer_proposals");
+
+/// Gov message proposed by a signer, executed by the contract once enough signers approved
+#[cw_serde]
+pub struct SignerProposal {
+    pub id: u64,
+    pub proposer: Addr,
+    pub msg: ExecuteMsg,
+    pub approvals: Vec<Addr>,
+    /// seconds after which the proposal can no longer be approved or executed
+    pub expires_at: Uint64,
+    pub executed: bool,
+}
+
+impl SignerProposal {
+    fn ensure_open(&self, now: u64) -> Result<(), ContractError> {
+        if self.executed {
+            return Err(ContractError::ProposalAlreadyExecuted { id: self.id });
+        }
+        if self.expires_at.u64() <= now {
+            return Err(ContractError::ProposalExpired { id: self.id });
+        }
+
+        Ok(())
+    }
+}
+
+/// Messages the gov role is allowed to send, except the ones that would take the gov role
+/// away from the contract: ownership transfers hand the proposer's role over, and revoking
+/// it directly would end the signer proposals for good
+fn is_gov_msg(msg: &ExecuteMsg, contract: &Addr) -> bool {
+    if let ExecuteMsg::RevokeRole {
+        role: Role::Gov,
+        account,
+    } = msg
+    {
+        return account != contract;
+    }
+
+    matches!(
+        msg,
+        ExecuteMsg::Initialize { .. }
+            | ExecuteMsg::SetSigner { .. }
+            | ExecuteMsg::SetUpdater { .. }
+            | ExecuteMsg::SetFastPriceEvents { .. }
+            | ExecuteMsg::SetVaultPriceFeed { .. }
+            | ExecuteMsg::SetPrimaryPriceFeed { .. }
+            | ExecuteMsg::SetQuorumConfig { .. }
+            | ExecuteMsg::SetCircuitBreakerConfig { .. }
+            | ExecuteMsg::ResetCircuitBreaker { .. }
+            | ExecuteMsg::SetUpdaterPubkey { .. }
+            | ExecuteMsg::SetPositionRouter { .. }
+            | ExecuteMsg::SetPrimaryPriceConfig { .. }
+            | ExecuteMsg::SetMaxTimeDeviation { .. }
+            | ExecuteMsg::SetPriceDuration { .. }
+            | ExecuteMsg::SetMaxPriceUpdateDelay { .. }
+            | ExecuteMsg::SetSpreadBasisPointsIfInactive { .. }
+            | ExecuteMsg::SetSpreadBasisPointsIfChainError { .. }
+            | ExecuteMsg::SetMinBlockInterval { .. }
+            | ExecuteMsg::SetIsSpreadEnabled { .. }
+            | ExecuteMsg::UpdateConfig { .. }
+            | ExecuteMsg::SetTimelockDelay { .. }
+            | ExecuteMsg::QueueAction { .. }
+            | ExecuteMsg::ExecuteAction { .. }
+            | ExecuteMsg::CancelAction { .. }
+            | ExecuteMsg::SetLastUpdatedAt { .. }
+            | ExecuteMsg::CancelOwnershipTransfer { .. }
+            | ExecuteMsg::GrantRole { .. }
+            | ExecuteMsg::RevokeRole { .. }
+            | ExecuteMsg::SetRoleAdmin { .. }
+            | ExecuteMsg::StartDisableVoteEpoch {}
+            | ExecuteMsg::Pause {}
+            | ExecuteMsg::Unpause {}
+            | ExecuteMsg::SetPauseFallback { .. }
+    )
+}
+
+pub fn submit_proposal(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    msg: ExecuteMsg,
+    expires_at: Option<Uint64>,
+) -> Result<Response, ContractError> {
+    only_signer(deps.as_ref(), &sender)?;
+    if !is_gov_msg(&msg, &env.contract.address) {
+        return Err(ContractError::InvalidProposalMsg {});
+    }
+
+    let now = env.block.time.seconds();
+    let expires_at = expires_at.unwrap_or(Uint64::new(now + DEFAULT_PROPOSAL_DURATION));
+    if expires_at.u64() <= now {
+        return Err(ContractError::InvalidProposalExpiry {});
+    }
+
+    let id = NEXT_PROPOSAL_ID.may_load(deps.storage)?.unwrap_or_default();
+    let proposal = SignerProposal {
+        id,
+        proposer: sender.clone(),
+        msg,
+        approvals: vec![sender],
+        expires_at,
+        executed: false,
+    };
+    SIGNER_PROPOSALS.save(deps.storage, id, &proposal)?;
+    NEXT_PROPOSAL_ID.save(deps.storage, &(id + 1))?;
+
+    Ok(Response::new()
+        .add_attribute("method", "submit_proposal")
+        .add_attribute("id", id.to_string())
+        .add_attribute("expires_at", expires_at))
+}
+
+pub fn approve_proposal(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    id: u64,
+) -> Result<Response, ContractError> {
+    only_signer(deps.as_ref(), &sender)?;
+    let mut proposal = SIGNER_PROPOSALS
+        .may_load(deps.storage, id)?
+        .ok_or(ContractError::ProposalNotFound { id })?;
+    proposal.ensure_open(env.block.time.seconds())?;
+    if proposal.approvals.contains(&sender) {
+        return Err(ContractError::AlreadyVoted {});
+    }
+
+    proposal.approvals.push(sender.clone());
+    SIGNER_PROPOSALS.save(deps.storage, id, &proposal)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "approve_proposal")
+        .add_attribute("id", id.to_string())
+        .add_attribute("signer", sender))
+}
+
+/// Dispatches the proposal to the contract itself, which holds the gov role
+pub fn execute_proposal(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    id: u64,
+) -> Result<Response, ContractError> {
+    only_signer(deps.as_ref(), &sender)?;
+    let mut proposal = SIGNER_PROPOSALS
+        .may_load(deps.storage, id)?
+        .ok_or(ContractError::ProposalNotFound { id })?;
+    proposal.ensure_open(env.block.time.seconds())?;
+
+    // approvals of removed signers no longer count
+    let approvals = proposal
+        .approvals
+        .iter()
+        .filter(|signer| has_role(deps.storage, Role::Signer, signer))
+        .count();
+    let min_authorizations = load_min_authorizations(deps.storage)?;
+    if Uint256::from(approvals as u64) < min_authorizations {
+        return Err(ContractError::InsufficientApprovals { min_authorizations });
+    }
+
+    proposal.executed = true;
+    SIGNER_PROPOSALS.save(deps.storage, id, &proposal)?;
+
+    Ok(Response::new()
+        .add_message(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            msg: to_binary(&proposal.msg)?,
+            funds: vec![],
+        })
+        .add_attribute("method", "execute_proposal")
+        .add_attribute("id", id.to_string()))
+}
+
+pub fn signer_proposals(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<SignerProposal>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    SIGNER_PROPOSALS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, proposal)| proposal))
+        .collect()
+}
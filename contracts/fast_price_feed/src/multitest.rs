@@ -0,0 +1,371 @@
+use crate::contract::{execute, instantiate, migrate, query};
+use crate::migrations::STORAGE_VERSION;
+use crate::msg::{ExecuteMsg, InitialToken, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::state::{
+    Config, LastUpdated, PriceDataItem, SpreadBasisPoint, GLOBAL_STATE, TOKEN_STATES,
+};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::testing::{MockApi, MockStorage};
+use cosmwasm_std::{Addr, Empty, Order, Record, Storage, Uint256, Uint64};
+use cw_multi_test::{App, AppBuilder, BankKeeper, ContractWrapper, Executor};
+use cw_storage_plus::{Item, Map};
+use std::cell::Cell;
+use std::rc::Rc;
+
+// Cosmos SDK KVGasConfig, multi-test does not meter gas so the storage part of it is charged
+// here, which is the part the storage layout and the batch paths change
+const DELETE_COST: u64 = 1000;
+const READ_COST_FLAT: u64 = 1000;
+const READ_COST_PER_BYTE: u64 = 3;
+const WRITE_COST_FLAT: u64 = 2000;
+const WRITE_COST_PER_BYTE: u64 = 30;
+const ITER_NEXT_COST_FLAT: u64 = 30;
+
+// contract data is stored under the length-prefixed wasm and contract_data/{address} namespaces
+const CONTRACT_DATA_PREFIX: &[u8] = b"\x00\x04wasm";
+const CONTRACT_DATA_NAMESPACE: &[u8] = b"contract_data/";
+
+type GasApp = App<BankKeeper, MockApi, GasMeteredStorage>;
+
+// app storage charging the KV store gas of every access to contract data
+#[derive(Default)]
+struct GasMeteredStorage {
+    inner: MockStorage,
+    gas: Rc<Cell<u64>>,
+}
+
+impl GasMeteredStorage {
+    fn charge(&self, key: &[u8], flat: u64, per_byte: u64, len: usize) {
+        let is_contract_data = key.starts_with(CONTRACT_DATA_PREFIX)
+            && key.len() > CONTRACT_DATA_PREFIX.len() + 2
+            && key[CONTRACT_DATA_PREFIX.len() + 2..].starts_with(CONTRACT_DATA_NAMESPACE);
+        if is_contract_data {
+            self.gas
+                .set(self.gas.get() + flat + per_byte * (key.len() + len) as u64);
+        }
+    }
+}
+
+impl Storage for GasMeteredStorage {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        let value = self.inner.get(key);
+        let len = value.as_ref().map_or(0, Vec::len);
+        self.charge(key, READ_COST_FLAT, READ_COST_PER_BYTE, len);
+        value
+    }
+
+    fn range<'a>(
+        &'a self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'a> {
+        Box::new(self.inner.range(start, end, order).map(|(key, value)| {
+            self.charge(&key, ITER_NEXT_COST_FLAT, READ_COST_PER_BYTE, value.len());
+            (key, value)
+        }))
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.charge(key, WRITE_COST_FLAT, WRITE_COST_PER_BYTE, value.len());
+        self.inner.set(key, value)
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.charge(key, DELETE_COST, 0, 0);
+        self.inner.remove(key)
+    }
+}
+
+// the storage of one contract inside the app storage, enough to seed and read plain items
+struct ContractStorage<'a> {
+    inner: &'a mut dyn Storage,
+    namespace: Vec<u8>,
+}
+
+impl<'a> ContractStorage<'a> {
+    fn new(inner: &'a mut dyn Storage, contract: &Addr) -> Self {
+        let mut namespace = CONTRACT_DATA_PREFIX.to_vec();
+        let name = [CONTRACT_DATA_NAMESPACE, contract.as_bytes()].concat();
+        namespace.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        namespace.extend_from_slice(&name);
+        Self { inner, namespace }
+    }
+
+    fn key(&self, key: &[u8]) -> Vec<u8> {
+        [self.namespace.as_slice(), key].concat()
+    }
+}
+
+impl<'a> Storage for ContractStorage<'a> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.inner.get(&self.key(key))
+    }
+
+    fn range<'b>(
+        &'b self,
+        _start: Option<&[u8]>,
+        _end: Option<&[u8]>,
+        _order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        unimplemented!("the benchmarks only seed and read single records")
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        let key = self.key(key);
+        self.inner.set(&key, value)
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        let key = self.key(key);
+        self.inner.remove(&key)
+    }
+}
+
+struct Setup {
+    app: GasApp,
+    gas: Rc<Cell<u64>>,
+    feed_code: u64,
+    feed: Addr,
+    tokens: Vec<Addr>,
+}
+
+impl Setup {
+    // gas charged by `action`
+    fn gas_of<T>(&mut self, action: impl FnOnce(&mut GasApp) -> T) -> (T, u64) {
+        self.gas.set(0);
+        let result = action(&mut self.app);
+        (result, self.gas.get())
+    }
+
+    fn set_prices(&mut self, tokens: &[Addr], price: u64) -> u64 {
+        let msg = ExecuteMsg::SetPrices {
+            tokens: tokens.to_vec(),
+            prices: vec![Uint256::from(price); tokens.len()],
+            timestamp: Uint64::new(self.app.block_info().time.seconds()),
+        };
+        let feed = self.feed.clone();
+        let (res, gas) =
+            self.gas_of(|app| app.execute_contract(Addr::unchecked("updater"), feed, &msg, &[]));
+        res.unwrap();
+        gas
+    }
+}
+
+fn setup(token_count: usize) -> Setup {
+    let storage = GasMeteredStorage::default();
+    let gas = storage.gas.clone();
+    let mut app = AppBuilder::new().with_storage(storage).build(|_, _, _| {});
+    let gov = Addr::unchecked("gov");
+
+    let events_code = app.store_code(Box::new(ContractWrapper::<_, _, _, _, _, _, Empty>::new(
+        fast_price_events::contract::execute,
+        fast_price_events::contract::instantiate,
+        fast_price_events::contract::query,
+    )));
+    let events = app
+        .instantiate_contract(
+            events_code,
+            gov.clone(),
+            &fast_price_events::msg::InstantiateMsg {},
+            &[],
+            "fast_price_events",
+            None,
+        )
+        .unwrap();
+
+    let feed_code = app.store_code(Box::new(
+        ContractWrapper::new(execute, instantiate, query).with_migrate(migrate),
+    ));
+    let tokens: Vec<Addr> = (0..token_count)
+        .map(|i| Addr::unchecked(format!("token{}", i)))
+        .collect();
+    let msg = InstantiateMsg {
+        config: Config {
+            price_duration: Uint64::new(300),
+            max_price_update_delay: Uint64::new(3600),
+            min_block_interval: Uint64::zero(),
+            max_deviation_basis_points: Uint256::from(100u64),
+            fast_price_events: events.clone(),
+        },
+        gov: Some(gov.clone()),
+        token_manager: Addr::unchecked("token_manager"),
+        signers: vec![],
+        updaters: vec![Addr::unchecked("updater")],
+        min_authorizations: None,
+        tokens: tokens
+            .iter()
+            .map(|token| InitialToken {
+                token: token.clone(),
+                token_precision: Uint256::one(),
+            })
+            .collect(),
+        vault_price_feed: None,
+        spread_basis_points: None,
+        spread_enabled: None,
+        max_time_deviation: None,
+        price_data_interval: None,
+        primary_price_config: None,
+    };
+    let feed = app
+        .instantiate_contract(
+            feed_code,
+            gov.clone(),
+            &msg,
+            &[],
+            "fast_price_feed",
+            Some(gov.to_string()),
+        )
+        .unwrap();
+
+    let msg = fast_price_events::msg::ExecuteMsg::SetIsPriceFeed {
+        price_feed: feed.clone(),
+        is_active: true,
+    };
+    app.execute_contract(gov, events, &msg, &[]).unwrap();
+
+    Setup {
+        app,
+        gas,
+        feed_code,
+        feed,
+        tokens,
+    }
+}
+
+// config as stored before the token manager became a role
+#[cw_serde]
+struct LegacyConfig {
+    price_duration: Uint64,
+    max_price_update_delay: Uint64,
+    min_block_interval: Uint64,
+    max_deviation_basis_points: Uint256,
+    fast_price_events: Addr,
+    token_manager: Addr,
+}
+
+const LEGACY_CONFIG: Item<LegacyConfig> = Item::new("config");
+const LEGACY_SPREAD_BASIS_POINTS: Item<SpreadBasisPoint> = Item::new("spread_basis_point");
+const LEGACY_SPREAD_ENABLED: Item<bool> = Item::new("spread_enabled");
+const LEGACY_DISABLE_FAST_PRICE_VOTE_COUNT: Item<Uint256> = Item::new("disableFastPriceVotecount");
+const LEGACY_MIN_AUTH: Item<Uint256> = Item::new("MIN_AUTH");
+const LEGACY_PRICES: Map<&Addr, Uint256> = Map::new("prices");
+const LEGACY_PRICE_DATA: Map<&Addr, PriceDataItem> = Map::new("priceData");
+const LEGACY_TOKEN_LAST_UPDATED: Map<&Addr, LastUpdated> = Map::new("token_last_updated");
+const LEGACY_MAX_CUMULATIVE_DELTA_DIFFS: Map<&Addr, Uint256> = Map::new("maxCumulativeDeltaDiffs");
+
+// the nine records a price query loaded before GLOBAL_STATE and TOKEN_STATES
+fn load_legacy_price_records(store: &dyn Storage, token: &Addr) {
+    LEGACY_CONFIG.load(store).unwrap();
+    LEGACY_SPREAD_BASIS_POINTS.load(store).unwrap();
+    LEGACY_TOKEN_LAST_UPDATED.load(store, token).unwrap();
+    LEGACY_PRICES.load(store, token).unwrap();
+    LEGACY_SPREAD_ENABLED.load(store).unwrap();
+    LEGACY_DISABLE_FAST_PRICE_VOTE_COUNT.load(store).unwrap();
+    LEGACY_MIN_AUTH.load(store).unwrap();
+    LEGACY_PRICE_DATA.load(store, token).unwrap();
+    LEGACY_MAX_CUMULATIVE_DELTA_DIFFS
+        .load(store, token)
+        .unwrap();
+}
+
+#[test]
+fn test_get_price_gas() {
+    let mut setup = setup(1);
+    let token = setup.tokens[0].clone();
+    let feed = setup.feed.clone();
+    setup.set_prices(std::slice::from_ref(&token), 10_010);
+
+    // rewrite the records in the split layout and drop the consolidated ones, the migration
+    // folds them back from storage version 3
+    setup.app.init_modules(|_, _, storage| {
+        let mut store = ContractStorage::new(storage, &feed);
+        let global = GLOBAL_STATE.load(&store).unwrap();
+        let state = TOKEN_STATES.load(&store, &token).unwrap();
+        let config = LegacyConfig {
+            price_duration: global.config.price_duration,
+            max_price_update_delay: global.config.max_price_update_delay,
+            min_block_interval: global.config.min_block_interval,
+            max_deviation_basis_points: global.config.max_deviation_basis_points,
+            fast_price_events: global.config.fast_price_events,
+            token_manager: Addr::unchecked("token_manager"),
+        };
+        LEGACY_CONFIG.save(&mut store, &config).unwrap();
+        LEGACY_SPREAD_BASIS_POINTS
+            .save(&mut store, &global.spread_basis_points)
+            .unwrap();
+        LEGACY_SPREAD_ENABLED
+            .save(&mut store, &global.spread_enabled)
+            .unwrap();
+        LEGACY_DISABLE_FAST_PRICE_VOTE_COUNT
+            .save(&mut store, &Uint256::zero())
+            .unwrap();
+        LEGACY_MIN_AUTH.save(&mut store, &Uint256::one()).unwrap();
+        LEGACY_PRICES
+            .save(&mut store, &token, &state.price)
+            .unwrap();
+        LEGACY_PRICE_DATA
+            .save(&mut store, &token, &state.price_data)
+            .unwrap();
+        LEGACY_TOKEN_LAST_UPDATED
+            .save(&mut store, &token, &state.last_updated)
+            .unwrap();
+        LEGACY_MAX_CUMULATIVE_DELTA_DIFFS
+            .save(&mut store, &token, &state.max_cumulative_delta_diff)
+            .unwrap();
+        GLOBAL_STATE.remove(&mut store);
+        TOKEN_STATES.remove(&mut store, &token);
+        STORAGE_VERSION.save(&mut store, &3).unwrap();
+    });
+    let (_, legacy_gas) = setup.gas_of(|app| {
+        app.init_modules(|_, _, storage| {
+            load_legacy_price_records(&ContractStorage::new(storage, &feed), &token)
+        })
+    });
+
+    setup
+        .app
+        .migrate_contract(
+            Addr::unchecked("gov"),
+            feed.clone(),
+            &MigrateMsg::default(),
+            setup.feed_code,
+        )
+        .unwrap();
+    let msg = QueryMsg::GetPrice {
+        token,
+        ref_price: Uint256::from(10_000u64),
+        maximise: true,
+    };
+    let (price, gas) =
+        setup.gas_of(|app| app.wrap().query_wasm_smart::<Uint256>(feed, &msg).unwrap());
+
+    // the whole query against the reads of the split records alone, about half is saved
+    assert_eq!(price, Uint256::from(10_010u64));
+    println!("get_price storage gas: split records {legacy_gas}, consolidated records {gas}");
+    assert!(gas * 3 < legacy_gas * 2);
+}
+
+#[test]
+fn test_batch_update_gas() {
+    let mut setup = setup(8);
+    let tokens = setup.tokens.clone();
+    // the first update of a token writes records the later ones overwrite
+    setup.set_prices(&tokens, 10_000);
+
+    let mut single_gas = 0;
+    for token in tokens.iter() {
+        setup.app.update_block(cw_multi_test::next_block);
+        single_gas += setup.set_prices(std::slice::from_ref(token), 10_010);
+    }
+    setup.app.update_block(cw_multi_test::next_block);
+    let batch_gas = setup.set_prices(&tokens, 10_020);
+
+    // shared state is loaded and the event contract called once per batch, which saves
+    // about two fifths
+    println!(
+        "set_prices storage gas for {} tokens: one per call {single_gas}, one batch {batch_gas}",
+        tokens.len()
+    );
+    assert!(batch_gas * 3 < single_gas * 2);
+}
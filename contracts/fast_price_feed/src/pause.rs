@@ -0,0 +1,79 @@
+use crate::errors::ContractError;
+use crate::rbac::{has_role, is_gov, Role};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, DepsMut, Env, Event, Response, StdResult, Storage, Uint64};
+use cw_storage_plus::Item;
+
+pub const PAUSE_STATE: Item<PauseState> = Item::new("pause_state");
+
+#[cw_serde]
+#[derive(Default)]
+pub struct PauseState {
+    pub paused: bool,
+    pub paused_by: Option<Addr>,
+    pub paused_at: Option<Uint64>,
+    /// `get_price` returns the ref price unchanged while paused
+    pub ref_price_fallback: bool,
+}
+
+pub fn pause_state(store: &dyn Storage) -> StdResult<PauseState> {
+    Ok(PAUSE_STATE.may_load(store)?.unwrap_or_default())
+}
+
+pub fn when_not_paused(store: &dyn Storage) -> Result<(), ContractError> {
+    if pause_state(store)?.paused {
+        return Err(ContractError::Paused {});
+    }
+
+    Ok(())
+}
+
+/// Stops all price writes, allowed for guardians and gov
+pub fn pause(deps: DepsMut, env: Env, sender: Addr) -> Result<Response, ContractError> {
+    if !has_role(deps.storage, Role::Guardian, &sender) {
+        is_gov(deps.as_ref(), &sender)?;
+    }
+    when_not_paused(deps.storage)?;
+
+    let mut state = pause_state(deps.storage)?;
+    state.paused = true;
+    state.paused_by = Some(sender.clone());
+    state.paused_at = Some(Uint64::from(env.block.time.seconds()));
+    PAUSE_STATE.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_event(Event::new("paused").add_attribute("paused_by", sender))
+        .add_attribute("method", "pause"))
+}
+
+pub fn unpause(deps: DepsMut, sender: Addr) -> Result<Response, ContractError> {
+    is_gov(deps.as_ref(), &sender)?;
+    let mut state = pause_state(deps.storage)?;
+    if !state.paused {
+        return Err(ContractError::NotPaused {});
+    }
+
+    state.paused = false;
+    state.paused_by = None;
+    state.paused_at = None;
+    PAUSE_STATE.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_event(Event::new("unpaused").add_attribute("unpaused_by", sender))
+        .add_attribute("method", "unpause"))
+}
+
+pub fn set_pause_fallback(
+    deps: DepsMut,
+    sender: Addr,
+    ref_price_fallback: bool,
+) -> Result<Response, ContractError> {
+    is_gov(deps.as_ref(), &sender)?;
+    let mut state = pause_state(deps.storage)?;
+    state.ref_price_fallback = ref_price_fallback;
+    PAUSE_STATE.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "set_pause_fallback")
+        .add_attribute("ref_price_fallback", ref_price_fallback.to_string()))
+}
@@ -0,0 +1,205 @@
+use crate::errors::ContractError;
+use crate::execute::PRICE_PRECISION;
+use crate::rbac::is_gov;
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, DepsMut, Response, StdResult, Storage, Uint256};
+use cw_storage_plus::Item;
+
+pub const PRICE_BIT_LAYOUT: Item<PriceBitLayout> = Item::new("price_bit_layout");
+
+const WORD_BITS: u32 = 256;
+// widest slot whose price still fits a u128
+const MAX_BIT_WIDTH: u32 = 128;
+const MAX_DECIMALS_BITS: u32 = 8;
+
+/// Order in which the prices of a word are packed
+#[cw_serde]
+pub enum PriceEndianness {
+    /// the first price takes the least significant bits
+    Little,
+    /// the first price takes the most significant bits
+    Big,
+}
+
+/// How prices are packed into the `Uint256` words of the compacted price messages
+#[cw_serde]
+pub struct PriceBitLayout {
+    /// bits of a slot, decimals bits included
+    pub bit_width: u32,
+    pub prices_per_word: u32,
+    pub endianness: PriceEndianness,
+    /// low bits of a slot holding the decimals of its price, zero to scale by the token precision
+    pub decimals_bits: u32,
+}
+
+/// Four 64-bit prices per word, the format the contract started with
+impl Default for PriceBitLayout {
+    fn default() -> Self {
+        Self {
+            bit_width: 64,
+            prices_per_word: 4,
+            endianness: PriceEndianness::Little,
+            decimals_bits: 0,
+        }
+    }
+}
+
+impl PriceBitLayout {
+    /// Eight 32-bit prices per word as packed by GMX keepers
+    pub fn gmx() -> Self {
+        Self {
+            bit_width: 32,
+            prices_per_word: 8,
+            endianness: PriceEndianness::Little,
+            decimals_bits: 0,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ContractError> {
+        if self.prices_per_word == 0
+            || self.decimals_bits > MAX_DECIMALS_BITS
+            || self.bit_width <= self.decimals_bits
+            || self.bit_width > MAX_BIT_WIDTH
+            || self
+                .bit_width
+                .checked_mul(self.prices_per_word)
+                .is_none_or(|bits| bits > WORD_BITS)
+        {
+            return Err(ContractError::InvalidPriceBitLayout {});
+        }
+
+        Ok(())
+    }
+
+    // offset of the slot's least significant bit in the word
+    fn shift(&self, slot: u32) -> u32 {
+        match self.endianness {
+            PriceEndianness::Little => slot * self.bit_width,
+            PriceEndianness::Big => WORD_BITS - (slot + 1) * self.bit_width,
+        }
+    }
+}
+
+/// A price as stored in one slot of a packed word
+#[cw_serde]
+pub struct PackedPrice {
+    pub price: Uint256,
+    /// decimals of `price`, only set by layouts with decimals bits
+    pub decimals: Option<u32>,
+}
+
+impl PackedPrice {
+    /// Scales the price to `PRICE_PRECISION`, by its own decimals when it has some
+    pub fn adjusted_price(&self, token_precision: Uint256) -> Result<Uint256, ContractError> {
+        let precision = match self.decimals {
+            Some(decimals) => Uint256::from(10u64).checked_pow(decimals)?,
+            None => token_precision,
+        };
+
+        Ok(self
+            .price
+            .checked_multiply_ratio(PRICE_PRECISION, precision)?)
+    }
+}
+
+pub fn price_bit_layout(store: &dyn Storage) -> StdResult<PriceBitLayout> {
+    Ok(PRICE_BIT_LAYOUT.may_load(store)?.unwrap_or_default())
+}
+
+// the layout given with a message wins over the stored one
+pub fn resolve_price_bit_layout(
+    store: &dyn Storage,
+    layout: Option<PriceBitLayout>,
+) -> Result<PriceBitLayout, ContractError> {
+    match layout {
+        Some(layout) => {
+            layout.validate()?;
+            Ok(layout)
+        }
+        None => Ok(price_bit_layout(store)?),
+    }
+}
+
+/// Reads every slot of the words, in token index order
+pub fn decode_prices(
+    layout: &PriceBitLayout,
+    words: &[Uint256],
+) -> Result<Vec<PackedPrice>, ContractError> {
+    layout.validate()?;
+
+    let mut prices = Vec::with_capacity(words.len() * layout.prices_per_word as usize);
+    for word in words {
+        for slot in 0..layout.prices_per_word {
+            let shift = layout.shift(slot);
+            // drop the bits above the slot, then the ones below it
+            let value =
+                (*word << (WORD_BITS - shift - layout.bit_width)) >> (WORD_BITS - layout.bit_width);
+            let price = match layout.decimals_bits {
+                0 => PackedPrice {
+                    price: value,
+                    decimals: None,
+                },
+                decimals_bits => PackedPrice {
+                    price: value >> decimals_bits,
+                    // at most MAX_DECIMALS_BITS, the lowest byte holds them
+                    decimals: Some(u32::from(
+                        (value % (Uint256::one() << decimals_bits)).to_le_bytes()[0],
+                    )),
+                },
+            };
+            prices.push(price);
+        }
+    }
+
+    Ok(prices)
+}
+
+/// Packs the prices into words, the inverse of `decode_prices`
+pub fn encode_prices(
+    layout: &PriceBitLayout,
+    prices: &[PackedPrice],
+) -> Result<Vec<Uint256>, ContractError> {
+    layout.validate()?;
+
+    let price_bits = layout.bit_width - layout.decimals_bits;
+    prices
+        .chunks(layout.prices_per_word as usize)
+        .map(|chunk| {
+            let mut word = Uint256::zero();
+            for (slot, PackedPrice { price, decimals }) in chunk.iter().enumerate() {
+                if !(*price >> price_bits).is_zero() {
+                    return Err(ContractError::PriceExceedsBitWidth { price: *price });
+                }
+                let decimals = match (layout.decimals_bits, decimals) {
+                    (0, None) => Uint256::zero(),
+                    (decimals_bits, Some(decimals))
+                        if decimals_bits > 0 && (*decimals >> decimals_bits) == 0 =>
+                    {
+                        Uint256::from(*decimals)
+                    }
+                    _ => return Err(ContractError::InvalidPriceBitLayout {}),
+                };
+                let value = (*price << layout.decimals_bits) + decimals;
+                word += value << layout.shift(slot as u32);
+            }
+
+            Ok(word)
+        })
+        .collect()
+}
+
+pub fn set_price_bit_layout(
+    deps: DepsMut,
+    sender: Addr,
+    layout: PriceBitLayout,
+) -> Result<Response, ContractError> {
+    is_gov(deps.as_ref(), &sender)?;
+    layout.validate()?;
+
+    PRICE_BIT_LAYOUT.save(deps.storage, &layout)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "set_price_bit_layout")
+        .add_attribute("bit_width", layout.bit_width.to_string())
+        .add_attribute("prices_per_word", layout.prices_per_word.to_string()))
+}
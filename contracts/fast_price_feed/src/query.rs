@@ -1,10 +1,21 @@
+use crate::errors::ContractError;
+use crate::helpers::{
+    effective_token_params, load_disable_votes, load_effective_token_params, load_token_state,
+};
+use crate::msg::DisableFastPriceVoter;
+use crate::pause::pause_state;
 use crate::state::{
-    PriceDataItem, CONFIG, DISABLE_FAST_PRICE_VOTE_COUNT, LAST_UPDATED, MAX_CUMULATIVE_DELTA_DIFFS,
-    MIN_AUTH, PRICES, PRICE_DATA, SPREAD_BASIS_POINT_STATE, SPREAD_ENABLED,
+    EffectiveTokenParams, GlobalState, LastUpdated, PriceDataItem, TokenData, TokenParams,
+    TokenState, GLOBAL_STATE, LAST_UPDATED, TOKENS, TOKEN_INDEXES,
 };
-use cosmwasm_std::{Addr, Deps, StdResult, Uint256, Uint64};
+use cosmwasm_std::{Addr, Deps, Order, StdResult, Uint256, Uint64};
+use cw_storage_plus::Bound;
+
+pub const BASIS_POINTS_DIVISOR: Uint256 = Uint256::from_u128(10000u128);
 
-const BASIS_POINTS_DIVISOR: Uint256 = Uint256::from_u128(10000u128);
+// settings for pagination
+pub(crate) const MAX_LIMIT: u32 = 30;
+pub(crate) const DEFAULT_LIMIT: u32 = 10;
 
 pub fn get_price(
     deps: Deps,
@@ -12,21 +23,28 @@ pub fn get_price(
     block_timestamp: Uint64,
     ref_price: Uint256,
     maximise: bool,
-) -> StdResult<Uint256> {
-    let config = CONFIG.load(deps.storage)?;
-    let spread = SPREAD_BASIS_POINT_STATE.load(deps.storage)?;
+) -> Result<Uint256, ContractError> {
+    let pause_state = pause_state(deps.storage)?;
+    if pause_state.paused && pause_state.ref_price_fallback {
+        return Ok(ref_price);
+    }
+
+    let global = GLOBAL_STATE.load(deps.storage)?;
+    let state = load_token_state(deps.storage, &token)?;
+    let params = effective_token_params(&global, &state);
 
     let current_time: Uint64 = block_timestamp;
-    let last_updated_at = LAST_UPDATED.load(deps.storage)?.last_updated_at;
-    let max_price_update_delay = config.max_price_update_delay;
-    let price_duration: Uint64 = config.price_duration;
+    // a token that was never written is treated as stale
+    let last_updated_at = state.last_updated.last_updated_at;
+    let max_price_update_delay = params.max_price_update_delay;
+    let price_duration: Uint64 = params.price_duration;
 
     let basis_points_divisor = BASIS_POINTS_DIVISOR;
-    let spread_basis_points_if_chain_error = spread.spread_basis_points_if_chain_error;
-    let spread_basis_points_if_inactive = spread.spread_basis_points_if_inactive;
-    let max_deviation_basis_points = config.max_deviation_basis_points;
+    let spread_basis_points_if_chain_error = params.spread_basis_points_if_chain_error;
+    let spread_basis_points_if_inactive = params.spread_basis_points_if_inactive;
+    let max_deviation_basis_points = params.max_deviation_basis_points;
 
-    if current_time.u64() > (last_updated_at + max_price_update_delay.u64()) {
+    if current_time > last_updated_at.checked_add(max_price_update_delay)? {
         return calculate_price_with_spread(
             ref_price,
             spread_basis_points_if_chain_error,
@@ -35,7 +53,7 @@ pub fn get_price(
         );
     }
 
-    if current_time.u64() > (last_updated_at + price_duration.u64()) {
+    if current_time > last_updated_at.checked_add(price_duration)? {
         return calculate_price_with_spread(
             ref_price,
             spread_basis_points_if_inactive,
@@ -44,19 +62,21 @@ pub fn get_price(
         );
     }
 
-    let fast_price = PRICES.load(deps.storage, &token)?;
+    let fast_price = state.price;
 
     if fast_price == Uint256::zero() {
         return Ok(ref_price);
     }
 
     let diff_basis_points = if ref_price > fast_price {
-        (ref_price - fast_price) * basis_points_divisor / ref_price
+        ref_price.checked_sub(fast_price)?
     } else {
-        (fast_price - ref_price) * basis_points_divisor / ref_price
-    };
+        fast_price.checked_sub(ref_price)?
+    }
+    .checked_mul(basis_points_divisor)?
+    .checked_div(ref_price)?;
 
-    let result = favor_fast_price(deps, token)?;
+    let result = _favor_fast_price(&global, &state, pause_state.paused, block_timestamp)?;
     let has_spread = !result || diff_basis_points > max_deviation_basis_points;
 
     if has_spread {
@@ -78,31 +98,52 @@ pub fn get_price(
     Ok(fast_price)
 }
 
-pub fn favor_fast_price(deps: Deps, token: Addr) -> StdResult<bool> {
-    let is_spread_enabled = SPREAD_ENABLED.load(deps.storage)?;
+pub fn favor_fast_price(deps: Deps, token: Addr, block_timestamp: Uint64) -> StdResult<bool> {
+    let global = GLOBAL_STATE.load(deps.storage)?;
+    let state = load_token_state(deps.storage, &token)?;
+    let paused = pause_state(deps.storage)?.paused;
+    _favor_fast_price(&global, &state, paused, block_timestamp)
+}
 
-    if is_spread_enabled {
+fn _favor_fast_price(
+    global: &GlobalState,
+    state: &TokenState,
+    paused: bool,
+    block_timestamp: Uint64,
+) -> StdResult<bool> {
+    if global.spread_enabled || paused {
         return Ok(false);
     }
 
-    let disable_fast_price_vote_count = DISABLE_FAST_PRICE_VOTE_COUNT.load(deps.storage)?;
-    let min_authorizations = MIN_AUTH.load(deps.storage)?;
+    // without signers there are no disable votes to count
+    if let Some(min_authorizations) = global.min_authorizations {
+        let disable_fast_price_vote_count =
+            Uint256::from(global.disable_votes.count(block_timestamp));
+        if disable_fast_price_vote_count >= min_authorizations {
+            return Ok(false);
+        }
+
+        let token_disable_fast_price_vote_count =
+            Uint256::from(state.disable_votes.count(block_timestamp));
+        if token_disable_fast_price_vote_count >= min_authorizations {
+            return Ok(false);
+        }
+    }
 
-    if disable_fast_price_vote_count >= min_authorizations {
+    // a tripped breaker keeps the token on the primary price until it is reset
+    if state.circuit_breaker.tripped {
         return Ok(false);
     }
 
-    let price_data = PRICE_DATA.load(deps.storage, &token)?;
-
-    let max_cumulative_delta_diff = MAX_CUMULATIVE_DELTA_DIFFS.load(deps.storage, &token)?;
+    let price_data = &state.price_data;
+    let max_cumulative_delta_diff = state.max_cumulative_delta_diff;
 
     if price_data
         .cumulative_fast_delta
         .gt(&price_data.cumulative_ref_delta)
         && price_data
             .cumulative_fast_delta
-            .checked_sub(price_data.cumulative_ref_delta)
-            .unwrap()
+            .checked_sub(price_data.cumulative_ref_delta)?
             .gt(&max_cumulative_delta_diff)
     {
         return Ok(false);
@@ -111,9 +152,60 @@ pub fn favor_fast_price(deps: Deps, token: Addr) -> StdResult<bool> {
     Ok(true)
 }
 
+pub fn get_token_params(deps: Deps, token: Addr) -> StdResult<TokenParams> {
+    Ok(load_token_state(deps.storage, &token)?.params)
+}
+
+pub fn get_effective_token_params(deps: Deps, token: Addr) -> StdResult<EffectiveTokenParams> {
+    load_effective_token_params(deps.storage, &token)
+}
+
 pub fn get_price_data(deps: Deps, token: Addr) -> StdResult<PriceDataItem> {
-    let price_data = PRICE_DATA.load(deps.storage, &token)?;
-    Ok(price_data)
+    Ok(load_token_state(deps.storage, &token)?.price_data)
+}
+
+pub fn get_last_updated(deps: Deps, token: Option<Addr>) -> StdResult<LastUpdated> {
+    match token {
+        Some(token) => Ok(load_token_state(deps.storage, &token)?.last_updated),
+        None => Ok(LAST_UPDATED.may_load(deps.storage)?.unwrap_or_default()),
+    }
+}
+
+pub fn get_token(deps: Deps, token: Addr) -> StdResult<TokenData> {
+    TOKENS.load(deps.storage, &token)
+}
+
+pub fn get_tokens(
+    deps: Deps,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<TokenData>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    TOKENS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, token_data)| token_data))
+        .collect()
+}
+
+pub fn get_token_indexes(
+    deps: Deps,
+    start_after: Option<u32>,
+    limit: Option<u32>,
+) -> StdResult<Vec<TokenData>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    TOKEN_INDEXES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (_, token) = item?;
+            TOKENS.load(deps.storage, &token)
+        })
+        .collect()
 }
 
 fn calculate_price_with_spread(
@@ -121,12 +213,30 @@ fn calculate_price_with_spread(
     spread: Uint256,
     divisor: Uint256,
     maximise: bool,
-) -> StdResult<Uint256> {
-    let result_price = if maximise {
-        ref_price * (divisor + spread) / divisor
+) -> Result<Uint256, ContractError> {
+    let numerator = if maximise {
+        divisor.checked_add(spread)?
     } else {
-        ref_price * (divisor - spread) / divisor
+        divisor.checked_sub(spread)?
     };
 
-    Ok(result_price)
+    Ok(ref_price.checked_multiply_ratio(numerator, divisor)?)
+}
+
+/// Signers whose disable vote for `token`, or the global one, currently counts
+pub fn get_disable_fast_price_voters(
+    deps: Deps,
+    token: Option<Addr>,
+    block_timestamp: Uint64,
+) -> StdResult<Vec<DisableFastPriceVoter>> {
+    let votes = load_disable_votes(deps.storage, token.as_ref(), block_timestamp)?;
+
+    Ok(votes
+        .into_iter()
+        .map(|(signer, vote)| DisableFastPriceVoter {
+            signer,
+            voted_at: vote.voted_at,
+            expires_at: vote.expires_at,
+        })
+        .collect())
 }
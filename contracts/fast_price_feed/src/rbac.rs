@@ -0,0 +1,282 @@
+use crate::errors::ContractError;
+use crate::helpers::{quorum_min_observations, update_global_state, update_token_state};
+use crate::query::{DEFAULT_LIMIT, MAX_LIMIT};
+use crate::state::{
+    DISABLE_FAST_PRICE_VOTES, DISABLE_VOTE_EPOCH, GLOBAL_STATE, PENDING_OWNERS, QUORUM_CONFIG,
+    TOKENS, TOKEN_DISABLE_FAST_PRICE_VOTES,
+};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    Addr, Deps, DepsMut, Empty, Order, Response, StdError, StdResult, Storage, Uint256,
+};
+use cw_storage_plus::{Bound, Item, Map};
+
+pub const ROLE_MEMBERS: Map<(&str, &Addr), Empty> = Map::new("role_members");
+pub const ROLE_MEMBER_COUNTS: Map<&str, u32> = Map::new("role_member_counts");
+pub const ROLE_ADMINS: Map<&str, Role> = Map::new("role_admins");
+
+// storage used before the roles were introduced, only read by the migration
+const LEGACY_GOV: Item<Addr> = Item::new("GOV");
+const LEGACY_TOKEN_MANAGER: Item<Addr> = Item::new("token_manager");
+const LEGACY_SIGNER_COUNT: Item<u32> = Item::new("signer_count");
+const LEGACY_IS_UPDATER: Map<&Addr, bool> = Map::new("isUpdater");
+const LEGACY_IS_SIGNER: Map<&Addr, bool> = Map::new("isSigner");
+const LEGACY_POSITION_ROUTERS: Map<&Addr, bool> = Map::new("position_routers");
+
+#[cw_serde]
+#[derive(Copy)]
+pub enum Role {
+    Gov,
+    TokenManager,
+    Updater,
+    Signer,
+    PositionRouter,
+    /// can pause price writes, unpausing is left to gov
+    Guardian,
+}
+
+impl Role {
+    pub fn key(&self) -> &'static str {
+        match self {
+            Role::Gov => "gov",
+            Role::TokenManager => "token_manager",
+            Role::Updater => "updater",
+            Role::Signer => "signer",
+            Role::PositionRouter => "position_router",
+            Role::Guardian => "guardian",
+        }
+    }
+}
+
+pub fn has_role(store: &dyn Storage, role: Role, account: &Addr) -> bool {
+    ROLE_MEMBERS.has(store, (role.key(), account))
+}
+
+pub fn only_role(deps: Deps, role: Role, account: &Addr) -> Result<(), ContractError> {
+    if !has_role(deps.storage, role, account) {
+        return Err(ContractError::Forbidden {});
+    }
+
+    Ok(())
+}
+
+pub fn is_gov(deps: Deps, address: &Addr) -> Result<(), ContractError> {
+    only_role(deps, Role::Gov, address)
+}
+
+pub fn only_updater(deps: Deps, address: &Addr) -> Result<(), ContractError> {
+    only_role(deps, Role::Updater, address)
+}
+
+pub fn only_signer(deps: Deps, address: &Addr) -> Result<(), ContractError> {
+    only_role(deps, Role::Signer, address)
+}
+
+pub fn only_token_manager(deps: Deps, address: &Addr) -> Result<(), ContractError> {
+    only_role(deps, Role::TokenManager, address)
+}
+
+pub fn only_position_router(deps: Deps, address: &Addr) -> Result<(), ContractError> {
+    if !has_role(deps.storage, Role::PositionRouter, address) {
+        return Err(ContractError::InvalidPositionRouter {
+            position_router: address.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Role allowed to grant and revoke `role`, gov unless configured otherwise
+pub fn role_admin(store: &dyn Storage, role: Role) -> StdResult<Role> {
+    Ok(ROLE_ADMINS
+        .may_load(store, role.key())?
+        .unwrap_or(Role::Gov))
+}
+
+pub fn role_member_count(store: &dyn Storage, role: Role) -> StdResult<u32> {
+    Ok(ROLE_MEMBER_COUNTS
+        .may_load(store, role.key())?
+        .unwrap_or_default())
+}
+
+pub fn role_members(
+    deps: Deps,
+    role: Role,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<Addr>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    ROLE_MEMBERS
+        .prefix(role.key())
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect()
+}
+
+/// Adds or removes `account` from `role` and keeps the state tied to the role consistent,
+/// returns false when the membership did not change
+pub fn set_role_member(
+    store: &mut dyn Storage,
+    role: Role,
+    account: &Addr,
+    is_member: bool,
+) -> Result<bool, ContractError> {
+    if has_role(store, role, account) == is_member {
+        return Ok(false);
+    }
+
+    let member_count = role_member_count(store, role)?;
+    let member_count = if is_member {
+        member_count + 1
+    } else {
+        member_count
+            .checked_sub(1)
+            .ok_or(StdError::generic_err("Role member count underflow"))?
+    };
+
+    if role == Role::Updater && !is_member {
+        // enabled quorum rounds must stay reachable by the remaining updaters
+        let quorum_config = QUORUM_CONFIG.may_load(store)?.unwrap_or_default();
+        if quorum_config.enabled {
+            let min_observations = quorum_min_observations(store, &quorum_config)?;
+            if min_observations > Uint256::from(member_count) {
+                return Err(ContractError::InsufficientUpdaters { min_observations });
+            }
+        }
+    }
+
+    if is_member {
+        ROLE_MEMBERS.save(store, (role.key(), account), &Empty {})?;
+    } else {
+        ROLE_MEMBERS.remove(store, (role.key(), account));
+    }
+    ROLE_MEMBER_COUNTS.save(store, role.key(), &member_count)?;
+
+    if role == Role::Signer && !is_member {
+        // min authorizations must stay reachable once the contract is initialized
+        let global = GLOBAL_STATE.may_load(store)?;
+        if let Some(min_authorizations) = global.and_then(|global| global.min_authorizations) {
+            if min_authorizations > Uint256::from(member_count) {
+                return Err(ContractError::InsufficientSigners { min_authorizations });
+            }
+        }
+
+        // a removed signer no longer backs the votes it cast
+        let epoch = DISABLE_VOTE_EPOCH.may_load(store)?.unwrap_or_default();
+        if let Some(vote) = DISABLE_FAST_PRICE_VOTES.may_load(store, account)? {
+            DISABLE_FAST_PRICE_VOTES.remove(store, account);
+            if vote.epoch == epoch {
+                update_global_state(store, |global| global.disable_votes.remove(vote.expires_at))?;
+            }
+        }
+        let tokens = TOKENS
+            .keys(store, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for token in tokens.iter() {
+            if let Some(vote) = TOKEN_DISABLE_FAST_PRICE_VOTES.may_load(store, (token, account))? {
+                TOKEN_DISABLE_FAST_PRICE_VOTES.remove(store, (token, account));
+                if vote.epoch == epoch {
+                    update_token_state(store, token, |state| {
+                        state.disable_votes.remove(vote.expires_at)
+                    })?;
+                }
+            }
+        }
+    }
+
+    Ok(true)
+}
+
+pub fn grant_role(
+    deps: DepsMut,
+    sender: Addr,
+    role: Role,
+    account: Addr,
+) -> Result<Response, ContractError> {
+    only_role(deps.as_ref(), role_admin(deps.storage, role)?, &sender)?;
+    set_role_member(deps.storage, role, &account, true)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "grant_role")
+        .add_attribute("role", role.key())
+        .add_attribute("account", account))
+}
+
+pub fn revoke_role(
+    deps: DepsMut,
+    sender: Addr,
+    role: Role,
+    account: Addr,
+) -> Result<Response, ContractError> {
+    only_role(deps.as_ref(), role_admin(deps.storage, role)?, &sender)?;
+    set_role_member(deps.storage, role, &account, false)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "revoke_role")
+        .add_attribute("role", role.key())
+        .add_attribute("account", account))
+}
+
+pub fn renounce_role(deps: DepsMut, sender: Addr, role: Role) -> Result<Response, ContractError> {
+    only_role(deps.as_ref(), role, &sender)?;
+    set_role_member(deps.storage, role, &sender, false)?;
+
+    // a proposal made by the last holder can no longer be completed by anyone
+    if role_member_count(deps.storage, role)? == 0 {
+        PENDING_OWNERS.remove(deps.storage, role.key());
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "renounce_role")
+        .add_attribute("role", role.key())
+        .add_attribute("account", sender))
+}
+
+pub fn set_role_admin(
+    deps: DepsMut,
+    sender: Addr,
+    role: Role,
+    admin_role: Role,
+) -> Result<Response, ContractError> {
+    only_role(deps.as_ref(), role_admin(deps.storage, role)?, &sender)?;
+    ROLE_ADMINS.save(deps.storage, role.key(), &admin_role)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "set_role_admin")
+        .add_attribute("role", role.key())
+        .add_attribute("admin_role", admin_role.key()))
+}
+
+/// Moves the accounts of the per-permission maps into their roles
+pub fn migrate_legacy_roles(store: &mut dyn Storage) -> Result<(), ContractError> {
+    for (item, role) in [
+        (LEGACY_GOV, Role::Gov),
+        (LEGACY_TOKEN_MANAGER, Role::TokenManager),
+    ] {
+        if let Some(account) = item.may_load(store)? {
+            set_role_member(store, role, &account, true)?;
+            item.remove(store);
+        }
+    }
+
+    for (map, role) in [
+        (LEGACY_IS_UPDATER, Role::Updater),
+        (LEGACY_IS_SIGNER, Role::Signer),
+        (LEGACY_POSITION_ROUTERS, Role::PositionRouter),
+    ] {
+        let entries = map
+            .range(store, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (account, is_active) in entries {
+            if is_active {
+                set_role_member(store, role, &account, true)?;
+            }
+            map.remove(store, &account);
+        }
+    }
+    LEGACY_SIGNER_COUNT.remove(store);
+
+    Ok(())
+}
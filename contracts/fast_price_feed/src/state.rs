@@ -3,41 +3,79 @@ use cosmwasm_std::{Addr, Uint256, Uint64};
 use cw_storage_plus::{Item, Map};
 
 pub const IS_INITIALIZED: Item<bool> = Item::new("is_initialized");
-pub const GOV: Item<Addr> = Item::new("GOV");
+/// primary price feed of the tokens without a feed of their own
 pub const VAULT_ADDRESS: Item<Addr> = Item::new("vault_address");
-pub const MIN_AUTH: Item<Uint256> = Item::new("MIN_AUTH");
-pub const CONFIG: Item<Config> = Item::new("config");
-pub const PRICE_DATA_STATE: Item<PriceDataItem> = Item::new("price_data_item");
-pub const SPREAD_BASIS_POINT_STATE: Item<SpreadBasisPoint> = Item::new("spread_basis_point");
-pub const SPREAD_ENABLED: Item<bool> = Item::new("spread_enabled");
-pub const DISABLE_FAST_PRICE_VOTE_COUNT: Item<Uint256> = Item::new("disableFastPriceVotecount");
-pub const TOKEN_DATA: Item<Vec<TokenData>> = Item::new("token_data");
+pub const GLOBAL_STATE: Item<GlobalState> = Item::new("global_state");
+pub const DISABLE_VOTE_EPOCH: Item<u64> = Item::new("disable_vote_epoch");
+pub const NEXT_TOKEN_INDEX: Item<u32> = Item::new("next_token_index");
 pub const LAST_UPDATED: Item<LastUpdated> = Item::new("last_updated");
-pub const MAX_TIME_DEVIATION: Item<u64> = Item::new("max_time_deviation");
-pub const TOKEN_MANAGER: Item<Addr> = Item::new("token_manager");
+pub const MAX_TIME_DEVIATION: Item<Uint64> = Item::new("max_time_deviation");
 pub const PRICE_DATA_INTERVAL: Item<Uint64> = Item::new("price_data_interval");
+pub const PRIMARY_PRICE_CONFIG: Item<PrimaryPriceConfig> = Item::new("primary_price_config");
+/// price feed answering the reference price of each token
+pub const PRIMARY_PRICE_FEEDS: Map<&Addr, Addr> = Map::new("primary_price_feeds");
 
-pub const IS_UPDATER: Map<&Addr, bool> = Map::new("isUpdater");
-pub const IS_SIGNER: Map<&Addr, bool> = Map::new("isSigner");
-pub const PRICES: Map<&Addr, Uint256> = Map::new("prices");
-pub const DISABLE_FAST_PRICE_VOTES: Map<&Addr, bool> = Map::new("disableFastPriceVotes");
-pub const MAX_CUMULATIVE_DELTA_DIFFS: Map<&Addr, Uint256> = Map::new("maxCumulativeDeltaDiffs");
-pub const PRICE_DATA: Map<&Addr, PriceDataItem> = Map::new("priceData");
+pub const DISABLE_FAST_PRICE_VOTES: Map<&Addr, DisableVote> = Map::new("disable_fast_price_votes");
+pub const TOKEN_DISABLE_FAST_PRICE_VOTES: Map<(&Addr, &Addr), DisableVote> =
+    Map::new("token_disable_fast_price_votes");
+pub const TOKENS: Map<&Addr, TokenData> = Map::new("tokens");
+pub const TOKEN_INDEXES: Map<u32, Addr> = Map::new("token_indexes");
+pub const TOKEN_STATES: Map<&Addr, TokenState> = Map::new("token_states");
+pub const UPDATER_PUBKEYS: Map<&[u8], PubkeyType> = Map::new("updater_pubkeys");
+pub const UPDATER_NONCES: Map<&[u8], u64> = Map::new("updater_nonces");
+pub const QUORUM_CONFIG: Item<QuorumConfig> = Item::new("quorum_config");
+pub const PENDING_ROUNDS: Map<&Addr, PendingRound> = Map::new("pending_rounds");
+pub const PENDING_OWNERS: Map<&str, PendingOwner> = Map::new("pending_owners");
+pub const CIRCUIT_BREAKER_CONFIG: Item<CircuitBreakerConfig> = Item::new("circuit_breaker_config");
+pub const CIRCUIT_BREAKER_RESET_VOTES: Map<(&Addr, &Addr), bool> =
+    Map::new("circuit_breaker_reset_votes");
+
+/// Global values read by every price query, kept in one record
+#[cw_serde]
+pub struct GlobalState {
+    pub config: Config,
+    pub spread_basis_points: SpreadBasisPoint,
+    pub spread_enabled: bool,
+    /// unset until signers are configured
+    pub min_authorizations: Option<Uint256>,
+    /// global disable votes of the current epoch
+    #[serde(default)]
+    pub disable_votes: DisableVoteTally,
+}
+
+/// Per-token values read by price queries and written by price updates, kept in one record
+#[cw_serde]
+#[derive(Default)]
+pub struct TokenState {
+    pub price: Uint256,
+    pub price_data: PriceDataItem,
+    pub last_updated: LastUpdated,
+    pub max_cumulative_delta_diff: Uint256,
+    pub params: TokenParams,
+    /// disable votes of the current epoch for this token
+    #[serde(default)]
+    pub disable_votes: DisableVoteTally,
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreaker,
+}
 
 #[cw_serde]
 pub struct Config {
+    /// seconds a fast price stays valid
     pub price_duration: Uint64,
+    /// seconds without update after which the chain error spread applies
     pub max_price_update_delay: Uint64,
+    /// blocks
     pub min_block_interval: Uint64,
     pub max_deviation_basis_points: Uint256,
     pub fast_price_events: Addr,
-    pub token_manager: Addr,
 }
 
 #[cw_serde]
 #[derive(Default)]
 pub struct PriceDataItem {
     pub ref_price: Uint256,
+    /// seconds
     pub ref_time: Uint64,
     pub cumulative_ref_delta: Uint256,
     pub cumulative_fast_delta: Uint256,
@@ -53,21 +91,173 @@ pub struct SpreadBasisPoint {
 #[cw_serde]
 #[derive(Default)]
 pub struct LastUpdated {
-    pub last_updated_at: u64,
+    /// seconds
+    pub last_updated_at: Uint64,
     pub last_updated_block: u64,
 }
 
+#[cw_serde]
+pub struct DisableVote {
+    /// voting epoch the vote was cast in, votes of older epochs are void
+    pub epoch: u64,
+    pub voted_at: Uint64,
+    /// seconds after which the vote no longer counts
+    pub expires_at: Option<Uint64>,
+}
+
+impl DisableVote {
+    pub fn is_active(&self, epoch: u64, now: Uint64) -> bool {
+        self.epoch == epoch && self.expires_at.is_none_or(|expires_at| now < expires_at)
+    }
+}
+
+/// Expiries of the disable votes cast in the current epoch, so price queries can count
+/// the votes without scanning them
+#[cw_serde]
+#[derive(Default)]
+pub struct DisableVoteTally {
+    /// one entry per vote, `None` for votes that never expire
+    pub expiries: Vec<Option<Uint64>>,
+}
+
+impl DisableVoteTally {
+    pub fn count(&self, now: Uint64) -> u64 {
+        self.expiries
+            .iter()
+            .filter(|expires_at| expires_at.is_none_or(|expires_at| now < expires_at))
+            .count() as u64
+    }
+
+    /// Records a vote, dropping the entries that already expired
+    pub fn add(&mut self, expires_at: Option<Uint64>, now: Uint64) {
+        self.expiries
+            .retain(|expires_at| expires_at.is_none_or(|expires_at| now < expires_at));
+        self.expiries.push(expires_at);
+    }
+
+    pub fn remove(&mut self, expires_at: Option<Uint64>) {
+        if let Some(index) = self.expiries.iter().position(|entry| *entry == expires_at) {
+            self.expiries.swap_remove(index);
+        }
+    }
+}
+
+#[cw_serde]
+pub struct PrimaryPriceConfig {
+    /// decimals of the answers reported by the primary price feeds
+    pub price_decimals: u32,
+    /// max age in seconds of a primary price feed answer, zero disables the check
+    pub max_price_age: Uint64,
+}
+
+impl Default for PrimaryPriceConfig {
+    fn default() -> Self {
+        Self {
+            price_decimals: 8,
+            max_price_age: Uint64::zero(),
+        }
+    }
+}
+
 #[cw_serde]
 pub struct TokenData {
     pub token: Addr,
     pub token_precision: Uint256,
+    /// position of the token in the compacted price formats
+    pub index: u32,
 }
 
 impl TokenData {
-    pub fn new(token: Addr, token_precision: Uint256) -> Self {
+    pub fn new(token: Addr, token_precision: Uint256, index: u32) -> Self {
         Self {
             token,
             token_precision,
+            index,
         }
     }
 }
+
+/// Per-token overrides of the global `Config` and `SpreadBasisPoint` values
+#[cw_serde]
+#[derive(Default)]
+pub struct TokenParams {
+    pub price_duration: Option<Uint64>,
+    pub max_price_update_delay: Option<Uint64>,
+    pub max_deviation_basis_points: Option<Uint256>,
+    pub spread_basis_points_if_inactive: Option<Uint256>,
+    pub spread_basis_points_if_chain_error: Option<Uint256>,
+}
+
+/// Parameters applied to a token once its overrides are resolved
+#[cw_serde]
+pub struct EffectiveTokenParams {
+    pub price_duration: Uint64,
+    pub max_price_update_delay: Uint64,
+    pub max_deviation_basis_points: Uint256,
+    pub spread_basis_points_if_inactive: Uint256,
+    pub spread_basis_points_if_chain_error: Uint256,
+    pub max_cumulative_delta_diff: Uint256,
+}
+
+#[cw_serde]
+pub enum PubkeyType {
+    Secp256k1,
+    Ed25519,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct QuorumConfig {
+    /// when enabled prices are only committed once enough updaters observed them
+    pub enabled: bool,
+    /// observations needed to commit a price, min_authorizations is used when unset
+    pub min_observations: Option<u32>,
+    /// seconds after which a pending round is discarded
+    pub round_duration: Uint64,
+    /// deviation from the median above which an observation is flagged as outlier
+    pub outlier_basis_points: Uint256,
+}
+
+#[cw_serde]
+pub struct Observation {
+    pub updater: Addr,
+    pub price: Uint256,
+    pub timestamp: Uint64,
+}
+
+#[cw_serde]
+pub struct PendingRound {
+    pub started_at: Uint64,
+    pub observations: Vec<Observation>,
+}
+
+#[cw_serde]
+pub struct PendingOwner {
+    /// holder giving up the role once the transfer is accepted
+    pub proposer: Addr,
+    pub new_owner: Addr,
+    /// seconds after which the proposal can no longer be accepted
+    pub expires_at: Option<Uint64>,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct CircuitBreakerConfig {
+    pub enabled: bool,
+    /// deviation between the fast and the ref price counted as abnormal
+    pub max_deviation_basis_points: Uint256,
+    /// abnormal updates in a row after which the breaker trips
+    pub max_consecutive_deviations: u32,
+    /// excess of cumulative_fast_delta over cumulative_ref_delta that trips the breaker,
+    /// zero disables the check
+    pub max_cumulative_delta_excess: Uint256,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct CircuitBreaker {
+    pub consecutive_deviations: u32,
+    pub tripped: bool,
+    pub tripped_at: Uint64,
+    pub reset_votes: u32,
+}
@@ -1,12 +1,40 @@
-use crate::contract::execute;
+use crate::contract::{execute, instantiate, migrate, query};
 use crate::execute::*;
-use crate::helpers::{is_gov, only_signer, only_token_manager, only_updater};
-use crate::msg::ExecuteMsg;
+use crate::migrations::*;
+use crate::msg::{
+    ConfigUpdate, ExecuteMsg, InitialToken, InstantiateMsg, MigrateMsg,
+    PositionRequestKeysStartResponse, PositionRouterExecuteMsg, PriceSignature, QueryMsg,
+};
+use crate::multisig::*;
+use crate::pause::*;
+use crate::price_bits::*;
+use crate::rbac::*;
 use crate::state::*;
+use crate::timelock::*;
 
 use crate::errors::ContractError;
-use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-use cosmwasm_std::{Addr, DepsMut, MessageInfo, Uint256, Uint64};
+use crate::helpers::{
+    get_latest_primary_price, load_token_index_list, load_token_state, price_payload_hash,
+    update_global_state, update_token_state, validate_primary_price_config,
+};
+use crate::query::{
+    favor_fast_price, get_disable_fast_price_voters, get_effective_token_params, get_last_updated,
+    get_price, get_token, get_token_indexes, get_tokens,
+};
+use cosmwasm_std::testing::{
+    mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR,
+};
+use cosmwasm_std::{
+    from_binary, from_slice, to_binary, Addr, Attribute, Binary, ContractResult, CosmosMsg, Deps,
+    DepsMut, Int256, MessageInfo, Order, OwnedDeps, QuerierWrapper, Record, Storage, SystemResult,
+    Uint128, Uint256, Uint64, WasmMsg, WasmQuery,
+};
+use cw_storage_plus::{Item, Map};
+use fast_price_events::msg::ExecuteMsg as FastPriceEventsExecuteMsg;
+use price_feed::msg::QueryMsg as PriceFeedQueryMsg;
+use price_feed::query::GetRoundDataResult;
+use std::cell::Cell;
+use std::rc::Rc;
 
 fn generate_config() -> Config {
     Config {
@@ -15,20 +43,149 @@ fn generate_config() -> Config {
         min_block_interval: Uint64::zero(),
         max_deviation_basis_points: Uint256::zero(),
         fast_price_events: Addr::unchecked(""),
-        token_manager: Addr::unchecked(""),
+    }
+}
+
+// stores `config` in the global state, keeping the other global values
+fn save_config(storage: &mut dyn Storage, config: Config) {
+    let global = match GLOBAL_STATE.may_load(storage).unwrap() {
+        Some(global) => GlobalState { config, ..global },
+        None => GlobalState {
+            config,
+            spread_basis_points: SpreadBasisPoint::default(),
+            spread_enabled: false,
+            min_authorizations: None,
+            disable_votes: DisableVoteTally::default(),
+        },
+    };
+    GLOBAL_STATE.save(storage, &global).unwrap();
+}
+
+fn instantiate_msg(config: Config) -> InstantiateMsg {
+    InstantiateMsg {
+        config,
+        gov: None,
+        token_manager: Addr::unchecked("token_manager"),
+        signers: vec![],
+        updaters: vec![],
+        min_authorizations: None,
+        tokens: vec![],
+        vault_price_feed: None,
+        spread_basis_points: None,
+        spread_enabled: None,
+        max_time_deviation: None,
+        price_data_interval: None,
+        primary_price_config: None,
     }
 }
 
 fn setup_with_gov(deps: DepsMut) -> MessageInfo {
     let gov = Addr::unchecked("gov");
-    GOV.save(deps.storage, &gov).unwrap();
+    set_role_member(deps.storage, Role::Gov, &gov, true).unwrap();
     let info = mock_info(gov.as_str(), &[]);
     info
 }
 
+// answers every vault price feed query with a single round holding `answer`,
+// returns the number of queries made
+fn mock_vault_price_feed(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+    answer: i128,
+    updated_at: u64,
+) -> Rc<Cell<usize>> {
+    let queries = Rc::new(Cell::new(0));
+    let counter = queries.clone();
+    deps.querier.update_wasm(move |query| match query {
+        WasmQuery::Smart { msg, .. } => {
+            counter.set(counter.get() + 1);
+            let response = match from_slice(msg).unwrap() {
+                PriceFeedQueryMsg::GetLatestRound => to_binary(&Uint128::one()),
+                PriceFeedQueryMsg::GetLatestAnswer => to_binary(&Int256::from(answer)),
+                PriceFeedQueryMsg::GetRoundData { round_id } => to_binary(&GetRoundDataResult {
+                    round_id,
+                    answer: Int256::from(answer),
+                    started_at: Uint128::from(updated_at),
+                    updated_at: Uint128::from(updated_at),
+                    answered_in_round: round_id,
+                }),
+                _ => panic!("unexpected price feed query"),
+            };
+            SystemResult::Ok(ContractResult::Ok(response.unwrap()))
+        }
+        _ => panic!("unexpected query"),
+    });
+
+    queries
+}
+
+// registers `count` tokens named token0, token1... with a precision of one
+fn setup_with_tokens(mut deps: DepsMut, count: usize) -> Vec<Addr> {
+    let token_manager = Addr::unchecked("token_manager");
+    set_role_member(
+        deps.branch().storage,
+        Role::TokenManager,
+        &token_manager,
+        true,
+    )
+    .unwrap();
+
+    (0..count)
+        .map(|i| {
+            let token = Addr::unchecked(format!("token{}", i));
+            let msg = ExecuteMsg::AddToken {
+                token: token.clone(),
+                token_precision: Uint256::one(),
+            };
+            execute(
+                deps.branch(),
+                mock_env(),
+                mock_info(token_manager.as_str(), &[]),
+                msg,
+            )
+            .unwrap();
+            token
+        })
+        .collect()
+}
+
+// storage that counts the reads and writes going through it
+#[derive(Default)]
+struct CountingStorage {
+    inner: MockStorage,
+    reads: Cell<usize>,
+    writes: usize,
+}
+
+impl Storage for CountingStorage {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.reads.set(self.reads.get() + 1);
+        self.inner.get(key)
+    }
+
+    fn range<'a>(
+        &'a self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'a> {
+        self.reads.set(self.reads.get() + 1);
+        self.inner.range(start, end, order)
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.writes += 1;
+        self.inner.set(key, value)
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.writes += 1;
+        self.inner.remove(key)
+    }
+}
+
 fn setup_with_updater(deps: DepsMut) -> MessageInfo {
     let updater = Addr::unchecked("updated");
-    IS_UPDATER.save(deps.storage, &updater, &true).unwrap();
+    set_role_member(deps.storage, Role::Updater, &updater, true).unwrap();
     let info = mock_info(updater.as_str(), &[]);
     info
 }
@@ -38,57 +195,26 @@ fn test_set_compacted_prices() {
     let mut deps = mock_dependencies();
     let env = mock_env();
     let mock_info = mock_info("admin", &[]);
+    mock_vault_price_feed(&mut deps, 100_000_000, env.block.time.seconds());
 
-    VAULT_ADDRESS
-        .save(&mut deps.storage, &Addr::unchecked("mock_vault_address"))
-        .unwrap();
-    CONFIG
-        .save(
-            &mut deps.storage,
-            &Config {
-                fast_price_events: Addr::unchecked("fast_price_events"),
-                token_manager: Addr::unchecked("toke_manager"),
-                max_deviation_basis_points: Uint256::zero(),
-                max_price_update_delay: Uint64::zero(),
-                price_duration: Uint64::zero(),
-                min_block_interval: Uint64::zero(),
-            },
-        )
-        .unwrap();
+    save_config(
+        &mut deps.storage,
+        Config {
+            fast_price_events: Addr::unchecked("fast_price_events"),
+            max_deviation_basis_points: Uint256::zero(),
+            max_price_update_delay: Uint64::zero(),
+            price_duration: Uint64::zero(),
+            min_block_interval: Uint64::zero(),
+        },
+    );
 
-    TOKEN_DATA
-        .save(
-            &mut deps.storage,
-            &vec![
-                TokenData {
-                    token: Addr::unchecked("token0"),
-                    token_precision: Uint256::one(),
-                },
-                TokenData {
-                    token: Addr::unchecked("token1"),
-                    token_precision: Uint256::one(),
-                },
-                TokenData {
-                    token: Addr::unchecked("token2"),
-                    token_precision: Uint256::one(),
-                },
-                TokenData {
-                    token: Addr::unchecked("token3"),
-                    token_precision: Uint256::one(),
-                },
-                TokenData {
-                    token: Addr::unchecked("token4"),
-                    token_precision: Uint256::one(),
-                },
-            ],
-        )
-        .unwrap();
+    setup_with_tokens(deps.as_mut(), 5);
 
     PRICE_DATA_INTERVAL
         .save(deps.as_mut().storage, &Uint64::one())
         .unwrap();
     MAX_TIME_DEVIATION
-        .save(deps.as_mut().storage, &1000u64)
+        .save(deps.as_mut().storage, &Uint64::new(1000))
         .unwrap();
 
     // generate price for functions and pack then in Uint256
@@ -101,17 +227,29 @@ fn test_set_compacted_prices() {
     let msg = ExecuteMsg::SetCompactedPrices {
         price_bit_array: vec![Uint256::from_le_bytes(combined_bytes)],
         timestamp: env.block.time.seconds().into(),
+        layout: None,
     };
 
+    // only updaters can write compacted prices
+    let result = execute(deps.as_mut(), env.clone(), mock_info.clone(), msg.clone());
+    assert_eq!(result.unwrap_err(), ContractError::Forbidden {});
+
+    set_role_member(
+        deps.as_mut().storage,
+        Role::Updater,
+        &mock_info.sender,
+        true,
+    )
+    .unwrap();
     let result = execute(deps.as_mut(), env, mock_info, msg);
     assert!(result.is_ok());
 
-    for i in 0..prices.len() - 1 {
+    for (i, price) in prices.iter().enumerate() {
         assert_eq!(
-            PRICES
-                .load(&deps.storage, &Addr::unchecked(format!("token{}", i)))
-                .unwrap(),
-            Uint256::from(prices[i])
+            load_token_state(&deps.storage, &Addr::unchecked(format!("token{}", i)))
+                .unwrap()
+                .price,
+            Uint256::from(*price)
                 .checked_mul(crate::execute::PRICE_PRECISION)
                 .unwrap()
         );
@@ -119,9 +257,9 @@ fn test_set_compacted_prices() {
 
     // there is no price for token4
     assert_eq!(
-        PRICES
-            .load(&deps.storage, &Addr::unchecked("token5"))
-            .unwrap_or_default(),
+        load_token_state(&deps.storage, &Addr::unchecked("token4"))
+            .unwrap()
+            .price,
         Uint256::zero()
     );
 }
@@ -129,16 +267,31 @@ fn test_set_compacted_prices() {
 #[test]
 fn test_initialize() {
     let mut deps = mock_dependencies();
+    save_config(deps.as_mut().storage, generate_config());
     let sender = Addr::unchecked("sender");
     let env = mock_env();
     let info = mock_info("sender", &[]);
 
-    let min_auth = Uint256::from(12345u64);
+    let min_auth = Uint256::from(2u64);
     let signers = vec![Addr::unchecked("signer1"), Addr::unchecked("signer2")];
     let updaters = vec![Addr::unchecked("updater1"), Addr::unchecked("updater2")];
 
     // Test initialization
-    GOV.save(deps.as_mut().storage, &sender).ok();
+    set_role_member(deps.as_mut().storage, Role::Gov, &sender, true).ok();
+    let msg = ExecuteMsg::Initialize {
+        min_auth: Uint256::from(3u64),
+        signers: signers.clone(),
+        updaters: updaters.clone(),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::InvalidMinAuthorizations {
+            min_authorizations: Uint256::from(3u64),
+            signer_count: 2
+        }
+    );
+
     let msg = ExecuteMsg::Initialize {
         min_auth,
         signers: signers.clone(),
@@ -149,18 +302,25 @@ fn test_initialize() {
 
     // Check if signers and updaters are saved correctly
     for signer in signers.iter() {
-        assert_eq!(IS_SIGNER.load(deps.as_mut().storage, signer).unwrap(), true);
+        assert!(has_role(deps.as_mut().storage, Role::Signer, signer));
     }
 
     for updater in updaters.iter() {
-        assert!(IS_UPDATER.load(deps.as_mut().storage, updater).unwrap(),)
+        assert!(has_role(deps.as_mut().storage, Role::Updater, updater),)
     }
 
     // Check if min_auth is saved correctly
-    assert_eq!(MIN_AUTH.load(deps.as_mut().storage).unwrap(), min_auth);
+    assert_eq!(
+        GLOBAL_STATE
+            .load(deps.as_mut().storage)
+            .unwrap()
+            .min_authorizations
+            .unwrap(),
+        min_auth
+    );
 
     // Check if contract is marked as initialized
-    assert_eq!(IS_INITIALIZED.load(deps.as_mut().storage).unwrap(), true);
+    assert!(IS_INITIALIZED.load(deps.as_mut().storage).unwrap());
 
     // Test re-initialization
     let msg = ExecuteMsg::Initialize {
@@ -179,7 +339,7 @@ fn test_is_gov() {
     let other_address = Addr::unchecked("other");
 
     // Set the governor's address in storage
-    GOV.save(deps.as_mut().storage, &gov_address).unwrap();
+    set_role_member(deps.as_mut().storage, Role::Gov, &gov_address, true).unwrap();
 
     // Test with governor's address
     let result = is_gov(deps.as_ref(), &gov_address);
@@ -199,9 +359,7 @@ fn test_only_updater() {
     let non_updater_addr = Addr::unchecked("non_updater");
 
     // Set the updater in storage
-    IS_UPDATER
-        .save(deps.as_mut().storage, &updater_addr, &true)
-        .unwrap();
+    set_role_member(deps.as_mut().storage, Role::Updater, &updater_addr, true).unwrap();
 
     // Test with updater address
     assert_eq!(only_updater(deps.as_ref(), &updater_addr), Ok(()));
@@ -220,9 +378,7 @@ fn test_only_signer() {
     let non_signer_addr = Addr::unchecked("non_signer");
 
     // Set the signer in storage
-    IS_SIGNER
-        .save(deps.as_mut().storage, &signer_addr, &true)
-        .unwrap();
+    set_role_member(deps.as_mut().storage, Role::Signer, &signer_addr, true).unwrap();
 
     // Test with signer address
     assert_eq!(only_signer(deps.as_ref(), &signer_addr), Ok(()));
@@ -240,10 +396,9 @@ fn test_disable_fast_price() {
     let env = mock_env();
     let sender = Addr::unchecked("sender_address");
 
-    IS_SIGNER
-        .save(deps.as_mut().storage, &sender, &true)
-        .unwrap();
-    let res = disable_fast_price(deps.as_mut(), env.clone(), sender.clone()).unwrap();
+    save_config(deps.as_mut().storage, generate_config());
+    set_role_member(deps.as_mut().storage, Role::Signer, &sender, true).unwrap();
+    let res = disable_fast_price(deps.as_mut(), env.clone(), sender.clone(), None, None).unwrap();
 
     // Check attributes in the response
     assert_eq!(res.attributes[0].key, "method");
@@ -252,10 +407,11 @@ fn test_disable_fast_price() {
     assert_eq!(res.attributes[1].value, "sender_address");
 
     // Check if the vote was saved correctly
-    let vote: bool = DISABLE_FAST_PRICE_VOTES
+    let vote = DISABLE_FAST_PRICE_VOTES
         .load(deps.as_ref().storage, &sender)
         .unwrap();
-    assert_eq!(vote, true);
+    assert_eq!(vote.voted_at, Uint64::from(env.block.time.seconds()));
+    assert_eq!(vote.expires_at, None);
 }
 
 #[test]
@@ -264,17 +420,16 @@ fn test_enable_fast_price() {
     let env = mock_env();
     let sender = Addr::unchecked("sender_address");
 
-    IS_SIGNER
-        .save(deps.as_mut().storage, &sender, &true)
-        .unwrap();
+    save_config(deps.as_mut().storage, generate_config());
+    set_role_member(deps.as_mut().storage, Role::Signer, &sender, true).unwrap();
 
     // should fail as already enabled
-    let res = enable_fast_price(deps.as_mut(), env.clone(), sender.clone());
+    let res = enable_fast_price(deps.as_mut(), env.clone(), sender.clone(), None);
     assert_eq!(res.unwrap_err(), ContractError::AlreadyEnabled {});
 
     // should work work
-    disable_fast_price(deps.as_mut(), env.clone(), sender.clone()).unwrap();
-    let res = enable_fast_price(deps.as_mut(), env.clone(), sender.clone()).unwrap();
+    disable_fast_price(deps.as_mut(), env.clone(), sender.clone(), None, None).unwrap();
+    let res = enable_fast_price(deps.as_mut(), env.clone(), sender.clone(), None).unwrap();
 
     // Check attributes in the response
     assert_eq!(res.attributes[0].key, "method");
@@ -282,11 +437,179 @@ fn test_enable_fast_price() {
     assert_eq!(res.attributes[1].key, "sender");
     assert_eq!(res.attributes[1].value, "sender_address");
 
-    // Check if the vote was saved correctly
-    let vote: bool = DISABLE_FAST_PRICE_VOTES
-        .load(deps.as_ref().storage, &sender)
+    // Check if the vote was withdrawn
+    assert!(!DISABLE_FAST_PRICE_VOTES.has(deps.as_ref().storage, &sender));
+}
+
+#[test]
+fn test_disable_fast_price_per_token() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let now = Uint64::from(env.block.time.seconds());
+    let tokens = setup_with_tokens(deps.as_mut(), 2);
+
+    save_config(deps.as_mut().storage, generate_config());
+    update_global_state(deps.as_mut().storage, |global| {
+        global.spread_enabled = false;
+        global.min_authorizations = Some(Uint256::from(2u64));
+    })
+    .unwrap();
+    let signers: Vec<Addr> = (0..2)
+        .map(|i| {
+            let signer = Addr::unchecked(format!("signer{}", i));
+            set_role_member(deps.as_mut().storage, Role::Signer, &signer, true).unwrap();
+            signer
+        })
+        .collect();
+    for token in tokens.iter() {
+        update_token_state(deps.as_mut().storage, token, |state| {
+            state.price_data = PriceDataItem::default()
+        })
+        .unwrap();
+    }
+
+    let res = disable_fast_price(
+        deps.as_mut(),
+        env.clone(),
+        signers[0].clone(),
+        Some(Addr::unchecked("unknown")),
+        None,
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::TokenNotFound {
+            token: "unknown".to_string()
+        }
+    );
+
+    for signer in signers.iter() {
+        disable_fast_price(
+            deps.as_mut(),
+            env.clone(),
+            signer.clone(),
+            Some(tokens[0].clone()),
+            None,
+        )
         .unwrap();
-    assert_eq!(vote, false);
+    }
+    let res = disable_fast_price(
+        deps.as_mut(),
+        env.clone(),
+        signers[0].clone(),
+        Some(tokens[0].clone()),
+        None,
+    );
+    assert_eq!(res.unwrap_err(), ContractError::AlreadyVoted {});
+
+    // only the voted token falls back to the primary price
+    assert!(!favor_fast_price(deps.as_ref(), tokens[0].clone(), now).unwrap());
+    assert!(favor_fast_price(deps.as_ref(), tokens[1].clone(), now).unwrap());
+    assert!(!DISABLE_FAST_PRICE_VOTES.has(deps.as_ref().storage, &signers[0]));
+
+    enable_fast_price(
+        deps.as_mut(),
+        env.clone(),
+        signers[0].clone(),
+        Some(tokens[0].clone()),
+    )
+    .unwrap();
+    assert!(favor_fast_price(deps.as_ref(), tokens[0].clone(), now).unwrap());
+
+    // the global votes still disable every token
+    for signer in signers.iter() {
+        disable_fast_price(deps.as_mut(), env.clone(), signer.clone(), None, None).unwrap();
+    }
+    assert!(!favor_fast_price(deps.as_ref(), tokens[1].clone(), now).unwrap());
+}
+
+#[test]
+fn test_disable_votes_lifecycle() {
+    let mut deps = mock_dependencies();
+    let mut env = mock_env();
+    let gov = setup_with_gov(deps.as_mut());
+    let tokens = setup_with_tokens(deps.as_mut(), 1);
+    save_config(deps.as_mut().storage, generate_config());
+    let now = Uint64::from(env.block.time.seconds());
+    let signers: Vec<Addr> = (0..3)
+        .map(|i| {
+            let signer = Addr::unchecked(format!("signer{}", i));
+            let msg = ExecuteMsg::SetSigner {
+                account: signer.clone(),
+                is_active: true,
+            };
+            execute(deps.as_mut(), env.clone(), gov.clone(), msg).unwrap();
+            signer
+        })
+        .collect();
+    let voters =
+        |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>, token: Option<Addr>, now: Uint64| {
+            get_disable_fast_price_voters(deps.as_ref(), token, now)
+                .unwrap()
+                .into_iter()
+                .map(|voter| voter.signer)
+                .collect::<Vec<_>>()
+        };
+
+    let res = disable_fast_price(
+        deps.as_mut(),
+        env.clone(),
+        signers[0].clone(),
+        None,
+        Some(now),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::InvalidVoteExpiry {});
+
+    disable_fast_price(
+        deps.as_mut(),
+        env.clone(),
+        signers[0].clone(),
+        None,
+        Some(now + Uint64::new(60)),
+    )
+    .unwrap();
+    disable_fast_price(deps.as_mut(), env.clone(), signers[1].clone(), None, None).unwrap();
+    disable_fast_price(
+        deps.as_mut(),
+        env.clone(),
+        signers[2].clone(),
+        Some(tokens[0].clone()),
+        None,
+    )
+    .unwrap();
+    assert_eq!(voters(&deps, None, now), signers[0..2].to_vec());
+
+    // expired votes stop counting and can be cast again
+    env.block.time = env.block.time.plus_seconds(60);
+    let now = Uint64::from(env.block.time.seconds());
+    assert_eq!(voters(&deps, None, now), vec![signers[1].clone()]);
+    disable_fast_price(deps.as_mut(), env.clone(), signers[0].clone(), None, None).unwrap();
+
+    // removing a signer withdraws its votes
+    let msg = ExecuteMsg::SetSigner {
+        account: signers[2].clone(),
+        is_active: false,
+    };
+    execute(deps.as_mut(), env.clone(), gov.clone(), msg).unwrap();
+    assert!(voters(&deps, Some(tokens[0].clone()), now).is_empty());
+
+    // a new epoch voids every vote
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(signers[0].as_str(), &[]),
+        ExecuteMsg::StartDisableVoteEpoch {},
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Forbidden {});
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        gov,
+        ExecuteMsg::StartDisableVoteEpoch {},
+    )
+    .unwrap();
+    assert!(voters(&deps, None, now).is_empty());
+    disable_fast_price(deps.as_mut(), env, signers[1].clone(), None, None).unwrap();
+    assert_eq!(voters(&deps, None, now), vec![signers[1].clone()]);
 }
 
 #[test]
@@ -296,9 +619,13 @@ fn test_only_token_manager() {
     let non_token_manager_addr = Addr::unchecked("non_token_manager");
 
     // Set the token manager in storage
-    TOKEN_MANAGER
-        .save(deps.as_mut().storage, &token_manager_addr)
-        .unwrap();
+    set_role_member(
+        deps.as_mut().storage,
+        Role::TokenManager,
+        &token_manager_addr,
+        true,
+    )
+    .unwrap();
 
     // Test with token manager address
     assert_eq!(
@@ -327,10 +654,413 @@ fn test_set_signer() {
     let res = execute(deps.as_mut(), env, info, msg);
     assert!(res.is_ok());
 
-    let status = IS_SIGNER.load(deps.as_ref().storage, &account).unwrap();
+    let status = has_role(deps.as_ref().storage, Role::Signer, &account);
     assert!(status);
 }
 
+#[test]
+fn test_min_authorizations_bounds() {
+    let mut deps = mock_dependencies();
+    save_config(deps.as_mut().storage, generate_config());
+    let gov = setup_with_gov(deps.as_mut());
+    let env = mock_env();
+    let token_manager = mock_info("token_manager", &[]);
+    set_role_member(
+        deps.as_mut().storage,
+        Role::TokenManager,
+        &token_manager.sender,
+        true,
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::Initialize {
+        min_auth: Uint256::one(),
+        signers: vec![Addr::unchecked("signer1"), Addr::unchecked("signer1")],
+        updaters: vec![],
+    };
+    execute(deps.as_mut(), env.clone(), gov.clone(), msg).unwrap();
+    assert_eq!(
+        role_member_count(deps.as_ref().storage, Role::Signer).unwrap(),
+        1
+    );
+
+    let msg = ExecuteMsg::SetSigner {
+        account: Addr::unchecked("signer2"),
+        is_active: true,
+    };
+    execute(deps.as_mut(), env.clone(), gov.clone(), msg).unwrap();
+
+    for min_authorizations in [0u64, 3] {
+        let msg = ExecuteMsg::SetMinAuthorizations {
+            min_authorizations: Uint256::from(min_authorizations),
+        };
+        let res = execute(deps.as_mut(), env.clone(), token_manager.clone(), msg);
+        assert_eq!(
+            res.unwrap_err(),
+            ContractError::InvalidMinAuthorizations {
+                min_authorizations: Uint256::from(min_authorizations),
+                signer_count: 2
+            }
+        );
+    }
+    let msg = ExecuteMsg::SetMinAuthorizations {
+        min_authorizations: Uint256::from(2u64),
+    };
+    execute(deps.as_mut(), env.clone(), token_manager, msg).unwrap();
+
+    // removing a signer can not leave min_authorizations out of reach
+    let msg = ExecuteMsg::SetSigner {
+        account: Addr::unchecked("signer2"),
+        is_active: false,
+    };
+    let res = execute(deps.as_mut(), env, gov, msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::InsufficientSigners {
+            min_authorizations: Uint256::from(2u64)
+        }
+    );
+}
+
+#[test]
+fn test_instantiate() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let creator = mock_info("creator", &[]);
+    let config = Config {
+        price_duration: Uint64::new(300),
+        max_price_update_delay: Uint64::new(3600),
+        ..generate_config()
+    };
+    let token = |token: &str, token_precision: u64| InitialToken {
+        token: Addr::unchecked(token),
+        token_precision: Uint256::from(token_precision),
+    };
+    let msg = InstantiateMsg {
+        gov: Some(Addr::unchecked("gov")),
+        signers: vec![
+            Addr::unchecked("signer1"),
+            Addr::unchecked("signer2"),
+            Addr::unchecked("signer2"),
+            Addr::unchecked("signer3"),
+        ],
+        updaters: vec![Addr::unchecked("updater")],
+        tokens: vec![token("token0", 1), token("token1", 1000)],
+        vault_price_feed: Some(Addr::unchecked("vault_price_feed")),
+        ..instantiate_msg(config.clone())
+    };
+
+    // everything is validated before anything is written
+    for (invalid, err) in [
+        (
+            InstantiateMsg {
+                min_authorizations: Some(Uint256::from(4u64)),
+                ..msg.clone()
+            },
+            ContractError::InvalidMinAuthorizations {
+                min_authorizations: Uint256::from(4u64),
+                signer_count: 3,
+            },
+        ),
+        (
+            InstantiateMsg {
+                tokens: vec![token("token0", 1), token("token0", 1)],
+                ..msg.clone()
+            },
+            ContractError::TokenAlreadyExists {
+                token: "token0".to_string(),
+            },
+        ),
+        (
+            InstantiateMsg {
+                spread_basis_points: Some(SpreadBasisPoint {
+                    spread_basis_points_if_inactive: Uint256::from(10001u64),
+                    spread_basis_points_if_chain_error: Uint256::zero(),
+                }),
+                ..msg.clone()
+            },
+            ContractError::InvalidSpreadBasisPoints {},
+        ),
+        (
+            InstantiateMsg {
+                config: Config {
+                    max_price_update_delay: Uint64::new(300),
+                    ..config.clone()
+                },
+                ..msg.clone()
+            },
+            ContractError::InvalidMaxPriceUpdateDelay {},
+        ),
+        (
+            InstantiateMsg {
+                price_data_interval: Some(Uint64::zero()),
+                ..msg.clone()
+            },
+            ContractError::InvalidPriceDataInterval {},
+        ),
+    ] {
+        let res = instantiate(deps.as_mut(), env.clone(), creator.clone(), invalid);
+        assert_eq!(res.unwrap_err(), err);
+        assert!(!GLOBAL_STATE.exists(deps.as_ref().storage));
+    }
+
+    instantiate(deps.as_mut(), env.clone(), creator.clone(), msg).unwrap();
+    let store = deps.as_ref().storage;
+    assert!(has_role(store, Role::Gov, &Addr::unchecked("gov")));
+    assert!(!has_role(store, Role::Gov, &creator.sender));
+    assert!(has_role(
+        store,
+        Role::TokenManager,
+        &Addr::unchecked("token_manager")
+    ));
+    assert!(has_role(store, Role::Updater, &Addr::unchecked("updater")));
+    assert_eq!(role_member_count(store, Role::Signer).unwrap(), 3);
+    assert_eq!(
+        GLOBAL_STATE
+            .load(store)
+            .unwrap()
+            .min_authorizations
+            .unwrap(),
+        Uint256::from(2u64)
+    );
+    assert_eq!(
+        get_token(deps.as_ref(), Addr::unchecked("token1"))
+            .unwrap()
+            .index,
+        1
+    );
+    assert_eq!(MAX_TIME_DEVIATION.load(store).unwrap(), Uint64::new(3600));
+    assert_eq!(PRICE_DATA_INTERVAL.load(store).unwrap(), Uint64::new(60));
+    assert!(!GLOBAL_STATE.load(store).unwrap().spread_enabled);
+
+    // Initialize is only needed for contracts bootstrapped without signers
+    let msg = ExecuteMsg::Initialize {
+        min_auth: Uint256::one(),
+        signers: vec![],
+        updaters: vec![],
+    };
+    let res = execute(deps.as_mut(), env.clone(), mock_info("gov", &[]), msg);
+    assert_eq!(res.unwrap_err(), ContractError::AlreadyInitialized {});
+
+    // prices can be read right away
+    let price = get_price(
+        deps.as_ref(),
+        Addr::unchecked("token0"),
+        Uint64::new(env.block.time.seconds()),
+        Uint256::from(100u64),
+        true,
+    )
+    .unwrap();
+    assert_eq!(price, Uint256::from(100u64));
+}
+
+#[test]
+fn test_update_config() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let gov = mock_info("gov", &[]);
+    let token_manager = mock_info("token_manager", &[]);
+    let config = Config {
+        price_duration: Uint64::new(300),
+        max_price_update_delay: Uint64::new(3600),
+        ..generate_config()
+    };
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        gov.clone(),
+        instantiate_msg(config),
+    )
+    .unwrap();
+    let update_config = |update: ConfigUpdate| ExecuteMsg::UpdateConfig { update };
+
+    // the token manager can only touch its own fields
+    let msg = update_config(ConfigUpdate {
+        price_duration: Some(Uint64::new(600)),
+        max_deviation_basis_points: Some(Uint256::from(50u64)),
+        ..ConfigUpdate::default()
+    });
+    let res = execute(deps.as_mut(), env.clone(), token_manager.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::Forbidden {});
+    let msg = update_config(ConfigUpdate {
+        max_deviation_basis_points: Some(Uint256::from(50u64)),
+        ..ConfigUpdate::default()
+    });
+    execute(deps.as_mut(), env.clone(), token_manager, msg).unwrap();
+
+    // invariants are checked on the merged values, nothing is saved on failure
+    for (update, err) in [
+        (
+            ConfigUpdate {
+                price_duration: Some(Uint64::new(1200)),
+                max_price_update_delay: Some(Uint64::new(1200)),
+                ..ConfigUpdate::default()
+            },
+            ContractError::InvalidMaxPriceUpdateDelay {},
+        ),
+        (
+            ConfigUpdate {
+                price_duration: Some(Uint64::new(600)),
+                spread_basis_points_if_inactive: Some(Uint256::from(10001u64)),
+                ..ConfigUpdate::default()
+            },
+            ContractError::InvalidSpreadBasisPoints {},
+        ),
+        (
+            ConfigUpdate {
+                price_duration: Some(Uint64::new(600)),
+                price_data_interval: Some(Uint64::zero()),
+                ..ConfigUpdate::default()
+            },
+            ContractError::InvalidPriceDataInterval {},
+        ),
+    ] {
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            gov.clone(),
+            update_config(update),
+        );
+        assert_eq!(res.unwrap_err(), err);
+        assert_eq!(
+            GLOBAL_STATE
+                .load(deps.as_ref().storage)
+                .unwrap()
+                .config
+                .price_duration,
+            Uint64::new(300)
+        );
+    }
+
+    let msg = update_config(ConfigUpdate {
+        price_duration: Some(Uint64::new(600)),
+        max_price_update_delay: Some(Uint64::new(7200)),
+        spread_basis_points_if_chain_error: Some(Uint256::from(100u64)),
+        spread_enabled: Some(true),
+        price_data_interval: Some(Uint64::new(30)),
+        ..ConfigUpdate::default()
+    });
+    let res = execute(deps.as_mut(), env.clone(), gov.clone(), msg).unwrap();
+    let event = &res.events[0];
+    assert_eq!(event.ty, "config_updated");
+    assert_eq!(event.attributes.len(), 10);
+    assert_eq!(event.attributes[0].key, "old_price_duration");
+    assert_eq!(event.attributes[0].value, "300");
+    assert_eq!(event.attributes[1].key, "new_price_duration");
+    assert_eq!(event.attributes[1].value, "600");
+    let config = GLOBAL_STATE.load(deps.as_ref().storage).unwrap().config;
+    assert_eq!(config.max_price_update_delay, Uint64::new(7200));
+    assert_eq!(config.max_deviation_basis_points, Uint256::from(50u64));
+    assert!(
+        GLOBAL_STATE
+            .load(deps.as_ref().storage)
+            .unwrap()
+            .spread_enabled
+    );
+    assert_eq!(
+        PRICE_DATA_INTERVAL.load(deps.as_ref().storage).unwrap(),
+        Uint64::new(30)
+    );
+
+    // timelocked fields still have to be queued unless the change is safer
+    let msg = ExecuteMsg::SetTimelockDelay {
+        timelock_delay: Uint64::new(3600),
+    };
+    execute(deps.as_mut(), env.clone(), gov.clone(), msg).unwrap();
+    let msg = update_config(ConfigUpdate {
+        price_duration: Some(Uint64::new(900)),
+        ..ConfigUpdate::default()
+    });
+    let res = execute(deps.as_mut(), env.clone(), gov.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::TimelockRequired {});
+    let msg = update_config(ConfigUpdate {
+        price_duration: Some(Uint64::new(120)),
+        min_block_interval: Some(Uint64::new(5)),
+        ..ConfigUpdate::default()
+    });
+    execute(deps.as_mut(), env, gov, msg).unwrap();
+}
+
+#[test]
+fn test_ownership_transfer() {
+    let mut deps = mock_dependencies();
+    let mut env = mock_env();
+    let config = Config {
+        price_duration: Uint64::one(),
+        max_price_update_delay: Uint64::new(3600),
+        ..generate_config()
+    };
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("gov", &[]),
+        instantiate_msg(config),
+    )
+    .unwrap();
+    let gov = mock_info("gov", &[]);
+    let new_gov = mock_info("new_gov", &[]);
+    let now = env.block.time.seconds();
+
+    // proposals are offered by the current holder only
+    let propose = |expires_at: Option<u64>| ExecuteMsg::ProposeOwnership {
+        role: Role::Gov,
+        new_owner: new_gov.sender.clone(),
+        expires_at: expires_at.map(Uint64::new),
+    };
+    let res = execute(deps.as_mut(), env.clone(), new_gov.clone(), propose(None));
+    assert_eq!(res.unwrap_err(), ContractError::Forbidden {});
+    let res = execute(deps.as_mut(), env.clone(), gov.clone(), propose(Some(now)));
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::InvalidOwnershipTransferExpiry {}
+    );
+
+    // a stale proposal can not be accepted
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        gov.clone(),
+        propose(Some(now + 60)),
+    )
+    .unwrap();
+    let accept = ExecuteMsg::AcceptOwnership { role: Role::Gov };
+    let res = execute(deps.as_mut(), env.clone(), gov.clone(), accept.clone());
+    assert_eq!(res.unwrap_err(), ContractError::Forbidden {});
+    env.block.time = env.block.time.plus_seconds(60);
+    let res = execute(deps.as_mut(), env.clone(), new_gov.clone(), accept.clone());
+    assert_eq!(res.unwrap_err(), ContractError::OwnershipTransferExpired {});
+
+    let cancel = ExecuteMsg::CancelOwnershipTransfer { role: Role::Gov };
+    execute(deps.as_mut(), env.clone(), gov.clone(), cancel.clone()).unwrap();
+    let res = execute(deps.as_mut(), env.clone(), gov.clone(), cancel);
+    assert_eq!(res.unwrap_err(), ContractError::NoPendingOwner {});
+
+    execute(deps.as_mut(), env.clone(), gov.clone(), propose(None)).unwrap();
+    execute(deps.as_mut(), env.clone(), new_gov.clone(), accept).unwrap();
+    assert!(has_role(deps.as_ref().storage, Role::Gov, &new_gov.sender));
+    assert!(!PENDING_OWNERS.has(deps.as_ref().storage, Role::Gov.key()));
+    assert_eq!(
+        is_gov(deps.as_ref(), &gov.sender),
+        Err(ContractError::Forbidden {})
+    );
+
+    // renouncing leaves the role without holder
+    let renounce = ExecuteMsg::RenounceRole {
+        role: Role::TokenManager,
+    };
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("token_manager", &[]),
+        renounce,
+    )
+    .unwrap();
+    assert_eq!(
+        only_token_manager(deps.as_ref(), &Addr::unchecked("token_manager")),
+        Err(ContractError::Forbidden {})
+    );
+}
+
 #[test]
 fn test_set_updater() {
     let mut deps = mock_dependencies();
@@ -345,7 +1075,7 @@ fn test_set_updater() {
     let res = execute(deps.as_mut(), env, info, msg);
     assert!(res.is_ok());
 
-    let status = IS_UPDATER.load(deps.as_ref().storage, &account).unwrap();
+    let status = has_role(deps.as_ref().storage, Role::Updater, &account);
     assert!(status);
 }
 
@@ -357,9 +1087,7 @@ fn test_set_fast_price_events() {
     let env = mock_env();
 
     //init with defualt config
-    CONFIG
-        .save(deps.as_mut().storage, &generate_config())
-        .unwrap();
+    save_config(deps.as_mut().storage, generate_config());
 
     let msg = ExecuteMsg::SetFastPriceEvents {
         fast_price_events: account.clone(),
@@ -367,7 +1095,7 @@ fn test_set_fast_price_events() {
     let res = execute(deps.as_mut(), env, info, msg);
     assert!(res.is_ok());
 
-    let config = CONFIG.load(deps.as_ref().storage).unwrap();
+    let config = GLOBAL_STATE.load(deps.as_ref().storage).unwrap().config;
     assert_eq!(config.fast_price_events, account);
 }
 
@@ -388,6 +1116,68 @@ fn test_set_vault_price_feed() {
     assert_eq!(vault, account);
 }
 
+#[test]
+fn test_set_primary_price_feed() {
+    let mut deps = mock_dependencies();
+    let gov = setup_with_gov(deps.as_mut());
+    let env = mock_env();
+    let token = setup_with_tokens(deps.as_mut(), 1).remove(0);
+    let price_feed = Addr::unchecked("price_feed");
+
+    // only registered tokens get a feed
+    let msg = ExecuteMsg::SetPrimaryPriceFeed {
+        token: Addr::unchecked("unknown"),
+        price_feed: Some(price_feed.clone()),
+    };
+    let res = execute(deps.as_mut(), env.clone(), gov.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::TokenNotFound {
+            token: "unknown".to_string()
+        }
+    );
+
+    let msg = ExecuteMsg::SetPrimaryPriceFeed {
+        token: token.clone(),
+        price_feed: Some(price_feed.clone()),
+    };
+    execute(deps.as_mut(), env.clone(), gov.clone(), msg).unwrap();
+    let res = PRIMARY_PRICE_FEEDS
+        .may_load(deps.as_ref().storage, &token)
+        .unwrap();
+    assert_eq!(res, Some(price_feed));
+
+    let msg = ExecuteMsg::SetPrimaryPriceFeed {
+        token: token.clone(),
+        price_feed: None,
+    };
+    execute(deps.as_mut(), env.clone(), gov, msg).unwrap();
+    let res = PRIMARY_PRICE_FEEDS
+        .may_load(deps.as_ref().storage, &token)
+        .unwrap();
+    assert_eq!(res, None);
+
+    // without a feed of its own the token falls back to the vault price feed
+    let query_feed = |deps: Deps| -> Option<Addr> {
+        from_binary(
+            &query(
+                deps,
+                env.clone(),
+                QueryMsg::PrimaryPriceFeed {
+                    token: token.clone(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+    assert_eq!(query_feed(deps.as_ref()), None);
+    VAULT_ADDRESS
+        .save(deps.as_mut().storage, &Addr::unchecked("vault"))
+        .unwrap();
+    assert_eq!(query_feed(deps.as_ref()), Some(Addr::unchecked("vault")));
+}
+
 #[test]
 fn test_max_time_deviation() {
     let mut deps = mock_dependencies();
@@ -401,7 +1191,7 @@ fn test_max_time_deviation() {
     assert!(res.is_ok());
 
     let time_diviation = MAX_TIME_DEVIATION.load(deps.as_ref().storage).unwrap();
-    assert_eq!(time_diviation, 1);
+    assert_eq!(time_diviation, Uint64::one());
 }
 
 #[test]
@@ -410,9 +1200,23 @@ fn test_set_price_duration() {
     let info = setup_with_gov(deps.as_mut());
     let env = mock_env();
 
-    CONFIG
-        .save(deps.as_mut().storage, &generate_config())
-        .unwrap();
+    save_config(
+        deps.as_mut().storage,
+        Config {
+            max_price_update_delay: Uint64::new(600),
+            ..generate_config()
+        },
+    );
+
+    // a fast price has to expire before the max update delay
+    let msg = ExecuteMsg::SetPriceDuration {
+        price_duration: Uint64::new(600),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::InvalidMaxPriceUpdateDelay {}
+    );
 
     let msg = ExecuteMsg::SetPriceDuration {
         price_duration: Uint64::one(),
@@ -420,7 +1224,7 @@ fn test_set_price_duration() {
     let res = execute(deps.as_mut(), env, info, msg);
     assert!(res.is_ok());
 
-    let config = CONFIG.load(deps.as_ref().storage).unwrap();
+    let config = GLOBAL_STATE.load(deps.as_ref().storage).unwrap().config;
     assert_eq!(config.price_duration.u64(), 1);
 }
 
@@ -430,18 +1234,31 @@ fn test_set_max_price_update_delay() {
     let info = setup_with_gov(deps.as_mut());
     let env = mock_env();
 
-    CONFIG
-        .save(deps.as_mut().storage, &generate_config())
-        .unwrap();
+    save_config(
+        deps.as_mut().storage,
+        Config {
+            price_duration: Uint64::one(),
+            ..generate_config()
+        },
+    );
 
     let msg = ExecuteMsg::SetMaxPriceUpdateDelay {
         max_price_update_delay: Uint64::one(),
     };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::InvalidMaxPriceUpdateDelay {}
+    );
+
+    let msg = ExecuteMsg::SetMaxPriceUpdateDelay {
+        max_price_update_delay: Uint64::new(2),
+    };
     let res = execute(deps.as_mut(), env, info, msg);
     assert!(res.is_ok());
 
-    let config = CONFIG.load(deps.as_ref().storage).unwrap();
-    assert_eq!(config.max_price_update_delay.u64(), 1);
+    let config = GLOBAL_STATE.load(deps.as_ref().storage).unwrap().config;
+    assert_eq!(config.max_price_update_delay.u64(), 2);
 }
 
 #[test]
@@ -450,41 +1267,222 @@ fn test_set_last_updated_at() {
     let info = setup_with_gov(deps.as_mut());
     let env = mock_env();
 
-    CONFIG
-        .save(deps.as_mut().storage, &generate_config())
-        .unwrap();
+    save_config(deps.as_mut().storage, generate_config());
 
     let msg = ExecuteMsg::SetLastUpdatedAt {
+        token: None,
         last_updated_at: Uint64::one(),
     };
-    let res = execute(deps.as_mut(), env, info, msg);
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
     assert!(res.is_ok());
 
     let config = LAST_UPDATED.load(deps.as_ref().storage).unwrap();
-    assert_eq!(config.last_updated_at, 1);
+    assert_eq!(config.last_updated_at, Uint64::one());
+
+    let token = Addr::unchecked("token");
+    let msg = ExecuteMsg::SetLastUpdatedAt {
+        token: Some(token.clone()),
+        last_updated_at: Uint64::new(2),
+    };
+    execute(deps.as_mut(), env, info, msg).unwrap();
+
+    let last_updated = get_last_updated(deps.as_ref(), Some(token)).unwrap();
+    assert_eq!(last_updated.last_updated_at, Uint64::new(2));
+    let last_updated = get_last_updated(deps.as_ref(), None).unwrap();
+    assert_eq!(last_updated.last_updated_at, Uint64::one());
 }
 
 #[test]
-fn test_set_basis_points() {
+fn test_timelock() {
     let mut deps = mock_dependencies();
     let info = setup_with_gov(deps.as_mut());
-    let env = mock_env();
-
-    let msg = ExecuteMsg::SetSpreadBasisPointsIfChainError {
-        spread_basis_points_if_chain_error: Uint256::one(),
-    };
-    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
-    assert!(res.is_ok());
+    let mut env = mock_env();
+    save_config(
+        deps.as_mut().storage,
+        Config {
+            price_duration: Uint64::new(300),
+            max_price_update_delay: Uint64::new(3600),
+            ..generate_config()
+        },
+    );
 
+    // without a delay every change applies immediately
     let msg = ExecuteMsg::SetSpreadBasisPointsIfInactive {
-        spread_basis_points_if_inactive: Uint256::one(),
+        spread_basis_points_if_inactive: Uint256::from(10u64),
     };
-    let res = execute(deps.as_mut(), env, info, msg);
-    assert!(res.is_ok());
+    execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
-    let spread = SPREAD_BASIS_POINT_STATE
-        .load(deps.as_ref().storage)
-        .unwrap();
+    let msg = ExecuteMsg::SetTimelockDelay {
+        timelock_delay: Uint64::new(MAX_TIMELOCK_DELAY + 1),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::InvalidTimelockDelay {});
+    let msg = ExecuteMsg::SetTimelockDelay {
+        timelock_delay: Uint64::new(3600),
+    };
+    execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // riskier moves have to be queued, safer ones still apply at once
+    for msg in [
+        ExecuteMsg::SetPriceDuration {
+            price_duration: Uint64::new(600),
+        },
+        ExecuteMsg::SetSpreadBasisPointsIfInactive {
+            spread_basis_points_if_inactive: Uint256::zero(),
+        },
+        ExecuteMsg::SetIsSpreadEnabled {
+            spread_enabled: false,
+        },
+        ExecuteMsg::SetTimelockDelay {
+            timelock_delay: Uint64::zero(),
+        },
+    ] {
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+        assert_eq!(res.unwrap_err(), ContractError::TimelockRequired {});
+    }
+    let msg = ExecuteMsg::SetIsSpreadEnabled {
+        spread_enabled: true,
+    };
+    execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::SetSpreadBasisPointsIfChainError {
+        spread_basis_points_if_chain_error: Uint256::from(50u64),
+    };
+    execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::SetPriceDuration {
+        price_duration: Uint64::new(120),
+    };
+    execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    assert!(
+        GLOBAL_STATE
+            .load(deps.as_ref().storage)
+            .unwrap()
+            .spread_enabled
+    );
+
+    let queue = |action: TimelockAction| ExecuteMsg::QueueAction { action };
+    let msg = queue(TimelockAction::SetPriceDuration {
+        price_duration: Uint64::new(600),
+    });
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("user", &[]),
+        msg.clone(),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Forbidden {});
+    execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let msg = queue(TimelockAction::SetSpreadBasisPointsIfInactive {
+        spread_basis_points_if_inactive: Uint256::from(10001u64),
+    });
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::InvalidSpreadBasisPoints {});
+    let msg = queue(TimelockAction::SetIsSpreadEnabled {
+        spread_enabled: false,
+    });
+    execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let actions = pending_actions(deps.as_ref(), None, None).unwrap();
+    let eta = Uint64::new(env.block.time.seconds() + 3600);
+    assert_eq!(
+        actions,
+        vec![
+            PendingAction {
+                id: 0,
+                action: TimelockAction::SetPriceDuration {
+                    price_duration: Uint64::new(600),
+                },
+                eta,
+            },
+            PendingAction {
+                id: 1,
+                action: TimelockAction::SetIsSpreadEnabled {
+                    spread_enabled: false,
+                },
+                eta,
+            },
+        ]
+    );
+    let actions = pending_actions(deps.as_ref(), Some(0), None).unwrap();
+    assert_eq!(actions.len(), 1);
+
+    // actions wait for their eta and can be cancelled until executed
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::ExecuteAction { id: 0 },
+    );
+    assert_eq!(res.unwrap_err(), ContractError::TimelockNotReady { eta });
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::CancelAction { id: 1 },
+    )
+    .unwrap();
+
+    env.block.time = env.block.time.plus_seconds(3600);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::ExecuteAction { id: 0 },
+    )
+    .unwrap();
+    assert_eq!(
+        GLOBAL_STATE
+            .load(deps.as_ref().storage)
+            .unwrap()
+            .config
+            .price_duration,
+        Uint64::new(600)
+    );
+    for id in [0, 1] {
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::ExecuteAction { id },
+        );
+        assert_eq!(
+            res.unwrap_err(),
+            ContractError::TimelockActionNotFound { id }
+        );
+    }
+    assert!(
+        GLOBAL_STATE
+            .load(deps.as_ref().storage)
+            .unwrap()
+            .spread_enabled
+    );
+    assert!(pending_actions(deps.as_ref(), None, None)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_set_basis_points() {
+    let mut deps = mock_dependencies();
+    save_config(deps.as_mut().storage, generate_config());
+    let info = setup_with_gov(deps.as_mut());
+    let env = mock_env();
+
+    let msg = ExecuteMsg::SetSpreadBasisPointsIfChainError {
+        spread_basis_points_if_chain_error: Uint256::one(),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    assert!(res.is_ok());
+
+    let msg = ExecuteMsg::SetSpreadBasisPointsIfInactive {
+        spread_basis_points_if_inactive: Uint256::one(),
+    };
+    let res = execute(deps.as_mut(), env, info, msg);
+    assert!(res.is_ok());
+
+    let spread = GLOBAL_STATE
+        .load(deps.as_ref().storage)
+        .unwrap()
+        .spread_basis_points;
 
     assert_eq!(spread.spread_basis_points_if_chain_error, Uint256::one());
     assert_eq!(spread.spread_basis_points_if_inactive, Uint256::one());
@@ -499,33 +1497,2745 @@ fn test_set_prices() {
     let tokens = vec![Addr::unchecked("token0"), Addr::unchecked("token1")];
     let prices = vec![Uint256::from(100u64), Uint256::from(200u64)];
     let timestamp = env.block.time;
+    mock_vault_price_feed(&mut deps, 100_000_000, env.block.time.seconds());
 
-    CONFIG
-        .save(deps.as_mut().storage, &generate_config())
-        .unwrap();
+    save_config(deps.as_mut().storage, generate_config());
+    setup_with_tokens(deps.as_mut(), 2);
     MAX_TIME_DEVIATION
-        .save(deps.as_mut().storage, &1000u64)
+        .save(deps.as_mut().storage, &Uint64::new(1000))
         .unwrap();
-    VAULT_ADDRESS
-        .save(deps.as_mut().storage, &Addr::unchecked("vault"))
+    PRIMARY_PRICE_FEEDS
+        .save(deps.as_mut().storage, &tokens[0], &Addr::unchecked("vault"))
         .unwrap();
     PRICE_DATA_INTERVAL
         .save(deps.as_mut().storage, &Uint64::one())
         .unwrap();
 
     let msg = ExecuteMsg::SetPrices {
-        tokens,
+        tokens: tokens.clone(),
         prices: prices.clone(),
         timestamp: Uint64::from(timestamp.seconds()),
     };
-    let res = execute(deps.as_mut(), env, info, msg);
-    assert!(res.is_ok());
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+    // the written prices are reported with a single message and event
+    assert_eq!(res.messages.len(), 1);
+    match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => match from_binary(msg).unwrap() {
+            FastPriceEventsExecuteMsg::EmitPriceEvents { prices: emitted } => {
+                let emitted: Vec<Uint256> =
+                    emitted.into_iter().map(|update| update.price).collect();
+                assert_eq!(emitted, prices)
+            }
+            _ => panic!("unexpected message"),
+        },
+        _ => panic!("unexpected message"),
+    }
+    assert_eq!(res.events.len(), 1);
+    assert_eq!(res.events[0].ty, "prices_updated");
 
     // check prices
-    for i in 0..prices.len() {
-        let price = PRICES
-            .load(&deps.storage, &Addr::unchecked(format!("token{}", i)))
-            .unwrap();
-        assert_eq!(price, prices[i]);
+    for (i, expected) in prices.iter().enumerate() {
+        let price = load_token_state(&deps.storage, &Addr::unchecked(format!("token{}", i)))
+            .unwrap()
+            .price;
+        assert_eq!(price, *expected);
+    }
+
+    // only the written tokens are marked as updated
+    let last_updated = get_last_updated(deps.as_ref(), Some(Addr::unchecked("token0"))).unwrap();
+    assert_eq!(last_updated.last_updated_at.u64(), timestamp.seconds());
+    let last_updated = get_last_updated(deps.as_ref(), Some(Addr::unchecked("token2"))).unwrap();
+    assert_eq!(last_updated, LastUpdated::default());
+
+    // the primary price is recorded as ref price
+    let price_data = load_token_state(&deps.storage, &Addr::unchecked("token0"))
+        .unwrap()
+        .price_data;
+    assert_eq!(price_data.ref_price, crate::execute::PRICE_PRECISION);
+    // a token without a primary price feed is not compared
+    let price_data = load_token_state(&deps.storage, &Addr::unchecked("token1"))
+        .unwrap()
+        .price_data;
+    assert_eq!(price_data, PriceDataItem::default());
+}
+
+#[test]
+fn test_set_prices_with_stale_primary_price() {
+    let mut deps = mock_dependencies();
+    let info = setup_with_updater(deps.as_mut());
+    let env = mock_env();
+    let now = env.block.time.seconds();
+
+    save_config(deps.as_mut().storage, generate_config());
+    let tokens = setup_with_tokens(deps.as_mut(), 2);
+    MAX_TIME_DEVIATION
+        .save(deps.as_mut().storage, &Uint64::new(1000))
+        .unwrap();
+    PRICE_DATA_INTERVAL
+        .save(deps.as_mut().storage, &Uint64::one())
+        .unwrap();
+    PRIMARY_PRICE_CONFIG
+        .save(
+            deps.as_mut().storage,
+            &PrimaryPriceConfig {
+                price_decimals: 8,
+                max_price_age: Uint64::new(60),
+            },
+        )
+        .unwrap();
+    // both tokens fall back to the vault price feed
+    VAULT_ADDRESS
+        .save(deps.as_mut().storage, &Addr::unchecked("vault"))
+        .unwrap();
+    let msg = ExecuteMsg::SetPrices {
+        tokens: tokens.clone(),
+        prices: vec![Uint256::from(100u64), Uint256::from(200u64)],
+        timestamp: Uint64::new(now),
+    };
+
+    mock_vault_price_feed(&mut deps, 100_000_000, now);
+    execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    for token in tokens.iter() {
+        let price_data = load_token_state(&deps.storage, token).unwrap().price_data;
+        assert_eq!(price_data.ref_price, crate::execute::PRICE_PRECISION);
+    }
+
+    // a stale round keeps the last reference data and is reported per token
+    mock_vault_price_feed(&mut deps, 200_000_000, now - 61);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    let stale: Vec<_> = res
+        .events
+        .iter()
+        .filter(|event| event.ty == "primary_price_stale")
+        .collect();
+    assert_eq!(stale.len(), 2);
+    assert_eq!(
+        stale[0].attributes,
+        vec![
+            Attribute::new("token", "token0"),
+            Attribute::new("price_feed", "vault"),
+            Attribute::new("updated_at", (now - 61).to_string()),
+        ]
+    );
+    for token in tokens.iter() {
+        let price_data = load_token_state(&deps.storage, token).unwrap().price_data;
+        assert_eq!(price_data.ref_price, crate::execute::PRICE_PRECISION);
+    }
+    assert_eq!(
+        load_token_state(&deps.storage, &tokens[1]).unwrap().price,
+        Uint256::from(200u64)
+    );
+}
+
+#[test]
+fn test_set_prices_errors() {
+    let mut deps = mock_dependencies();
+    let info = setup_with_updater(deps.as_mut());
+    let env = mock_env();
+    let now = Uint64::from(env.block.time.seconds());
+    let token = Addr::unchecked("token0");
+    mock_vault_price_feed(&mut deps, 100_000_000, env.block.time.seconds());
+    let msg = ExecuteMsg::SetPrices {
+        tokens: vec![token.clone()],
+        prices: vec![Uint256::from(100u64)],
+        timestamp: now,
+    };
+
+    save_config(deps.as_mut().storage, generate_config());
+    MAX_TIME_DEVIATION
+        .save(deps.as_mut().storage, &Uint64::new(1000))
+        .unwrap();
+    // prices are only written for registered tokens
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::TokenNotFound {
+            token: token.to_string()
+        }
+    );
+    setup_with_tokens(deps.as_mut(), 1);
+
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::Uninitialized {
+            item: "price_data_interval".to_string()
+        }
+    );
+    PRIMARY_PRICE_FEEDS
+        .save(deps.as_mut().storage, &token, &Addr::unchecked("vault"))
+        .unwrap();
+
+    // a zero interval can only come from storage, the setter rejects it
+    let token_manager = mock_info("token_manager", &[]);
+    set_role_member(
+        deps.as_mut().storage,
+        Role::TokenManager,
+        &token_manager.sender,
+        true,
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        token_manager,
+        ExecuteMsg::SetPriceDataInterval {
+            price_data_interval: Uint64::zero(),
+        },
+    );
+    assert_eq!(res.unwrap_err(), ContractError::InvalidPriceDataInterval {});
+    PRICE_DATA_INTERVAL
+        .save(deps.as_mut().storage, &Uint64::zero())
+        .unwrap();
+    let price_data = PriceDataItem {
+        ref_price: PRICE_PRECISION * Uint256::from(2u64),
+        ref_time: now,
+        ..PriceDataItem::default()
+    };
+    update_token_state(deps.as_mut().storage, &token, |state| {
+        state.price_data = price_data.clone()
+    })
+    .unwrap();
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    assert_eq!(res.unwrap_err(), ContractError::InvalidPriceDataInterval {});
+
+    // the cumulative deltas are checked for overflow
+    PRICE_DATA_INTERVAL
+        .save(deps.as_mut().storage, &Uint64::new(3600))
+        .unwrap();
+    update_token_state(deps.as_mut().storage, &token, |state| {
+        state.price_data = PriceDataItem {
+            cumulative_ref_delta: Uint256::MAX,
+            ..price_data.clone()
+        }
+    })
+    .unwrap();
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    assert!(matches!(res.unwrap_err(), ContractError::Overflow(_)));
+
+    // a zero fast price leaves the fast delta untouched instead of dividing by it
+    update_token_state(deps.as_mut().storage, &token, |state| {
+        state.price_data = price_data;
+        state.price = Uint256::zero();
+    })
+    .unwrap();
+    execute(deps.as_mut(), env, info, msg).unwrap();
+    let price_data = load_token_state(deps.as_ref().storage, &token)
+        .unwrap()
+        .price_data;
+    assert_eq!(price_data.cumulative_ref_delta, Uint256::from(5_000_000u64));
+    assert_eq!(price_data.cumulative_fast_delta, Uint256::zero());
+}
+
+#[test]
+fn test_get_latest_primary_price() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let vault = Addr::unchecked("vault");
+    let now = Uint64::from(env.block.time.seconds());
+    let config = PrimaryPriceConfig {
+        price_decimals: 8,
+        max_price_age: Uint64::new(60),
+    };
+
+    // 1500.5 with 8 decimals is scaled to PRICE_PRECISION
+    mock_vault_price_feed(&mut deps, 150_050_000_000, now.u64());
+    let price = get_latest_primary_price(&deps.as_ref().querier, &vault, &config, now).unwrap();
+    assert_eq!(
+        price,
+        crate::execute::PRICE_PRECISION.multiply_ratio(15005u128, 10u128)
+    );
+
+    mock_vault_price_feed(&mut deps, -1, now.u64());
+    let res = get_latest_primary_price(&deps.as_ref().querier, &vault, &config, now);
+    assert_eq!(res.unwrap_err(), ContractError::PrimaryPriceNegative {});
+
+    mock_vault_price_feed(&mut deps, 0, now.u64());
+    let res = get_latest_primary_price(&deps.as_ref().querier, &vault, &config, now);
+    assert_eq!(res.unwrap_err(), ContractError::PrimaryPriceMissing {});
+
+    mock_vault_price_feed(&mut deps, 100, now.u64() - 61);
+    let res = get_latest_primary_price(&deps.as_ref().querier, &vault, &config, now);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::PrimaryPriceStale {
+            updated_at: now - Uint64::new(61)
+        }
+    );
+
+    let config = PrimaryPriceConfig {
+        price_decimals: 31,
+        max_price_age: Uint64::new(60),
+    };
+    assert_eq!(
+        validate_primary_price_config(&config).unwrap_err(),
+        ContractError::InvalidPriceDecimals {}
+    );
+}
+
+#[test]
+fn test_set_prices_with_bits_and_execute() {
+    let mut deps = mock_dependencies();
+    let info = setup_with_updater(deps.as_mut());
+    let env = mock_env();
+    let router = Addr::unchecked("position_router");
+
+    deps.querier.update_wasm(|query| match query {
+        WasmQuery::Smart { contract_addr, .. } if contract_addr == "position_router" => {
+            SystemResult::Ok(ContractResult::Ok(
+                to_binary(&PositionRequestKeysStartResponse {
+                    increase_position_request_keys_start: Uint256::from(10u64),
+                    decrease_position_request_keys_start: Uint256::from(20u64),
+                })
+                .unwrap(),
+            ))
+        }
+        _ => panic!("unexpected query"),
+    });
+
+    set_role_member(
+        deps.as_mut().storage,
+        Role::Gov,
+        &Addr::unchecked("gov"),
+        true,
+    )
+    .unwrap();
+    save_config(deps.as_mut().storage, generate_config());
+    MAX_TIME_DEVIATION
+        .save(deps.as_mut().storage, &Uint64::new(1000))
+        .unwrap();
+    PRICE_DATA_INTERVAL
+        .save(deps.as_mut().storage, &Uint64::new(60))
+        .unwrap();
+
+    let msg = ExecuteMsg::SetPricesWithBitsAndExecute {
+        position_router_addr: router.clone(),
+        price_bits: Uint256::zero(),
+        timestamp: Uint64::from(env.block.time.seconds()),
+        end_index_for_increase_positions: Uint256::from(100u64),
+        end_index_for_decrease_positions: Uint256::from(21u64),
+        max_increase_positions: Uint256::from(5u64),
+        max_decrease_positions: Uint256::from(5u64),
+        layout: None,
+    };
+
+    // the router must be allowlisted by gov
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::InvalidPositionRouter {
+            position_router: router.to_string()
+        }
+    );
+
+    let msg_set_router = ExecuteMsg::SetPositionRouter {
+        position_router: router.clone(),
+        is_active: true,
+    };
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("gov", &[]),
+        msg_set_router,
+    )
+    .unwrap();
+
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let expected = [
+        PositionRouterExecuteMsg::ExecuteIncreasePositions {
+            end_index: Uint256::from(15u64),
+            execution_fee_receiver: info.sender.clone(),
+        },
+        PositionRouterExecuteMsg::ExecuteDecreasePositions {
+            end_index: Uint256::from(21u64),
+            execution_fee_receiver: info.sender.clone(),
+        },
+    ];
+    assert_eq!(res.messages.len(), expected.len());
+    for (sub_msg, expected) in res.messages.iter().zip(expected.iter()) {
+        match &sub_msg.msg {
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr, msg, ..
+            }) => {
+                assert_eq!(contract_addr, router.as_str());
+                let msg: PositionRouterExecuteMsg = from_binary(msg).unwrap();
+                assert_eq!(&msg, expected);
+            }
+            _ => panic!("unexpected message"),
+        }
+    }
+
+    // the updater supplied bounds cannot overflow the end indexes
+    let msg = ExecuteMsg::SetPricesWithBitsAndExecute {
+        position_router_addr: router,
+        price_bits: Uint256::zero(),
+        timestamp: Uint64::from(env.block.time.seconds()),
+        end_index_for_increase_positions: Uint256::from(100u64),
+        end_index_for_decrease_positions: Uint256::from(21u64),
+        max_increase_positions: Uint256::MAX,
+        max_decrease_positions: Uint256::from(5u64),
+        layout: None,
+    };
+    let res = execute(deps.as_mut(), env, info, msg);
+    assert!(matches!(res.unwrap_err(), ContractError::Overflow(_)));
+}
+
+#[test]
+fn test_token_registry() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let tokens = setup_with_tokens(deps.as_mut(), 3);
+    let info = mock_info("token_manager", &[]);
+
+    // only the token manager can list tokens
+    let msg = ExecuteMsg::AddToken {
+        token: Addr::unchecked("token3"),
+        token_precision: Uint256::one(),
+    };
+    let res = execute(deps.as_mut(), env.clone(), mock_info("random", &[]), msg);
+    assert_eq!(res.unwrap_err(), ContractError::Forbidden {});
+
+    let msg = ExecuteMsg::AddToken {
+        token: tokens[0].clone(),
+        token_precision: Uint256::one(),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::TokenAlreadyExists {
+            token: tokens[0].to_string()
+        }
+    );
+
+    let msg = ExecuteMsg::UpdateTokenPrecision {
+        token: tokens[2].clone(),
+        token_precision: Uint256::from(1000u64),
+    };
+    execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    assert_eq!(
+        get_token(deps.as_ref(), tokens[2].clone())
+            .unwrap()
+            .token_precision,
+        Uint256::from(1000u64)
+    );
+
+    // delisting cleans up the token state but keeps the other indexes stable
+    update_token_state(deps.as_mut().storage, &tokens[1], |state| {
+        state.price = Uint256::one();
+        state.price_data = PriceDataItem::default();
+        state.max_cumulative_delta_diff = Uint256::one();
+    })
+    .unwrap();
+    let msg = ExecuteMsg::RemoveToken {
+        token: tokens[1].clone(),
+    };
+    execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    assert!(!TOKEN_STATES.has(deps.as_ref().storage, &tokens[1]));
+    assert!(!TOKEN_STATES.has(deps.as_ref().storage, &tokens[1]));
+    assert!(!TOKEN_STATES.has(deps.as_ref().storage, &tokens[1]));
+
+    let msg = ExecuteMsg::RemoveToken {
+        token: tokens[1].clone(),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::TokenNotFound {
+            token: tokens[1].to_string()
+        }
+    );
+
+    let msg = ExecuteMsg::AddToken {
+        token: Addr::unchecked("token3"),
+        token_precision: Uint256::one(),
+    };
+    execute(deps.as_mut(), env, info, msg).unwrap();
+
+    let indexes: Vec<u32> = get_token_indexes(deps.as_ref(), None, None)
+        .unwrap()
+        .iter()
+        .map(|token_data| token_data.index)
+        .collect();
+    assert_eq!(indexes, vec![0, 2, 3]);
+
+    let page = get_tokens(deps.as_ref(), Some(tokens[0].clone()), Some(1)).unwrap();
+    assert_eq!(page.len(), 1);
+    assert_eq!(page[0].token, tokens[2]);
+
+    let list = load_token_index_list(deps.as_ref().storage).unwrap();
+    assert_eq!(list.len(), 4);
+    assert!(list[1].is_none());
+}
+
+#[test]
+fn test_get_price_per_token_staleness() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let now = env.block.time.seconds();
+    let fresh_token = Addr::unchecked("fresh");
+    let stale_token = Addr::unchecked("stale");
+    let ref_price = Uint256::from(10_000u64);
+    let fast_price = Uint256::from(10_010u64);
+
+    let mut config = generate_config();
+    config.price_duration = Uint64::new(300);
+    config.max_price_update_delay = Uint64::new(3600);
+    config.max_deviation_basis_points = Uint256::from(100u64);
+    save_config(deps.as_mut().storage, config);
+    update_global_state(deps.as_mut().storage, |global| {
+        global.spread_basis_points = SpreadBasisPoint {
+            spread_basis_points_if_inactive: Uint256::from(10u64),
+            spread_basis_points_if_chain_error: Uint256::from(100u64),
+        };
+        global.spread_enabled = false;
+        global.min_authorizations = Some(Uint256::one());
+    })
+    .unwrap();
+    for (token, age) in [(&fresh_token, 10), (&stale_token, 600)] {
+        update_token_state(deps.as_mut().storage, token, |state| {
+            state.price = fast_price;
+            state.price_data = PriceDataItem::default();
+            state.max_cumulative_delta_diff = Uint256::zero();
+            state.last_updated = LastUpdated {
+                last_updated_at: Uint64::new(now - age),
+                last_updated_block: env.block.height,
+            };
+        })
+        .unwrap();
+    }
+
+    // the fresh token uses its fast price
+    let price = get_price(
+        deps.as_ref(),
+        fresh_token,
+        Uint64::new(now),
+        ref_price,
+        true,
+    )
+    .unwrap();
+    assert_eq!(price, fast_price);
+
+    // the stale token gets the inactive spread on the ref price
+    let price = get_price(
+        deps.as_ref(),
+        stale_token,
+        Uint64::new(now),
+        ref_price,
+        true,
+    )
+    .unwrap();
+    assert_eq!(price, Uint256::from(10_010u64));
+
+    // a token that was never written gets the chain error spread
+    update_token_state(deps.as_mut().storage, &Addr::unchecked("new"), |state| {
+        state.price = fast_price
+    })
+    .unwrap();
+    let price = get_price(
+        deps.as_ref(),
+        Addr::unchecked("new"),
+        Uint64::new(now),
+        ref_price,
+        true,
+    )
+    .unwrap();
+    assert_eq!(price, Uint256::from(10_100u64));
+}
+
+#[test]
+fn test_get_price_checked_arithmetic() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let now = env.block.time.seconds();
+    let token = Addr::unchecked("token");
+
+    let mut config = generate_config();
+    config.price_duration = Uint64::new(300);
+    config.max_price_update_delay = Uint64::MAX;
+    save_config(deps.as_mut().storage, config);
+    update_token_state(deps.as_mut().storage, &token, |state| {
+        state.price = Uint256::from(10_000u64);
+        state.last_updated.last_updated_at = Uint64::new(now);
+    })
+    .unwrap();
+
+    // the update delay overflows the last update time
+    let res = get_price(
+        deps.as_ref(),
+        token.clone(),
+        Uint64::new(now),
+        Uint256::from(10_000u64),
+        true,
+    );
+    assert!(matches!(res.unwrap_err(), ContractError::Overflow(_)));
+
+    // a zero ref price cannot be compared against
+    update_global_state(deps.as_mut().storage, |global| {
+        global.config.max_price_update_delay = Uint64::new(3600)
+    })
+    .unwrap();
+    let res = get_price(
+        deps.as_ref(),
+        token.clone(),
+        Uint64::new(now),
+        Uint256::zero(),
+        true,
+    );
+    assert!(matches!(res.unwrap_err(), ContractError::DivideByZero(_)));
+
+    // a spread above the divisor cannot be taken off the ref price
+    update_token_state(deps.as_mut().storage, &token, |state| {
+        state.last_updated.last_updated_at = Uint64::zero();
+        state.params.spread_basis_points_if_chain_error = Some(Uint256::from(10_001u64));
+    })
+    .unwrap();
+    let res = get_price(
+        deps.as_ref(),
+        token,
+        Uint64::new(now),
+        Uint256::from(10_000u64),
+        false,
+    );
+    assert!(matches!(res.unwrap_err(), ContractError::Overflow(_)));
+
+    // every token needs its own diff
+    let token_manager = mock_info("token_manager", &[]);
+    set_role_member(
+        deps.as_mut().storage,
+        Role::TokenManager,
+        &token_manager.sender,
+        true,
+    )
+    .unwrap();
+    let msg = ExecuteMsg::SetMaxCumulativeDeltaDiffs {
+        tokens: vec![Addr::unchecked("token0"), Addr::unchecked("token1")],
+        max_cumulative_delta_diffs: vec![Uint256::one()],
+    };
+    let res = execute(deps.as_mut(), env, token_manager, msg);
+    assert_eq!(res.unwrap_err(), ContractError::InvalidLength {});
+}
+
+#[test]
+fn test_pause() {
+    let mut deps = mock_dependencies();
+    let gov = setup_with_gov(deps.as_mut());
+    let updater = setup_with_updater(deps.as_mut());
+    let guardian = mock_info("guardian", &[]);
+    let env = mock_env();
+    let now = env.block.time.seconds();
+    let token = Addr::unchecked("token");
+    let ref_price = Uint256::from(10_000u64);
+
+    let mut config = generate_config();
+    config.price_duration = Uint64::new(300);
+    config.max_price_update_delay = Uint64::new(3600);
+    config.max_deviation_basis_points = Uint256::from(100u64);
+    save_config(deps.as_mut().storage, config);
+    update_global_state(deps.as_mut().storage, |global| {
+        global.spread_basis_points = SpreadBasisPoint::default();
+        global.spread_enabled = false;
+        global.min_authorizations = Some(Uint256::one());
+    })
+    .unwrap();
+    update_token_state(deps.as_mut().storage, &token, |state| {
+        state.price = Uint256::from(9_990u64);
+        state.price_data = PriceDataItem::default();
+        state.max_cumulative_delta_diff = Uint256::zero();
+        state.last_updated = LastUpdated {
+            last_updated_at: Uint64::new(now),
+            last_updated_block: env.block.height,
+        };
+    })
+    .unwrap();
+    let price = |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>| {
+        get_price(
+            deps.as_ref(),
+            token.clone(),
+            Uint64::new(now),
+            ref_price,
+            true,
+        )
+        .unwrap()
+    };
+    assert_eq!(price(&deps), Uint256::from(9_990u64));
+
+    // guardians pause without holding gov
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        guardian.clone(),
+        ExecuteMsg::Pause {},
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Forbidden {});
+    let msg = ExecuteMsg::GrantRole {
+        role: Role::Guardian,
+        account: guardian.sender.clone(),
+    };
+    execute(deps.as_mut(), env.clone(), gov.clone(), msg).unwrap();
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        guardian.clone(),
+        ExecuteMsg::Pause {},
+    )
+    .unwrap();
+    assert_eq!(res.events[0].ty, "paused");
+    let state = pause_state(deps.as_ref().storage).unwrap();
+    assert!(state.paused);
+    assert_eq!(state.paused_by, Some(guardian.sender.clone()));
+    assert_eq!(state.paused_at, Some(Uint64::new(now)));
+
+    for msg in [
+        ExecuteMsg::SetPrices {
+            tokens: vec![token.clone()],
+            prices: vec![ref_price],
+            timestamp: Uint64::new(now),
+        },
+        ExecuteMsg::SetCompactedPrices {
+            price_bit_array: vec![],
+            timestamp: Uint64::new(now),
+            layout: None,
+        },
+        ExecuteMsg::SetPricesWithBits {
+            price_bits: Uint256::zero(),
+            timestamp: Uint64::new(now),
+            layout: None,
+        },
+    ] {
+        let res = execute(deps.as_mut(), env.clone(), updater.clone(), msg);
+        assert_eq!(res.unwrap_err(), ContractError::Paused {});
     }
+
+    // a paused feed is no longer favored, or falls back to the ref price
+    assert!(!favor_fast_price(deps.as_ref(), token.clone(), Uint64::new(now)).unwrap());
+    assert_eq!(price(&deps), ref_price);
+    let msg = ExecuteMsg::SetPauseFallback {
+        ref_price_fallback: true,
+    };
+    execute(deps.as_mut(), env.clone(), gov.clone(), msg).unwrap();
+    update_token_state(deps.as_mut().storage, &token, |state| {
+        state.price = Uint256::from(10_010u64)
+    })
+    .unwrap();
+    assert_eq!(price(&deps), ref_price);
+
+    let res = execute(deps.as_mut(), env.clone(), guardian, ExecuteMsg::Unpause {});
+    assert_eq!(res.unwrap_err(), ContractError::Forbidden {});
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        gov.clone(),
+        ExecuteMsg::Unpause {},
+    )
+    .unwrap();
+    let res = execute(deps.as_mut(), env, gov, ExecuteMsg::Unpause {});
+    assert_eq!(res.unwrap_err(), ContractError::NotPaused {});
+    assert_eq!(price(&deps), Uint256::from(10_010u64));
+}
+
+#[test]
+fn test_set_token_params() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let token = setup_with_tokens(deps.as_mut(), 1).remove(0);
+    let info = mock_info("token_manager", &[]);
+
+    let mut config = generate_config();
+    config.price_duration = Uint64::new(300);
+    config.max_price_update_delay = Uint64::new(3600);
+    save_config(deps.as_mut().storage, config);
+    update_global_state(deps.as_mut().storage, |global| {
+        global.spread_basis_points = SpreadBasisPoint {
+            spread_basis_points_if_inactive: Uint256::from(10u64),
+            spread_basis_points_if_chain_error: Uint256::from(100u64),
+        }
+    })
+    .unwrap();
+
+    let params = TokenParams {
+        price_duration: Some(Uint64::new(900)),
+        spread_basis_points_if_inactive: Some(Uint256::from(50u64)),
+        ..TokenParams::default()
+    };
+    let msg = ExecuteMsg::SetTokenParams {
+        token: token.clone(),
+        params: params.clone(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("random", &[]),
+        msg.clone(),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Forbidden {});
+    execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // overrides win, everything else falls back to the global values
+    let effective = get_effective_token_params(deps.as_ref(), token.clone()).unwrap();
+    assert_eq!(effective.price_duration, Uint64::new(900));
+    assert_eq!(effective.max_price_update_delay, Uint64::new(3600));
+    assert_eq!(
+        effective.spread_basis_points_if_inactive,
+        Uint256::from(50u64)
+    );
+    assert_eq!(
+        effective.spread_basis_points_if_chain_error,
+        Uint256::from(100u64)
+    );
+
+    let other = get_effective_token_params(deps.as_ref(), Addr::unchecked("other")).unwrap();
+    assert_eq!(other.price_duration, Uint64::new(300));
+
+    // the inactive spread now applies after the token's own duration
+    let now = env.block.time.seconds();
+    update_token_state(deps.as_mut().storage, &token, |state| {
+        state.last_updated = LastUpdated {
+            last_updated_at: Uint64::new(now - 1000),
+            last_updated_block: env.block.height,
+        }
+    })
+    .unwrap();
+    let price = get_price(
+        deps.as_ref(),
+        token.clone(),
+        Uint64::new(now),
+        Uint256::from(10_000u64),
+        true,
+    )
+    .unwrap();
+    assert_eq!(price, Uint256::from(10_050u64));
+
+    let msg = ExecuteMsg::SetTokenParams {
+        token: token.clone(),
+        params: TokenParams {
+            spread_basis_points_if_chain_error: Some(Uint256::from(10_001u64)),
+            ..TokenParams::default()
+        },
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::InvalidSpreadBasisPoints {});
+
+    // unregistered tokens, zero durations and inverted delays are rejected
+    let cases = [
+        (
+            Addr::unchecked("other"),
+            TokenParams::default(),
+            ContractError::TokenNotFound {
+                token: "other".to_string(),
+            },
+        ),
+        (
+            token.clone(),
+            TokenParams {
+                price_duration: Some(Uint64::zero()),
+                ..TokenParams::default()
+            },
+            ContractError::InvalidPriceDuration {},
+        ),
+        (
+            token.clone(),
+            TokenParams {
+                max_price_update_delay: Some(Uint64::zero()),
+                ..TokenParams::default()
+            },
+            ContractError::InvalidMaxPriceUpdateDelay {},
+        ),
+        (
+            token.clone(),
+            TokenParams {
+                max_price_update_delay: Some(Uint64::new(300)),
+                ..TokenParams::default()
+            },
+            ContractError::InvalidMaxPriceUpdateDelay {},
+        ),
+    ];
+    for (token, params, err) in cases {
+        let msg = ExecuteMsg::SetTokenParams { token, params };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+        assert_eq!(res.unwrap_err(), err);
+    }
+    assert!(!TOKEN_STATES.has(&deps.storage, &Addr::unchecked("other")));
+}
+
+#[test]
+fn test_set_token_params_timelock() {
+    let mut deps = mock_dependencies();
+    let mut env = mock_env();
+    let gov = setup_with_gov(deps.as_mut());
+    let token = setup_with_tokens(deps.as_mut(), 1).remove(0);
+    let info = mock_info("token_manager", &[]);
+
+    let mut config = generate_config();
+    config.price_duration = Uint64::new(300);
+    config.max_price_update_delay = Uint64::new(3600);
+    save_config(deps.as_mut().storage, config);
+    update_global_state(deps.as_mut().storage, |global| {
+        global.spread_basis_points = SpreadBasisPoint {
+            spread_basis_points_if_inactive: Uint256::from(10u64),
+            spread_basis_points_if_chain_error: Uint256::from(100u64),
+        }
+    })
+    .unwrap();
+    TIMELOCK_DELAY.save(deps.as_mut().storage, &3600).unwrap();
+
+    // overrides loosening the values in effect have to wait for the delay
+    let loosening = [
+        TokenParams {
+            price_duration: Some(Uint64::new(900)),
+            ..TokenParams::default()
+        },
+        TokenParams {
+            max_price_update_delay: Some(Uint64::new(7200)),
+            ..TokenParams::default()
+        },
+        TokenParams {
+            spread_basis_points_if_inactive: Some(Uint256::from(5u64)),
+            ..TokenParams::default()
+        },
+        TokenParams {
+            spread_basis_points_if_chain_error: Some(Uint256::zero()),
+            ..TokenParams::default()
+        },
+    ];
+    for params in loosening {
+        let msg = ExecuteMsg::SetTokenParams {
+            token: token.clone(),
+            params,
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+        assert_eq!(res.unwrap_err(), ContractError::TimelockRequired {});
+    }
+    assert_eq!(
+        load_token_state(&deps.storage, &token).unwrap().params,
+        TokenParams::default()
+    );
+
+    // tightening applies immediately
+    let tight = TokenParams {
+        price_duration: Some(Uint64::new(120)),
+        spread_basis_points_if_chain_error: Some(Uint256::from(200u64)),
+        ..TokenParams::default()
+    };
+    let msg = ExecuteMsg::SetTokenParams {
+        token: token.clone(),
+        params: tight.clone(),
+    };
+    execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    assert_eq!(
+        load_token_state(&deps.storage, &token).unwrap().params,
+        tight
+    );
+
+    // dropping the overrides restores the looser global values
+    let msg = ExecuteMsg::SetTokenParams {
+        token: token.clone(),
+        params: TokenParams::default(),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::TimelockRequired {});
+
+    let msg = ExecuteMsg::QueueAction {
+        action: TimelockAction::SetTokenParams {
+            token: Addr::unchecked("other"),
+            params: TokenParams::default(),
+        },
+    };
+    let res = execute(deps.as_mut(), env.clone(), gov.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::TokenNotFound {
+            token: "other".to_string()
+        }
+    );
+    let msg = ExecuteMsg::QueueAction {
+        action: TimelockAction::SetTokenParams {
+            token: token.clone(),
+            params: TokenParams::default(),
+        },
+    };
+    execute(deps.as_mut(), env.clone(), gov.clone(), msg).unwrap();
+    env.block.time = env.block.time.plus_seconds(3600);
+    let res = execute(deps.as_mut(), env, gov, ExecuteMsg::ExecuteAction { id: 0 }).unwrap();
+    assert_eq!(res.attributes[2].value, "set_token_params");
+    assert_eq!(
+        load_token_state(&deps.storage, &token).unwrap().params,
+        TokenParams::default()
+    );
+}
+
+#[test]
+fn test_set_prices_signed() {
+    use k256::ecdsa::signature::hazmat::PrehashSigner;
+
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let gov = setup_with_gov(deps.as_mut());
+    let relayer = mock_info("relayer", &[]);
+    mock_vault_price_feed(&mut deps, 100_000_000, env.block.time.seconds());
+
+    save_config(deps.as_mut().storage, generate_config());
+    setup_with_tokens(deps.as_mut(), 1);
+    MAX_TIME_DEVIATION
+        .save(deps.as_mut().storage, &Uint64::new(1000))
+        .unwrap();
+    PRICE_DATA_INTERVAL
+        .save(deps.as_mut().storage, &Uint64::one())
+        .unwrap();
+
+    let secp_key = k256::ecdsa::SigningKey::from_bytes(&[1u8; 32]).unwrap();
+    let secp_pubkey = Binary::from(secp_key.verifying_key().to_bytes().to_vec());
+    let ed_key = ed25519_zebra::SigningKey::from([2u8; 32]);
+    let ed_pubkey =
+        Binary::from(<[u8; 32]>::from(ed25519_zebra::VerificationKey::from(&ed_key)).to_vec());
+
+    for (pubkey, pubkey_type) in [
+        (&secp_pubkey, PubkeyType::Secp256k1),
+        (&ed_pubkey, PubkeyType::Ed25519),
+    ] {
+        let msg = ExecuteMsg::SetUpdaterPubkey {
+            pubkey: pubkey.clone(),
+            pubkey_type,
+            is_active: true,
+        };
+        execute(deps.as_mut(), env.clone(), gov.clone(), msg).unwrap();
+    }
+
+    let tokens = vec![Addr::unchecked("token0")];
+    let prices = vec![Uint256::from(100u64)];
+    let timestamp = Uint64::from(env.block.time.seconds());
+    let sign = |nonce: u64| {
+        let hash = price_payload_hash(
+            &env.block.chain_id,
+            &env.contract.address,
+            nonce,
+            timestamp.u64(),
+            &tokens,
+            &prices,
+        );
+        let secp_signature: k256::ecdsa::Signature = secp_key.sign_prehash(&hash).unwrap();
+        let ed_signature = ed_key.sign(&hash);
+        vec![
+            PriceSignature {
+                pubkey: secp_pubkey.clone(),
+                signature: Binary::from(secp_signature.as_ref().to_vec()),
+                nonce: Uint64::new(nonce),
+            },
+            PriceSignature {
+                pubkey: ed_pubkey.clone(),
+                signature: Binary::from(<[u8; 64]>::from(ed_signature).to_vec()),
+                nonce: Uint64::new(nonce),
+            },
+        ]
+    };
+
+    let msg = ExecuteMsg::SetPricesSigned {
+        tokens: tokens.clone(),
+        prices: prices.clone(),
+        timestamp,
+        signatures: sign(1),
+    };
+    execute(deps.as_mut(), env.clone(), relayer.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        load_token_state(deps.as_ref().storage, &Addr::unchecked("token0"))
+            .unwrap()
+            .price,
+        prices[0]
+    );
+
+    // replaying the same signatures fails
+    let res = execute(deps.as_mut(), env.clone(), relayer.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::InvalidNonce { last_nonce: 1 }
+    );
+
+    // a tampered payload does not verify
+    let msg = ExecuteMsg::SetPricesSigned {
+        tokens: tokens.clone(),
+        prices: vec![Uint256::from(200u64)],
+        timestamp,
+        signatures: sign(2),
+    };
+    let res = execute(deps.as_mut(), env.clone(), relayer.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::InvalidSignature {
+            pubkey: secp_pubkey.to_base64()
+        }
+    );
+
+    // unregistered keys are rejected
+    let msg = ExecuteMsg::SetUpdaterPubkey {
+        pubkey: ed_pubkey.clone(),
+        pubkey_type: PubkeyType::Ed25519,
+        is_active: false,
+    };
+    execute(deps.as_mut(), env.clone(), gov, msg).unwrap();
+    let msg = ExecuteMsg::SetPricesSigned {
+        tokens: tokens.clone(),
+        prices: prices.clone(),
+        timestamp,
+        signatures: sign(2),
+    };
+    let res = execute(deps.as_mut(), env, relayer, msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::UnknownPubkey {
+            pubkey: ed_pubkey.to_base64()
+        }
+    );
+}
+
+#[test]
+fn test_quorum_median() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let gov = setup_with_gov(deps.as_mut());
+    let token = Addr::unchecked("token0");
+    mock_vault_price_feed(&mut deps, 100_000_000, env.block.time.seconds());
+
+    save_config(deps.as_mut().storage, generate_config());
+    setup_with_tokens(deps.as_mut(), 1);
+    MAX_TIME_DEVIATION
+        .save(deps.as_mut().storage, &Uint64::new(1000))
+        .unwrap();
+    PRIMARY_PRICE_FEEDS
+        .save(deps.as_mut().storage, &token, &Addr::unchecked("vault"))
+        .unwrap();
+    PRICE_DATA_INTERVAL
+        .save(deps.as_mut().storage, &Uint64::one())
+        .unwrap();
+    update_global_state(deps.as_mut().storage, |global| {
+        global.min_authorizations = Some(Uint256::from(3u64))
+    })
+    .unwrap();
+    let updaters: Vec<MessageInfo> = (0..3)
+        .map(|i| {
+            let updater = Addr::unchecked(format!("updater{}", i));
+            set_role_member(deps.as_mut().storage, Role::Updater, &updater, true).unwrap();
+            mock_info(updater.as_str(), &[])
+        })
+        .collect();
+
+    let msg = ExecuteMsg::SetQuorumConfig {
+        quorum_config: QuorumConfig {
+            enabled: true,
+            min_observations: None,
+            round_duration: Uint64::new(60),
+            outlier_basis_points: Uint256::from(100u64),
+        },
+    };
+    execute(deps.as_mut(), env.clone(), gov, msg).unwrap();
+
+    // a single updater can no longer write prices directly
+    let timestamp = Uint64::from(env.block.time.seconds());
+    let msg = ExecuteMsg::SetPrices {
+        tokens: vec![token.clone()],
+        prices: vec![Uint256::from(100u64)],
+        timestamp,
+    };
+    let res = execute(deps.as_mut(), env.clone(), updaters[0].clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::QuorumModeEnabled {});
+
+    // observations of unregistered tokens are rejected before opening a round
+    let msg = ExecuteMsg::SubmitObservations {
+        tokens: vec![Addr::unchecked("unknown")],
+        prices: vec![Uint256::from(100u64)],
+        timestamp,
+    };
+    let res = execute(deps.as_mut(), env.clone(), updaters[0].clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::TokenNotFound {
+            token: "unknown".to_string()
+        }
+    );
+
+    let observe = |price: u64| ExecuteMsg::SubmitObservations {
+        tokens: vec![token.clone()],
+        prices: vec![Uint256::from(price)],
+        timestamp,
+    };
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        updaters[0].clone(),
+        observe(100),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        updaters[1].clone(),
+        observe(150),
+    )
+    .unwrap();
+    // resubmitting replaces the previous observation
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        updaters[1].clone(),
+        observe(101),
+    )
+    .unwrap();
+
+    let round = PENDING_ROUNDS.load(deps.as_ref().storage, &token).unwrap();
+    assert_eq!(round.observations.len(), 2);
+    assert!(!TOKEN_STATES.has(deps.as_ref().storage, &token));
+
+    // resetting the token params keeps the observations in flight
+    let msg = ExecuteMsg::SetTokenParams {
+        token: token.clone(),
+        params: TokenParams::default(),
+    };
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("token_manager", &[]),
+        msg,
+    )
+    .unwrap();
+    let round = PENDING_ROUNDS.load(deps.as_ref().storage, &token).unwrap();
+    assert_eq!(round.observations.len(), 2);
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        updaters[2].clone(),
+        observe(150),
+    )
+    .unwrap();
+    assert_eq!(
+        load_token_state(deps.as_ref().storage, &token)
+            .unwrap()
+            .price,
+        Uint256::from(101u64)
+    );
+    assert!(!PENDING_ROUNDS.has(deps.as_ref().storage, &token));
+
+    let event = res
+        .events
+        .iter()
+        .find(|event| event.ty == "quorum_price_committed")
+        .unwrap();
+    let outliers: Vec<&str> = event
+        .attributes
+        .iter()
+        .filter(|attr| attr.key == "outlier")
+        .map(|attr| attr.value.as_str())
+        .collect();
+    assert_eq!(outliers, vec!["updater2"]);
+
+    // an extreme observation is flagged instead of aborting the round
+    let timestamp = timestamp + Uint64::one();
+    let prices = [Uint256::from(102u64), Uint256::MAX, Uint256::from(103u64)];
+    let mut res = None;
+    for (updater, price) in updaters.iter().zip(prices) {
+        let msg = ExecuteMsg::SubmitObservations {
+            tokens: vec![token.clone()],
+            prices: vec![price],
+            timestamp,
+        };
+        res = Some(execute(deps.as_mut(), env.clone(), updater.clone(), msg).unwrap());
+    }
+    assert_eq!(
+        load_token_state(deps.as_ref().storage, &token)
+            .unwrap()
+            .price,
+        Uint256::from(103u64)
+    );
+    let event = res
+        .unwrap()
+        .events
+        .into_iter()
+        .find(|event| event.ty == "quorum_price_committed")
+        .unwrap();
+    assert!(event
+        .attributes
+        .contains(&Attribute::new("outlier", "updater1")));
+}
+
+#[test]
+fn test_quorum_guards() {
+    let mut deps = mock_dependencies();
+    let mut env = mock_env();
+    let gov = setup_with_gov(deps.as_mut());
+    let token = setup_with_tokens(deps.as_mut(), 1).remove(0);
+
+    save_config(deps.as_mut().storage, generate_config());
+    MAX_TIME_DEVIATION
+        .save(deps.as_mut().storage, &Uint64::new(1000))
+        .unwrap();
+    PRICE_DATA_INTERVAL
+        .save(deps.as_mut().storage, &Uint64::one())
+        .unwrap();
+    update_global_state(deps.as_mut().storage, |global| {
+        global.min_authorizations = Some(Uint256::from(3u64))
+    })
+    .unwrap();
+    let updaters: Vec<MessageInfo> = (0..2)
+        .map(|i| {
+            let updater = Addr::unchecked(format!("updater{}", i));
+            set_role_member(deps.as_mut().storage, Role::Updater, &updater, true).unwrap();
+            mock_info(updater.as_str(), &[])
+        })
+        .collect();
+
+    // min observations, or min authorizations by default, have to be reachable
+    let quorum_config = |min_observations| QuorumConfig {
+        enabled: true,
+        min_observations,
+        round_duration: Uint64::new(60),
+        outlier_basis_points: Uint256::from(100u64),
+    };
+    for min_observations in [None, Some(3)] {
+        let msg = ExecuteMsg::SetQuorumConfig {
+            quorum_config: quorum_config(min_observations),
+        };
+        let res = execute(deps.as_mut(), env.clone(), gov.clone(), msg);
+        assert_eq!(
+            res.unwrap_err(),
+            ContractError::InsufficientUpdaters {
+                min_observations: Uint256::from(3u64)
+            }
+        );
+    }
+    let msg = ExecuteMsg::SetQuorumConfig {
+        quorum_config: quorum_config(Some(2)),
+    };
+    execute(deps.as_mut(), env.clone(), gov.clone(), msg).unwrap();
+
+    // and stay reachable when updaters are removed
+    let msg = ExecuteMsg::SetUpdater {
+        account: updaters[1].sender.clone(),
+        is_active: false,
+    };
+    let res = execute(deps.as_mut(), env.clone(), gov, msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::InsufficientUpdaters {
+            min_observations: Uint256::from(2u64)
+        }
+    );
+
+    let timestamp = Uint64::from(env.block.time.seconds());
+    let observe = |timestamp| ExecuteMsg::SubmitObservations {
+        tokens: vec![token.clone()],
+        prices: vec![Uint256::from(100u64)],
+        timestamp,
+    };
+    for updater in updaters.iter() {
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            updater.clone(),
+            observe(timestamp),
+        )
+        .unwrap();
+    }
+    let last_updated = load_token_state(&deps.storage, &token)
+        .unwrap()
+        .last_updated;
+    assert_eq!(last_updated.last_updated_at, timestamp);
+
+    // a round can not move the update time backwards
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        updaters[0].clone(),
+        observe(timestamp - Uint64::one()),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::ObservationTooOld {});
+
+    // nor commit before min block interval blocks passed
+    update_global_state(deps.as_mut().storage, |global| {
+        global.config.min_block_interval = Uint64::new(5)
+    })
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        updaters[0].clone(),
+        observe(timestamp),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::MinblockInterval {});
+    env.block.height += 5;
+    execute(deps.as_mut(), env, updaters[0].clone(), observe(timestamp)).unwrap();
+    assert!(PENDING_ROUNDS.has(&deps.storage, &token));
+}
+
+#[test]
+fn test_circuit_breaker() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let now = Uint64::from(env.block.time.seconds());
+    let gov = setup_with_gov(deps.as_mut());
+    let updater = setup_with_updater(deps.as_mut());
+    let token = Addr::unchecked("token0");
+    mock_vault_price_feed(&mut deps, 100_000_000, env.block.time.seconds());
+
+    save_config(deps.as_mut().storage, generate_config());
+    setup_with_tokens(deps.as_mut(), 1);
+    MAX_TIME_DEVIATION
+        .save(deps.as_mut().storage, &Uint64::new(1000))
+        .unwrap();
+    PRIMARY_PRICE_FEEDS
+        .save(deps.as_mut().storage, &token, &Addr::unchecked("vault"))
+        .unwrap();
+    PRICE_DATA_INTERVAL
+        .save(deps.as_mut().storage, &Uint64::one())
+        .unwrap();
+    update_global_state(deps.as_mut().storage, |global| {
+        global.spread_enabled = false;
+        global.min_authorizations = Some(Uint256::from(2u64));
+    })
+    .unwrap();
+    let signers: Vec<MessageInfo> = (0..2)
+        .map(|i| {
+            let signer = Addr::unchecked(format!("signer{}", i));
+            set_role_member(deps.as_mut().storage, Role::Signer, &signer, true).unwrap();
+            mock_info(signer.as_str(), &[])
+        })
+        .collect();
+
+    let msg = ExecuteMsg::SetCircuitBreakerConfig {
+        circuit_breaker_config: CircuitBreakerConfig {
+            enabled: true,
+            max_deviation_basis_points: Uint256::from(500u64),
+            max_consecutive_deviations: 2,
+            max_cumulative_delta_excess: Uint256::zero(),
+        },
+    };
+    let res = execute(deps.as_mut(), env.clone(), signers[0].clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::Forbidden {});
+    let msg = ExecuteMsg::SetCircuitBreakerConfig {
+        circuit_breaker_config: CircuitBreakerConfig {
+            enabled: true,
+            max_deviation_basis_points: Uint256::from(500u64),
+            max_consecutive_deviations: 2,
+            max_cumulative_delta_excess: Uint256::zero(),
+        },
+    };
+    execute(deps.as_mut(), env.clone(), gov.clone(), msg).unwrap();
+
+    // the ref price is one, every update below is 10% off
+    let set_prices = ExecuteMsg::SetPrices {
+        tokens: vec![token.clone()],
+        prices: vec![PRICE_PRECISION.multiply_ratio(11u64, 10u64)],
+        timestamp: Uint64::from(env.block.time.seconds()),
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        updater.clone(),
+        set_prices.clone(),
+    )
+    .unwrap();
+    assert!(!res
+        .events
+        .iter()
+        .any(|event| event.ty == "circuit_breaker_tripped"));
+    assert!(favor_fast_price(deps.as_ref(), token.clone(), now).unwrap());
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        updater.clone(),
+        set_prices.clone(),
+    )
+    .unwrap();
+    assert_eq!(res.events[1].ty, "circuit_breaker_tripped");
+    assert!(!favor_fast_price(deps.as_ref(), token.clone(), now).unwrap());
+
+    // signers reset once min_authorizations of them voted
+    let reset = ExecuteMsg::ResetCircuitBreaker {
+        token: token.clone(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        signers[0].clone(),
+        reset.clone(),
+    )
+    .unwrap();
+    assert!(res.events.is_empty());
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        signers[0].clone(),
+        reset.clone(),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::AlreadyVoted {});
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        signers[1].clone(),
+        reset.clone(),
+    )
+    .unwrap();
+    assert_eq!(res.events[0].ty, "circuit_breaker_reset");
+    assert!(favor_fast_price(deps.as_ref(), token.clone(), now).unwrap());
+    assert!(!CIRCUIT_BREAKER_RESET_VOTES.has(deps.as_ref().storage, (&token, &signers[0].sender)));
+
+    // gov resets on its own
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        updater.clone(),
+        set_prices.clone(),
+    )
+    .unwrap();
+    execute(deps.as_mut(), env.clone(), updater, set_prices).unwrap();
+    assert!(
+        load_token_state(deps.as_ref().storage, &token)
+            .unwrap()
+            .circuit_breaker
+            .tripped
+    );
+    execute(deps.as_mut(), env.clone(), gov.clone(), reset.clone()).unwrap();
+    let res = execute(deps.as_mut(), env, gov, reset);
+    assert_eq!(res.unwrap_err(), ContractError::CircuitBreakerNotTripped {});
+}
+
+#[test]
+fn test_circuit_breaker_deviation_overflow() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let updater = setup_with_updater(deps.as_mut());
+    let token = setup_with_tokens(deps.as_mut(), 1).remove(0);
+    // an answer of one with 30 decimals is a ref price of one
+    mock_vault_price_feed(&mut deps, 1, env.block.time.seconds());
+
+    save_config(deps.as_mut().storage, generate_config());
+    MAX_TIME_DEVIATION
+        .save(deps.as_mut().storage, &Uint64::new(1000))
+        .unwrap();
+    PRICE_DATA_INTERVAL
+        .save(deps.as_mut().storage, &Uint64::one())
+        .unwrap();
+    VAULT_ADDRESS
+        .save(deps.as_mut().storage, &Addr::unchecked("vault"))
+        .unwrap();
+    PRIMARY_PRICE_CONFIG
+        .save(
+            deps.as_mut().storage,
+            &PrimaryPriceConfig {
+                price_decimals: 30,
+                max_price_age: Uint64::zero(),
+            },
+        )
+        .unwrap();
+    CIRCUIT_BREAKER_CONFIG
+        .save(
+            deps.as_mut().storage,
+            &CircuitBreakerConfig {
+                enabled: true,
+                max_deviation_basis_points: Uint256::from(500u64),
+                max_consecutive_deviations: 2,
+                max_cumulative_delta_excess: Uint256::zero(),
+            },
+        )
+        .unwrap();
+
+    // the deviation of the submitted price from the ref price does not fit in 256 bits
+    let msg = ExecuteMsg::SetPrices {
+        tokens: vec![token.clone()],
+        prices: vec![Uint256::MAX],
+        timestamp: Uint64::from(env.block.time.seconds()),
+    };
+    let res = execute(deps.as_mut(), env, updater, msg);
+    assert!(matches!(res.unwrap_err(), ContractError::MultiplyRatio(_)));
+    assert_eq!(
+        load_token_state(&deps.storage, &token).unwrap(),
+        TokenState::default()
+    );
+}
+
+#[test]
+fn test_signer_proposals() {
+    let mut deps = mock_dependencies();
+    let mut env = mock_env();
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("gov", &[]),
+        instantiate_msg(Config {
+            price_duration: Uint64::one(),
+            max_price_update_delay: Uint64::new(3600),
+            ..generate_config()
+        }),
+    )
+    .unwrap();
+    let signers = ["signer1", "signer2", "signer3"].map(|signer| mock_info(signer, &[]));
+    let msg = ExecuteMsg::Initialize {
+        min_auth: Uint256::from(2u64),
+        signers: signers.iter().map(|info| info.sender.clone()).collect(),
+        updaters: vec![],
+    };
+    execute(deps.as_mut(), env.clone(), mock_info("gov", &[]), msg).unwrap();
+
+    // the gov key is lost, signers hand the role to a new account
+    let grant = ExecuteMsg::GrantRole {
+        role: Role::Gov,
+        account: Addr::unchecked("new_gov"),
+    };
+    let submit = |msg: ExecuteMsg, expires_at: Option<Uint64>| ExecuteMsg::SubmitProposal {
+        msg: Box::new(msg),
+        expires_at,
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("user", &[]),
+        submit(grant.clone(), None),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Forbidden {});
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        signers[0].clone(),
+        submit(
+            ExecuteMsg::DisableFastPrice {
+                token: None,
+                expires_at: None,
+            },
+            None,
+        ),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::InvalidProposalMsg {});
+    // the contract keeps the gov role the proposals are executed with
+    for msg in [
+        ExecuteMsg::ProposeOwnership {
+            role: Role::Gov,
+            new_owner: Addr::unchecked("new_gov"),
+            expires_at: None,
+        },
+        ExecuteMsg::RevokeRole {
+            role: Role::Gov,
+            account: Addr::unchecked(MOCK_CONTRACT_ADDR),
+        },
+    ] {
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            signers[0].clone(),
+            submit(msg, None),
+        );
+        assert_eq!(res.unwrap_err(), ContractError::InvalidProposalMsg {});
+    }
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        signers[0].clone(),
+        submit(grant.clone(), None),
+    )
+    .unwrap();
+
+    let execute_proposal = ExecuteMsg::ExecuteProposal { id: 0 };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        signers[0].clone(),
+        execute_proposal.clone(),
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::InsufficientApprovals {
+            min_authorizations: Uint256::from(2u64)
+        }
+    );
+    let approve = ExecuteMsg::ApproveProposal { id: 0 };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        signers[0].clone(),
+        approve.clone(),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::AlreadyVoted {});
+    execute(deps.as_mut(), env.clone(), signers[1].clone(), approve).unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        signers[2].clone(),
+        execute_proposal.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+            msg: to_binary(&grant).unwrap(),
+            funds: vec![],
+        })
+    );
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        signers[2].clone(),
+        execute_proposal,
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::ProposalAlreadyExecuted { id: 0 }
+    );
+
+    // the dispatched message runs with the gov role of the contract
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        grant,
+    )
+    .unwrap();
+    assert!(has_role(
+        deps.as_ref().storage,
+        Role::Gov,
+        &Addr::unchecked("new_gov")
+    ));
+
+    let now = env.block.time.seconds();
+    let epoch = ExecuteMsg::StartDisableVoteEpoch {};
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        signers[0].clone(),
+        submit(epoch.clone(), Some(Uint64::new(now))),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::InvalidProposalExpiry {});
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        signers[0].clone(),
+        submit(epoch, Some(Uint64::new(now + 60))),
+    )
+    .unwrap();
+    env.block.time = env.block.time.plus_seconds(60);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        signers[1].clone(),
+        ExecuteMsg::ApproveProposal { id: 1 },
+    );
+    assert_eq!(res.unwrap_err(), ContractError::ProposalExpired { id: 1 });
+
+    let proposals = signer_proposals(deps.as_ref(), None, None).unwrap();
+    assert_eq!(proposals.len(), 2);
+    assert!(proposals[0].executed);
+    assert_eq!(
+        proposals[0].approvals,
+        vec![signers[0].sender.clone(), signers[1].sender.clone()]
+    );
+    assert_eq!(proposals[1].expires_at, Uint64::new(now + 60));
+}
+
+#[test]
+fn test_roles() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let gov = setup_with_gov(deps.as_mut());
+    let manager = mock_info("updater_manager", &[]);
+    let updaters: Vec<Addr> = (0..3)
+        .map(|i| Addr::unchecked(format!("updater{}", i)))
+        .collect();
+
+    let grant = |account: &Addr| ExecuteMsg::GrantRole {
+        role: Role::Updater,
+        account: account.clone(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        manager.clone(),
+        grant(&updaters[0]),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::Forbidden {});
+
+    // gov delegates the updater role to a dedicated admin role
+    let msg = ExecuteMsg::GrantRole {
+        role: Role::TokenManager,
+        account: manager.sender.clone(),
+    };
+    execute(deps.as_mut(), env.clone(), gov.clone(), msg).unwrap();
+    let msg = ExecuteMsg::SetRoleAdmin {
+        role: Role::Updater,
+        admin_role: Role::TokenManager,
+    };
+    execute(deps.as_mut(), env.clone(), gov.clone(), msg).unwrap();
+    let res = execute(deps.as_mut(), env.clone(), gov.clone(), grant(&updaters[0]));
+    assert_eq!(res.unwrap_err(), ContractError::Forbidden {});
+    for updater in updaters.iter() {
+        execute(deps.as_mut(), env.clone(), manager.clone(), grant(updater)).unwrap();
+    }
+
+    let page = role_members(deps.as_ref(), Role::Updater, None, Some(2)).unwrap();
+    assert_eq!(page, updaters[0..2].to_vec());
+    let page = role_members(deps.as_ref(), Role::Updater, Some(page[1].clone()), None).unwrap();
+    assert_eq!(page, vec![updaters[2].clone()]);
+
+    let msg = ExecuteMsg::RevokeRole {
+        role: Role::Updater,
+        account: updaters[0].clone(),
+    };
+    execute(deps.as_mut(), env.clone(), manager, msg).unwrap();
+    let msg = ExecuteMsg::RenounceRole {
+        role: Role::Updater,
+    };
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info(updaters[1].as_str(), &[]),
+        msg,
+    )
+    .unwrap();
+    assert!(!has_role(
+        deps.as_ref().storage,
+        Role::Updater,
+        &updaters[0]
+    ));
+    assert!(!has_role(
+        deps.as_ref().storage,
+        Role::Updater,
+        &updaters[1]
+    ));
+    assert_eq!(
+        role_member_count(deps.as_ref().storage, Role::Updater).unwrap(),
+        1
+    );
+}
+
+#[test]
+fn test_migrate_legacy_roles() {
+    let mut deps = mock_dependencies();
+    let storage = deps.as_mut().storage;
+    let legacy_signers: Map<&Addr, bool> = Map::new("isSigner");
+    Item::new("GOV")
+        .save(storage, &Addr::unchecked("gov"))
+        .unwrap();
+    Map::<&Addr, bool>::new("isUpdater")
+        .save(storage, &Addr::unchecked("updater"), &true)
+        .unwrap();
+    legacy_signers
+        .save(storage, &Addr::unchecked("signer0"), &true)
+        .unwrap();
+    legacy_signers
+        .save(storage, &Addr::unchecked("signer1"), &false)
+        .unwrap();
+
+    migrate_legacy_roles(storage).unwrap();
+
+    assert!(has_role(storage, Role::Gov, &Addr::unchecked("gov")));
+    assert!(has_role(
+        storage,
+        Role::Updater,
+        &Addr::unchecked("updater")
+    ));
+    assert!(has_role(storage, Role::Signer, &Addr::unchecked("signer0")));
+    assert!(!has_role(
+        storage,
+        Role::Signer,
+        &Addr::unchecked("signer1")
+    ));
+    assert_eq!(role_member_count(storage, Role::Signer).unwrap(), 1);
+    assert!(legacy_signers.is_empty(storage));
+}
+
+#[test]
+fn test_migrate_time_units() {
+    #[cosmwasm_schema::cw_serde]
+    struct LegacyLastUpdated {
+        last_updated_at: u64,
+        last_updated_block: u64,
+    }
+
+    // split records of the layout the time units were migrated in
+    let config_item: Item<Config> = Item::new("config");
+    let token_last_updated: Map<&Addr, LastUpdated> = Map::new("token_last_updated");
+    let token_params: Map<&Addr, TokenParams> = Map::new("token_params");
+
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let now = env.block.time.seconds();
+    let storage = deps.as_mut().storage;
+    let eth = Addr::unchecked("eth");
+    let btc = Addr::unchecked("btc");
+    let legacy = LegacyLastUpdated {
+        last_updated_at: 1000,
+        last_updated_block: 10,
+    };
+    Item::<u64>::new("max_time_deviation")
+        .save(storage, &3600)
+        .unwrap();
+    Item::new("last_updated").save(storage, &legacy).unwrap();
+    Map::new("token_last_updated")
+        .save(storage, &eth, &legacy)
+        .unwrap();
+    token_last_updated
+        .save(
+            storage,
+            &btc,
+            &LastUpdated {
+                last_updated_at: Uint64::new(2000),
+                last_updated_block: 20,
+            },
+        )
+        .unwrap();
+    // durations were already added to second timestamps, a nanosecond value cannot be one
+    let mut config = generate_config();
+    config.price_duration = Uint64::new(300 * 1_000_000_000);
+    config_item.save(storage, &config).unwrap();
+    assert_eq!(
+        migrate_time_units(storage, &env).unwrap_err(),
+        ContractError::TimeOutOfRange {
+            field: "price_duration".to_string(),
+            value: config.price_duration
+        }
+    );
+    config.price_duration = Uint64::new(300);
+    config_item.save(storage, &config).unwrap();
+
+    let price_data: Map<&Addr, PriceDataItem> = Map::new("priceData");
+    let future = PriceDataItem {
+        ref_time: Uint64::new(now * 1_000_000_000),
+        ..PriceDataItem::default()
+    };
+    price_data.save(storage, &eth, &future).unwrap();
+    assert_eq!(
+        migrate_time_units(storage, &env).unwrap_err(),
+        ContractError::TimeOutOfRange {
+            field: "ref_time".to_string(),
+            value: future.ref_time
+        }
+    );
+    let past = PriceDataItem {
+        ref_time: Uint64::new(now),
+        ..PriceDataItem::default()
+    };
+    price_data.save(storage, &eth, &past).unwrap();
+    token_params
+        .save(
+            storage,
+            &eth,
+            &TokenParams {
+                price_duration: Some(Uint64::new(120)),
+                ..TokenParams::default()
+            },
+        )
+        .unwrap();
+
+    migrate_time_units(storage, &env).unwrap();
+    // running it again leaves the migrated values alone
+    migrate_time_units(storage, &env).unwrap();
+
+    assert_eq!(MAX_TIME_DEVIATION.load(storage).unwrap(), Uint64::new(3600));
+    let expected = LastUpdated {
+        last_updated_at: Uint64::new(1000),
+        last_updated_block: 10,
+    };
+    assert_eq!(LAST_UPDATED.load(storage).unwrap(), expected);
+    assert_eq!(token_last_updated.load(storage, &eth).unwrap(), expected);
+    assert_eq!(
+        token_last_updated
+            .load(storage, &btc)
+            .unwrap()
+            .last_updated_at,
+        Uint64::new(2000)
+    );
+    assert_eq!(
+        config_item.load(storage).unwrap().price_duration,
+        Uint64::new(300)
+    );
+    assert_eq!(
+        token_params.load(storage, &eth).unwrap().price_duration,
+        Some(Uint64::new(120))
+    );
+}
+
+// config as stored while it named the token manager
+#[cosmwasm_schema::cw_serde]
+struct LegacyConfig {
+    price_duration: Uint64,
+    max_price_update_delay: Uint64,
+    min_block_interval: Uint64,
+    max_deviation_basis_points: Uint256,
+    fast_price_events: Addr,
+    token_manager: Addr,
+}
+
+fn legacy_config(config: Config, token_manager: &str) -> LegacyConfig {
+    LegacyConfig {
+        price_duration: config.price_duration,
+        max_price_update_delay: config.max_price_update_delay,
+        min_block_interval: config.min_block_interval,
+        max_deviation_basis_points: config.max_deviation_basis_points,
+        fast_price_events: config.fast_price_events,
+        token_manager: Addr::unchecked(token_manager),
+    }
+}
+
+#[test]
+fn test_migrate() {
+    #[cosmwasm_schema::cw_serde]
+    struct LegacyTokenData {
+        token: Addr,
+        token_precision: Uint256,
+    }
+
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let contract = Addr::unchecked(MOCK_CONTRACT_ADDR);
+    let guardian = Addr::unchecked("guardian");
+    let storage = deps.as_mut().storage;
+    cw2::set_contract_version(storage, "crates.io:crates.io:{{project-name}}", "0.0.1").unwrap();
+    Item::new("GOV")
+        .save(storage, &Addr::unchecked("gov"))
+        .unwrap();
+    Item::new("config")
+        .save(storage, &legacy_config(generate_config(), "token_manager"))
+        .unwrap();
+    Item::new("token_data")
+        .save(
+            storage,
+            &vec![
+                LegacyTokenData {
+                    token: Addr::unchecked("eth"),
+                    token_precision: Uint256::from(1000u64),
+                },
+                LegacyTokenData {
+                    token: Addr::unchecked("btc"),
+                    token_precision: Uint256::from(100u64),
+                },
+            ],
+        )
+        .unwrap();
+    // fast prices were disabled by two signers, one vote is left over from a removed signer
+    let legacy_signers: Map<&Addr, bool> = Map::new("isSigner");
+    let legacy_votes: Map<&Addr, bool> = Map::new("disableFastPriceVotes");
+    let legacy_vote_count: Item<Uint256> = Item::new("disableFastPriceVotecount");
+    for signer in ["signer0", "signer1", "signer2"] {
+        legacy_signers
+            .save(storage, &Addr::unchecked(signer), &true)
+            .unwrap();
+    }
+    Item::new("MIN_AUTH")
+        .save(storage, &Uint256::from(2u64))
+        .unwrap();
+    for signer in ["signer0", "signer1", "signer3"] {
+        legacy_votes
+            .save(storage, &Addr::unchecked(signer), &true)
+            .unwrap();
+    }
+    legacy_vote_count
+        .save(storage, &Uint256::from(3u64))
+        .unwrap();
+    let tripped = CircuitBreaker {
+        consecutive_deviations: 3,
+        tripped: true,
+        tripped_at: Uint64::new(1000),
+        reset_votes: 1,
+    };
+    let legacy_breakers: Map<&Addr, CircuitBreaker> = Map::new("circuit_breakers");
+    legacy_breakers
+        .save(storage, &Addr::unchecked("btc"), &tripped)
+        .unwrap();
+    legacy_breakers
+        .save(storage, &Addr::unchecked("delisted"), &tripped)
+        .unwrap();
+
+    let msg = MigrateMsg {
+        timelock_delay: Some(Uint64::new(MAX_TIMELOCK_DELAY + 1)),
+        ..MigrateMsg::default()
+    };
+    let res = migrate(deps.as_mut(), env.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::InvalidTimelockDelay {});
+
+    let msg = MigrateMsg {
+        timelock_delay: Some(Uint64::new(3600)),
+        guardians: vec![guardian.clone()],
+        gov: Some(Addr::unchecked("new_gov")),
+        ..MigrateMsg::default()
+    };
+    let res = migrate(deps.as_mut(), env.clone(), msg).unwrap();
+    assert_eq!(
+        res.attributes[2].value,
+        "token_registry,roles,time_units,state_records,disable_votes,config_token_manager,circuit_breakers"
+    );
+
+    let storage = deps.as_ref().storage;
+    assert_eq!(
+        STORAGE_VERSION.load(storage).unwrap(),
+        latest_storage_version()
+    );
+    let version = cw2::get_contract_version(storage).unwrap();
+    assert_eq!(version.contract, "crates.io:fast_price_feed");
+
+    // the legacy vector order becomes the token index
+    let tokens = load_token_index_list(storage).unwrap();
+    assert_eq!(tokens.len(), 2);
+    let btc = tokens[1].clone().unwrap();
+    assert_eq!(btc.token, Addr::unchecked("btc"));
+    assert_eq!(btc.index, 1);
+    assert_eq!(btc.token_precision, Uint256::from(100u64));
+    assert!(!Item::<Vec<LegacyTokenData>>::new("token_data").exists(storage));
+
+    assert!(has_role(storage, Role::Gov, &Addr::unchecked("gov")));
+    assert!(has_role(storage, Role::Gov, &contract));
+    assert!(has_role(storage, Role::Gov, &Addr::unchecked("new_gov")));
+    assert!(has_role(
+        storage,
+        Role::TokenManager,
+        &Addr::unchecked("token_manager")
+    ));
+    assert!(has_role(storage, Role::Guardian, &guardian));
+    assert_eq!(timelock_delay(storage).unwrap(), 3600);
+
+    // the votes of current signers carry over into the current epoch
+    assert!(legacy_votes.is_empty(storage));
+    assert!(!legacy_vote_count.exists(storage));
+    assert!(DISABLE_FAST_PRICE_VOTES.has(storage, &Addr::unchecked("signer1")));
+    assert!(!DISABLE_FAST_PRICE_VOTES.has(storage, &Addr::unchecked("signer3")));
+    assert_eq!(
+        GLOBAL_STATE
+            .load(storage)
+            .unwrap()
+            .disable_votes
+            .count(Uint64::new(env.block.time.seconds())),
+        2
+    );
+    assert!(!favor_fast_price(
+        deps.as_ref(),
+        Addr::unchecked("eth"),
+        Uint64::new(env.block.time.seconds())
+    )
+    .unwrap());
+
+    // breakers move into the token records, those of delisted tokens are dropped
+    assert!(legacy_breakers.is_empty(storage));
+    let msg = QueryMsg::CircuitBreaker {
+        token: Addr::unchecked("btc"),
+    };
+    let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+    assert_eq!(from_binary::<CircuitBreaker>(&res).unwrap(), tripped);
+    assert!(!TOKEN_STATES.has(storage, &Addr::unchecked("delisted")));
+
+    // steps run once, later migrations only bump the version, the empty payload still parses
+    let msg: MigrateMsg = from_slice(b"{}").unwrap();
+    let res = migrate(deps.as_mut(), env.clone(), msg).unwrap();
+    assert_eq!(res.attributes[2].value, "");
+
+    cw2::set_contract_version(deps.as_mut().storage, "crates.io:price_feed", "0.0.1").unwrap();
+    let res = migrate(deps.as_mut(), env, MigrateMsg::default());
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::CannotMigrate {
+            previous_contract: "crates.io:price_feed".to_string()
+        }
+    );
+}
+
+#[test]
+fn test_get_price_storage_reads() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let now = env.block.time.seconds();
+    let token = Addr::unchecked("token");
+    let fast_price = Uint256::from(10_010u64);
+
+    let mut config = generate_config();
+    config.price_duration = Uint64::new(300);
+    config.max_price_update_delay = Uint64::new(3600);
+    config.max_deviation_basis_points = Uint256::from(100u64);
+    save_config(deps.as_mut().storage, config);
+    update_token_state(deps.as_mut().storage, &token, |state| {
+        state.price = fast_price;
+        state.last_updated.last_updated_at = Uint64::new(now);
+    })
+    .unwrap();
+
+    let storage = CountingStorage {
+        inner: std::mem::take(&mut deps.storage),
+        ..CountingStorage::default()
+    };
+    let deps = Deps {
+        storage: &storage,
+        api: &deps.api,
+        querier: QuerierWrapper::new(&deps.querier),
+    };
+    let price = get_price(
+        deps,
+        token,
+        Uint64::new(now),
+        Uint256::from(10_000u64),
+        true,
+    )
+    .unwrap();
+    assert_eq!(price, fast_price);
+    // pause state, global state and token state, the split records took twelve
+    // reads for the same price
+    assert_eq!(storage.reads.get(), 3);
+}
+
+#[test]
+fn test_get_price_storage_reads_with_disable_votes() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let now = Uint64::from(env.block.time.seconds());
+    let token = setup_with_tokens(deps.as_mut(), 1).remove(0);
+    let fast_price = Uint256::from(10_010u64);
+
+    let mut config = generate_config();
+    config.price_duration = Uint64::new(300);
+    config.max_price_update_delay = Uint64::new(3600);
+    config.max_deviation_basis_points = Uint256::from(100u64);
+    save_config(deps.as_mut().storage, config);
+    update_global_state(deps.as_mut().storage, |global| {
+        global.min_authorizations = Some(Uint256::from(2u64))
+    })
+    .unwrap();
+    update_token_state(deps.as_mut().storage, &token, |state| {
+        state.price = fast_price;
+        state.last_updated.last_updated_at = now;
+    })
+    .unwrap();
+    for signer in ["signer1", "signer2", "signer3"] {
+        set_role_member(
+            deps.as_mut().storage,
+            Role::Signer,
+            &Addr::unchecked(signer),
+            true,
+        )
+        .unwrap();
+    }
+    // one global and one token vote, each below min authorizations
+    disable_fast_price(
+        deps.as_mut(),
+        env.clone(),
+        Addr::unchecked("signer1"),
+        None,
+        None,
+    )
+    .unwrap();
+    disable_fast_price(
+        deps.as_mut(),
+        env.clone(),
+        Addr::unchecked("signer2"),
+        Some(token.clone()),
+        Some(now + Uint64::new(60)),
+    )
+    .unwrap();
+
+    let read_price = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, now: Uint64| {
+        let storage = CountingStorage {
+            inner: std::mem::take(&mut deps.storage),
+            ..CountingStorage::default()
+        };
+        let price = get_price(
+            Deps {
+                storage: &storage,
+                api: &deps.api,
+                querier: QuerierWrapper::new(&deps.querier),
+            },
+            token.clone(),
+            now,
+            Uint256::from(10_000u64),
+            true,
+        )
+        .unwrap();
+        let reads = storage.reads.get();
+        deps.storage = storage.inner;
+        (price, reads)
+    };
+
+    // the votes are counted from the records, the vote maps are not scanned
+    assert_eq!(read_price(&mut deps, now), (fast_price, 3));
+
+    // a second token vote disables the fast price without further reads
+    disable_fast_price(
+        deps.as_mut(),
+        env.clone(),
+        Addr::unchecked("signer3"),
+        Some(token.clone()),
+        None,
+    )
+    .unwrap();
+    assert_eq!(read_price(&mut deps, now), (fast_price, 3));
+    assert!(!favor_fast_price(deps.as_ref(), token.clone(), now).unwrap());
+
+    // once the first token vote expired, the fast price counts again
+    let later = now + Uint64::new(60);
+    update_token_state(deps.as_mut().storage, &token, |state| {
+        state.last_updated.last_updated_at = later
+    })
+    .unwrap();
+    assert!(favor_fast_price(deps.as_ref(), token.clone(), later).unwrap());
+
+    // removing the signer takes its vote out of the tally
+    disable_fast_price(
+        deps.as_mut(),
+        env.clone(),
+        Addr::unchecked("signer2"),
+        None,
+        None,
+    )
+    .unwrap();
+    assert!(!favor_fast_price(deps.as_ref(), token.clone(), later).unwrap());
+    set_role_member(
+        deps.as_mut().storage,
+        Role::Signer,
+        &Addr::unchecked("signer2"),
+        false,
+    )
+    .unwrap();
+    assert!(favor_fast_price(deps.as_ref(), token.clone(), later).unwrap());
+
+    // a new epoch clears the tallies
+    set_role_member(
+        deps.as_mut().storage,
+        Role::Gov,
+        &Addr::unchecked("gov"),
+        true,
+    )
+    .unwrap();
+    start_disable_vote_epoch(deps.as_mut(), Addr::unchecked("gov")).unwrap();
+    assert_eq!(
+        GLOBAL_STATE.load(&deps.storage).unwrap().disable_votes,
+        DisableVoteTally::default()
+    );
+    assert_eq!(
+        load_token_state(&deps.storage, &token)
+            .unwrap()
+            .disable_votes,
+        DisableVoteTally::default()
+    );
+}
+
+#[test]
+fn test_migrate_config_token_manager() {
+    #[cosmwasm_schema::cw_serde]
+    struct LegacyGlobalState {
+        config: LegacyConfig,
+        spread_basis_points: SpreadBasisPoint,
+        spread_enabled: bool,
+        min_authorizations: Option<Uint256>,
+    }
+
+    let mut deps = mock_dependencies();
+    let storage = deps.as_mut().storage;
+    let config = Config {
+        price_duration: Uint64::new(300),
+        ..generate_config()
+    };
+    Item::new("global_state")
+        .save(
+            storage,
+            &LegacyGlobalState {
+                config: legacy_config(config.clone(), "old_token_manager"),
+                spread_basis_points: SpreadBasisPoint::default(),
+                spread_enabled: true,
+                min_authorizations: Some(Uint256::from(2u64)),
+            },
+        )
+        .unwrap();
+    assert!(GLOBAL_STATE.load(storage).is_err());
+
+    migrate_config_token_manager(storage).unwrap();
+    // running it again leaves the migrated record alone
+    migrate_config_token_manager(storage).unwrap();
+
+    let global = GLOBAL_STATE.load(storage).unwrap();
+    assert_eq!(global.config, config);
+    assert!(global.spread_enabled);
+    assert_eq!(global.min_authorizations, Some(Uint256::from(2u64)));
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::GetConfig).unwrap();
+    assert_eq!(from_binary::<Config>(&res).unwrap(), config);
+}
+
+#[test]
+fn test_migrate_state_records() {
+    let mut deps = mock_dependencies();
+    let storage = deps.as_mut().storage;
+    let eth = Addr::unchecked("eth");
+    let btc = Addr::unchecked("btc");
+    let spread = SpreadBasisPoint {
+        spread_basis_points_if_inactive: Uint256::from(10u64),
+        spread_basis_points_if_chain_error: Uint256::from(100u64),
+    };
+    let last_updated = LastUpdated {
+        last_updated_at: Uint64::new(1000),
+        last_updated_block: 10,
+    };
+    let params = TokenParams {
+        price_duration: Some(Uint64::new(120)),
+        ..TokenParams::default()
+    };
+    Item::new("config")
+        .save(storage, &generate_config())
+        .unwrap();
+    Item::new("spread_basis_point")
+        .save(storage, &spread)
+        .unwrap();
+    Item::new("spread_enabled").save(storage, &true).unwrap();
+    Item::new("MIN_AUTH")
+        .save(storage, &Uint256::from(2u64))
+        .unwrap();
+    Map::new("prices")
+        .save(storage, &eth, &Uint256::from(3000u64))
+        .unwrap();
+    Map::new("token_last_updated")
+        .save(storage, &eth, &last_updated)
+        .unwrap();
+    Map::new("maxCumulativeDeltaDiffs")
+        .save(storage, &eth, &Uint256::from(5u64))
+        .unwrap();
+    Map::new("token_params")
+        .save(storage, &btc, &params)
+        .unwrap();
+    // votes of the current epoch are counted into the records, older ones are void
+    DISABLE_VOTE_EPOCH.save(storage, &1).unwrap();
+    let vote = |epoch, expires_at| DisableVote {
+        epoch,
+        voted_at: Uint64::new(900),
+        expires_at,
+    };
+    DISABLE_FAST_PRICE_VOTES
+        .save(storage, &Addr::unchecked("signer1"), &vote(1, None))
+        .unwrap();
+    DISABLE_FAST_PRICE_VOTES
+        .save(storage, &Addr::unchecked("signer2"), &vote(0, None))
+        .unwrap();
+    TOKEN_DISABLE_FAST_PRICE_VOTES
+        .save(
+            storage,
+            (&btc, &Addr::unchecked("signer1")),
+            &vote(1, Some(Uint64::new(2000))),
+        )
+        .unwrap();
+
+    migrate_state_records(storage).unwrap();
+
+    assert_eq!(
+        GLOBAL_STATE.load(storage).unwrap(),
+        GlobalState {
+            config: generate_config(),
+            spread_basis_points: spread,
+            spread_enabled: true,
+            min_authorizations: Some(Uint256::from(2u64)),
+            disable_votes: DisableVoteTally {
+                expiries: vec![None],
+            },
+        }
+    );
+    assert_eq!(
+        TOKEN_STATES.load(storage, &eth).unwrap(),
+        TokenState {
+            price: Uint256::from(3000u64),
+            last_updated,
+            max_cumulative_delta_diff: Uint256::from(5u64),
+            ..TokenState::default()
+        }
+    );
+    let btc_state = TOKEN_STATES.load(storage, &btc).unwrap();
+    assert_eq!(btc_state.params, params);
+    assert_eq!(
+        btc_state.disable_votes.expiries,
+        vec![Some(Uint64::new(2000))]
+    );
+    // the split records are gone
+    assert!(!Item::<Config>::new("config").exists(storage));
+    assert!(!Map::<&Addr, Uint256>::new("prices").has(storage, &eth));
+    assert!(!Map::<&Addr, TokenParams>::new("token_params").has(storage, &btc));
+}
+
+#[test]
+fn test_batch_update_costs() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let info = setup_with_updater(deps.as_mut());
+    let updated_at = env.block.time.seconds();
+
+    // feed{i} answers i + 1 with 8 decimals
+    let queries = Rc::new(Cell::new(0));
+    let counter = queries.clone();
+    deps.querier.update_wasm(move |query| match query {
+        WasmQuery::Smart { contract_addr, msg } => {
+            counter.set(counter.get() + 1);
+            let feed: i128 = contract_addr["feed".len()..].parse().unwrap();
+            let answer = Int256::from((feed + 1) * 100_000_000);
+            let response = match from_slice(msg).unwrap() {
+                PriceFeedQueryMsg::GetLatestRound => to_binary(&Uint128::one()),
+                PriceFeedQueryMsg::GetRoundData { round_id } => to_binary(&GetRoundDataResult {
+                    round_id,
+                    answer,
+                    started_at: Uint128::from(updated_at),
+                    updated_at: Uint128::from(updated_at),
+                    answered_in_round: round_id,
+                }),
+                _ => panic!("unexpected price feed query"),
+            };
+            SystemResult::Ok(ContractResult::Ok(response.unwrap()))
+        }
+        _ => panic!("unexpected query"),
+    });
+
+    save_config(deps.as_mut().storage, generate_config());
+    MAX_TIME_DEVIATION
+        .save(deps.as_mut().storage, &Uint64::new(1000))
+        .unwrap();
+    PRICE_DATA_INTERVAL
+        .save(deps.as_mut().storage, &Uint64::new(60))
+        .unwrap();
+    let tokens = setup_with_tokens(deps.as_mut(), 8);
+    for (i, token) in tokens.iter().enumerate() {
+        PRIMARY_PRICE_FEEDS
+            .save(
+                deps.as_mut().storage,
+                token,
+                &Addr::unchecked(format!("feed{}", i)),
+            )
+            .unwrap();
+    }
+
+    let mut storage = CountingStorage {
+        inner: std::mem::take(&mut deps.storage),
+        ..CountingStorage::default()
+    };
+    let deps = DepsMut {
+        storage: &mut storage,
+        api: &deps.api,
+        querier: QuerierWrapper::new(&deps.querier),
+    };
+    let msg = ExecuteMsg::SetCompactedPrices {
+        price_bit_array: vec![Uint256::from(1u64), Uint256::from(1u64)],
+        timestamp: env.block.time.seconds().into(),
+        layout: None,
+    };
+    let res = execute(deps, env, info, msg).unwrap();
+
+    // the shared state is read once for the whole batch, each token costs a read of its
+    // primary price feed, a read and write of its state and two queries of its feed, the gas
+    // is compared in `multitest::test_batch_update_gas`
+    assert_eq!(queries.get(), 2 * tokens.len());
+    assert_eq!(storage.reads.get(), 14 + 2 * tokens.len());
+    assert_eq!(storage.writes, 1 + tokens.len());
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(res.events.len(), 1);
+    assert_eq!(
+        res.events[0].attributes[0],
+        Attribute::new("count", tokens.len().to_string())
+    );
+
+    // every token keeps the reference price of its own feed
+    for (i, token) in tokens.iter().enumerate() {
+        assert_eq!(
+            load_token_state(&storage.inner, token)
+                .unwrap()
+                .price_data
+                .ref_price,
+            Uint256::from(i as u64 + 1) * PRICE_PRECISION
+        );
+    }
+}
+
+#[test]
+fn test_price_bit_layouts() {
+    let plain = |prices: &[u64]| -> Vec<PackedPrice> {
+        prices
+            .iter()
+            .map(|price| PackedPrice {
+                price: Uint256::from(*price),
+                decimals: None,
+            })
+            .collect()
+    };
+    let layouts = [
+        (PriceBitLayout::default(), plain(&[1, 2, 3, 4, u64::MAX])),
+        (PriceBitLayout::gmx(), plain(&[1, 2, 3, 4, 5, 6, 7, 8, 9])),
+        (
+            PriceBitLayout {
+                bit_width: 48,
+                prices_per_word: 5,
+                endianness: PriceEndianness::Big,
+                decimals_bits: 0,
+            },
+            plain(&[(1 << 48) - 1, 7, 0, 11, 13, 17]),
+        ),
+        (
+            PriceBitLayout {
+                bit_width: 32,
+                prices_per_word: 8,
+                endianness: PriceEndianness::Big,
+                decimals_bits: 5,
+            },
+            vec![
+                PackedPrice {
+                    price: Uint256::from(300_012u64),
+                    decimals: Some(2),
+                },
+                PackedPrice {
+                    price: Uint256::from(99u64),
+                    decimals: Some(31),
+                },
+            ],
+        ),
+    ];
+
+    for (layout, prices) in layouts {
+        let words = encode_prices(&layout, &prices).unwrap();
+        let per_word = layout.prices_per_word as usize;
+        assert_eq!(words.len(), prices.len().div_ceil(per_word));
+
+        // unused slots of the last word decode as zero prices
+        let decoded = decode_prices(&layout, &words).unwrap();
+        assert_eq!(decoded.len(), words.len() * per_word);
+        assert_eq!(decoded[..prices.len()], prices[..]);
+        assert!(decoded[prices.len()..]
+            .iter()
+            .all(|price| price.price.is_zero()));
+    }
+
+    // the default layout reads the little endian bytes of the word
+    let mut bytes = [0u8; 32];
+    bytes[8..16].copy_from_slice(&42u64.to_le_bytes());
+    let decoded =
+        decode_prices(&PriceBitLayout::default(), &[Uint256::from_le_bytes(bytes)]).unwrap();
+    assert_eq!(decoded[1].price, Uint256::from(42u64));
+
+    // GMX packs the price of token j at bit j * 32
+    let word = Uint256::from(5u64) << 96;
+    let decoded = decode_prices(&PriceBitLayout::gmx(), &[word]).unwrap();
+    assert_eq!(decoded[3].price, Uint256::from(5u64));
+
+    // the decimals replace the token precision when scaling
+    let price = PackedPrice {
+        price: Uint256::from(150u64),
+        decimals: Some(2),
+    };
+    assert_eq!(
+        price.adjusted_price(Uint256::from(1000u64)).unwrap(),
+        PRICE_PRECISION * Uint256::from(3u64) / Uint256::from(2u64)
+    );
+
+    for invalid in [
+        PriceBitLayout {
+            prices_per_word: 0,
+            ..PriceBitLayout::default()
+        },
+        PriceBitLayout {
+            prices_per_word: 5,
+            ..PriceBitLayout::default()
+        },
+        PriceBitLayout {
+            bit_width: 256,
+            prices_per_word: 1,
+            ..PriceBitLayout::default()
+        },
+        PriceBitLayout {
+            decimals_bits: 64,
+            ..PriceBitLayout::default()
+        },
+        // the layout size overflows u32
+        PriceBitLayout {
+            bit_width: 32,
+            prices_per_word: 1 << 30,
+            ..PriceBitLayout::default()
+        },
+    ] {
+        assert_eq!(
+            decode_prices(&invalid, &[Uint256::one()]).unwrap_err(),
+            ContractError::InvalidPriceBitLayout {}
+        );
+    }
+
+    let too_large = Uint256::from(1u64 << 32);
+    assert_eq!(
+        encode_prices(
+            &PriceBitLayout::gmx(),
+            &[PackedPrice {
+                price: too_large,
+                decimals: None
+            }]
+        )
+        .unwrap_err(),
+        ContractError::PriceExceedsBitWidth { price: too_large }
+    );
+}
+
+#[test]
+fn test_set_price_bit_layout() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let gov = setup_with_gov(deps.as_mut());
+    let updater = setup_with_updater(deps.as_mut());
+    mock_vault_price_feed(&mut deps, 100_000_000, env.block.time.seconds());
+
+    save_config(deps.as_mut().storage, generate_config());
+    MAX_TIME_DEVIATION
+        .save(deps.as_mut().storage, &Uint64::new(1000))
+        .unwrap();
+    PRICE_DATA_INTERVAL
+        .save(deps.as_mut().storage, &Uint64::new(60))
+        .unwrap();
+    let tokens = setup_with_tokens(deps.as_mut(), 8);
+
+    let msg = ExecuteMsg::SetPriceBitLayout {
+        layout: PriceBitLayout::gmx(),
+    };
+    let res = execute(deps.as_mut(), env.clone(), updater.clone(), msg.clone());
+    assert_eq!(res.unwrap_err(), ContractError::Forbidden {});
+    let invalid = ExecuteMsg::SetPriceBitLayout {
+        layout: PriceBitLayout {
+            bit_width: 0,
+            ..PriceBitLayout::gmx()
+        },
+    };
+    let res = execute(deps.as_mut(), env.clone(), gov.clone(), invalid);
+    assert_eq!(res.unwrap_err(), ContractError::InvalidPriceBitLayout {});
+    execute(deps.as_mut(), env.clone(), gov, msg).unwrap();
+
+    // eight GMX prices fit in one word
+    let prices: Vec<PackedPrice> = (1..=8u64)
+        .map(|price| PackedPrice {
+            price: Uint256::from(price),
+            decimals: None,
+        })
+        .collect();
+    let msg = ExecuteMsg::SetPricesWithBits {
+        price_bits: encode_prices(&PriceBitLayout::gmx(), &prices).unwrap()[0],
+        timestamp: Uint64::from(env.block.time.seconds()),
+        layout: None,
+    };
+    execute(deps.as_mut(), env.clone(), updater.clone(), msg).unwrap();
+    for (token, expected) in tokens.iter().zip(prices.iter()) {
+        assert_eq!(
+            load_token_state(deps.as_ref().storage, token)
+                .unwrap()
+                .price,
+            expected.price * PRICE_PRECISION
+        );
+    }
+
+    // a message can bring its own layout, here with per-token decimals
+    let layout = PriceBitLayout {
+        bit_width: 64,
+        prices_per_word: 4,
+        endianness: PriceEndianness::Big,
+        decimals_bits: 6,
+    };
+    let price = PackedPrice {
+        price: Uint256::from(12_345u64),
+        decimals: Some(2),
+    };
+    let msg = ExecuteMsg::SetCompactedPrices {
+        price_bit_array: encode_prices(&layout, &[price]).unwrap(),
+        timestamp: Uint64::from(env.block.time.seconds()),
+        layout: Some(layout),
+    };
+    execute(deps.as_mut(), env, updater, msg).unwrap();
+    assert_eq!(
+        load_token_state(deps.as_ref().storage, &tokens[0])
+            .unwrap()
+            .price,
+        PRICE_PRECISION * Uint256::from(12_345u64) / Uint256::from(100u64)
+    );
 }
@@ -0,0 +1,341 @@
+use crate::errors::ContractError;
+use crate::helpers::{
+    effective_token_params, load_global_state, load_token, load_token_state, update_global_state,
+    update_token_state, validate_price_durations,
+};
+use crate::query::{BASIS_POINTS_DIVISOR, DEFAULT_LIMIT, MAX_LIMIT};
+use crate::rbac::is_gov;
+use crate::state::{TokenParams, TokenState};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    Addr, Deps, DepsMut, Env, Order, Response, StdResult, Storage, Uint256, Uint64,
+};
+use cw_storage_plus::{Bound, Item, Map};
+
+// 7 days in seconds
+pub const MAX_TIMELOCK_DELAY: u64 = 7 * 24 * 60 * 60;
+
+pub const TIMELOCK_DELAY: Item<u64> = Item::new("timelock_delay");
+pub const NEXT_ACTION_ID: Item<u64> = Item::new("next_action_id");
+pub const PENDING_ACTIONS: Map<u64, PendingAction> = Map::new("pending_actions");
+
+/// Parameter change that has to wait for the timelock delay
+#[cw_serde]
+pub enum TimelockAction {
+    SetPriceDuration {
+        price_duration: Uint64,
+    },
+    SetMaxPriceUpdateDelay {
+        max_price_update_delay: Uint64,
+    },
+    SetSpreadBasisPointsIfInactive {
+        spread_basis_points_if_inactive: Uint256,
+    },
+    SetSpreadBasisPointsIfChainError {
+        spread_basis_points_if_chain_error: Uint256,
+    },
+    SetIsSpreadEnabled {
+        spread_enabled: bool,
+    },
+    SetTimelockDelay {
+        timelock_delay: Uint64,
+    },
+    /// per-token overrides, held to the rules of the global values they replace
+    SetTokenParams {
+        token: Addr,
+        params: TokenParams,
+    },
+}
+
+#[cw_serde]
+pub struct PendingAction {
+    pub id: u64,
+    pub action: TimelockAction,
+    /// seconds after which the action can be executed
+    pub eta: Uint64,
+}
+
+impl TimelockAction {
+    pub fn method(&self) -> &'static str {
+        match self {
+            TimelockAction::SetPriceDuration { .. } => "set_price_duration",
+            TimelockAction::SetMaxPriceUpdateDelay { .. } => "set_max_price_update_delay",
+            TimelockAction::SetSpreadBasisPointsIfInactive { .. } => {
+                "set_spread_basis_points_if_inactive"
+            }
+            TimelockAction::SetSpreadBasisPointsIfChainError { .. } => {
+                "set_spread_basis_points_if_chain_error"
+            }
+            TimelockAction::SetIsSpreadEnabled { .. } => "set_is_spread_enabled",
+            TimelockAction::SetTimelockDelay { .. } => "set_timelock_delay",
+            TimelockAction::SetTokenParams { .. } => "set_token_params",
+        }
+    }
+}
+
+pub fn timelock_delay(store: &dyn Storage) -> StdResult<u64> {
+    Ok(TIMELOCK_DELAY.may_load(store)?.unwrap_or_default())
+}
+
+fn validate_action(store: &dyn Storage, action: &TimelockAction) -> Result<(), ContractError> {
+    match action {
+        // the single-field setters keep the invariant `UpdateConfig` checks
+        TimelockAction::SetPriceDuration { price_duration } => {
+            let config = load_global_state(store)?.config;
+            validate_price_durations(*price_duration, config.max_price_update_delay)?;
+        }
+        TimelockAction::SetMaxPriceUpdateDelay {
+            max_price_update_delay,
+        } => {
+            let config = load_global_state(store)?.config;
+            validate_price_durations(config.price_duration, *max_price_update_delay)?;
+        }
+        TimelockAction::SetSpreadBasisPointsIfInactive {
+            spread_basis_points_if_inactive: spread,
+        }
+        | TimelockAction::SetSpreadBasisPointsIfChainError {
+            spread_basis_points_if_chain_error: spread,
+        } => {
+            if *spread > BASIS_POINTS_DIVISOR {
+                return Err(ContractError::InvalidSpreadBasisPoints {});
+            }
+        }
+        TimelockAction::SetTimelockDelay { timelock_delay } => {
+            if timelock_delay.u64() > MAX_TIMELOCK_DELAY {
+                return Err(ContractError::InvalidTimelockDelay {});
+            }
+        }
+        TimelockAction::SetTokenParams { token, params } => {
+            validate_token_params(store, token, params)?;
+        }
+        TimelockAction::SetIsSpreadEnabled { .. } => {}
+    }
+
+    Ok(())
+}
+
+fn validate_token_params(
+    store: &dyn Storage,
+    token: &Addr,
+    params: &TokenParams,
+) -> Result<(), ContractError> {
+    load_token(store, token)?;
+
+    let config = load_global_state(store)?.config;
+    let price_duration = params.price_duration.unwrap_or(config.price_duration);
+    let max_price_update_delay = params
+        .max_price_update_delay
+        .unwrap_or(config.max_price_update_delay);
+    if params.price_duration.is_some() || params.max_price_update_delay.is_some() {
+        validate_price_durations(price_duration, max_price_update_delay)?;
+    }
+    for spread in [
+        params.spread_basis_points_if_inactive,
+        params.spread_basis_points_if_chain_error,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        if spread > BASIS_POINTS_DIVISOR {
+            return Err(ContractError::InvalidSpreadBasisPoints {});
+        }
+    }
+
+    Ok(())
+}
+
+/// True when the action can only make prices more conservative, such actions skip the delay
+fn is_safer(store: &dyn Storage, action: &TimelockAction) -> Result<bool, ContractError> {
+    if let TimelockAction::SetTimelockDelay {
+        timelock_delay: delay,
+    } = action
+    {
+        return Ok(delay.u64() >= timelock_delay(store)?);
+    }
+
+    let global = load_global_state(store)?;
+    if let TimelockAction::SetTokenParams { token, params } = action {
+        // compared on the values in effect, an override may tighten or restore them
+        let state = load_token_state(store, token)?;
+        let current = effective_token_params(&global, &state);
+        let new = effective_token_params(
+            &global,
+            &TokenState {
+                params: params.clone(),
+                ..state
+            },
+        );
+        return Ok(new.price_duration <= current.price_duration
+            && new.max_price_update_delay <= current.max_price_update_delay
+            && new.spread_basis_points_if_inactive >= current.spread_basis_points_if_inactive
+            && new.spread_basis_points_if_chain_error
+                >= current.spread_basis_points_if_chain_error);
+    }
+
+    let spread = &global.spread_basis_points;
+    Ok(match action {
+        TimelockAction::SetPriceDuration { price_duration } => {
+            *price_duration <= global.config.price_duration
+        }
+        TimelockAction::SetMaxPriceUpdateDelay {
+            max_price_update_delay,
+        } => *max_price_update_delay <= global.config.max_price_update_delay,
+        TimelockAction::SetSpreadBasisPointsIfInactive {
+            spread_basis_points_if_inactive,
+        } => *spread_basis_points_if_inactive >= spread.spread_basis_points_if_inactive,
+        TimelockAction::SetSpreadBasisPointsIfChainError {
+            spread_basis_points_if_chain_error,
+        } => *spread_basis_points_if_chain_error >= spread.spread_basis_points_if_chain_error,
+        TimelockAction::SetIsSpreadEnabled { spread_enabled } => *spread_enabled,
+        TimelockAction::SetTimelockDelay { .. } | TimelockAction::SetTokenParams { .. } => true,
+    })
+}
+
+// callers validate the action against the current state first
+fn apply_action(store: &mut dyn Storage, action: &TimelockAction) -> Result<(), ContractError> {
+    match action {
+        TimelockAction::SetTimelockDelay { timelock_delay } => {
+            TIMELOCK_DELAY.save(store, &timelock_delay.u64())?;
+            return Ok(());
+        }
+        TimelockAction::SetTokenParams { token, params } => {
+            update_token_state(store, token, |state| state.params = params.clone())?;
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    update_global_state(store, |global| match action {
+        TimelockAction::SetPriceDuration { price_duration } => {
+            global.config.price_duration = *price_duration
+        }
+        TimelockAction::SetMaxPriceUpdateDelay {
+            max_price_update_delay,
+        } => global.config.max_price_update_delay = *max_price_update_delay,
+        TimelockAction::SetSpreadBasisPointsIfInactive {
+            spread_basis_points_if_inactive,
+        } => {
+            global.spread_basis_points.spread_basis_points_if_inactive =
+                *spread_basis_points_if_inactive
+        }
+        TimelockAction::SetSpreadBasisPointsIfChainError {
+            spread_basis_points_if_chain_error,
+        } => {
+            global
+                .spread_basis_points
+                .spread_basis_points_if_chain_error = *spread_basis_points_if_chain_error
+        }
+        TimelockAction::SetIsSpreadEnabled { spread_enabled } => {
+            global.spread_enabled = *spread_enabled
+        }
+        TimelockAction::SetTimelockDelay { .. } | TimelockAction::SetTokenParams { .. } => {}
+    })?;
+
+    Ok(())
+}
+
+/// Actions skip the queue without a delay configured or when they are safer moves
+pub fn check_immediate(store: &dyn Storage, action: &TimelockAction) -> Result<(), ContractError> {
+    if timelock_delay(store)? > 0 && !is_safer(store, action)? {
+        return Err(ContractError::TimelockRequired {});
+    }
+
+    Ok(())
+}
+
+pub fn apply_immediately(
+    store: &mut dyn Storage,
+    action: &TimelockAction,
+) -> Result<(), ContractError> {
+    validate_action(store, action)?;
+    check_immediate(store, action)?;
+    apply_action(store, action)
+}
+
+/// Raising the delay applies immediately, lowering it has to be queued
+pub fn set_timelock_delay(
+    deps: DepsMut,
+    sender: Addr,
+    timelock_delay: Uint64,
+) -> Result<Response, ContractError> {
+    is_gov(deps.as_ref(), &sender)?;
+    apply_immediately(
+        deps.storage,
+        &TimelockAction::SetTimelockDelay { timelock_delay },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("method", "set_timelock_delay")
+        .add_attribute("timelock_delay", timelock_delay))
+}
+
+pub fn queue_action(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    action: TimelockAction,
+) -> Result<Response, ContractError> {
+    is_gov(deps.as_ref(), &sender)?;
+    validate_action(deps.storage, &action)?;
+
+    let id = NEXT_ACTION_ID.may_load(deps.storage)?.unwrap_or_default();
+    let eta = Uint64::from(env.block.time.seconds() + timelock_delay(deps.storage)?);
+    PENDING_ACTIONS.save(deps.storage, id, &PendingAction { id, action, eta })?;
+    NEXT_ACTION_ID.save(deps.storage, &(id + 1))?;
+
+    Ok(Response::new()
+        .add_attribute("method", "queue_action")
+        .add_attribute("id", id.to_string())
+        .add_attribute("eta", eta))
+}
+
+pub fn execute_action(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    id: u64,
+) -> Result<Response, ContractError> {
+    is_gov(deps.as_ref(), &sender)?;
+    let pending = PENDING_ACTIONS
+        .may_load(deps.storage, id)?
+        .ok_or(ContractError::TimelockActionNotFound { id })?;
+    if env.block.time.seconds() < pending.eta.u64() {
+        return Err(ContractError::TimelockNotReady { eta: pending.eta });
+    }
+
+    validate_action(deps.storage, &pending.action)?;
+    apply_action(deps.storage, &pending.action)?;
+    PENDING_ACTIONS.remove(deps.storage, id);
+
+    Ok(Response::new()
+        .add_attribute("method", "execute_action")
+        .add_attribute("id", id.to_string())
+        .add_attribute("action", pending.action.method()))
+}
+
+pub fn cancel_action(deps: DepsMut, sender: Addr, id: u64) -> Result<Response, ContractError> {
+    is_gov(deps.as_ref(), &sender)?;
+    if !PENDING_ACTIONS.has(deps.storage, id) {
+        return Err(ContractError::TimelockActionNotFound { id });
+    }
+    PENDING_ACTIONS.remove(deps.storage, id);
+
+    Ok(Response::new()
+        .add_attribute("method", "cancel_action")
+        .add_attribute("id", id.to_string()))
+}
+
+pub fn pending_actions(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<PendingAction>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    PENDING_ACTIONS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, pending)| pending))
+        .collect()
+}
@@ -1,8 +1,9 @@
 use crate::query::GetRoundDataResult;
+#[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
-    Uint128,
+    to_binary, Addr, Binary, Deps, DepsMut, Env, Event, MessageInfo, Response, StdError, StdResult,
+    Uint128, Uint64,
 };
 use cw2::{get_contract_version, set_contract_version};
 
@@ -10,15 +11,18 @@ use crate::{
     error::ContractError,
     msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg},
     state::{
-        PriceFeedState, LATEST_ROUND, PRICE_FEED_ADMINS, PRICE_FEED_ANSWERS, PRICE_FEED_GOV,
-        PRICE_FEED_STATE,
+        PendingOwner, PriceFeedState, LATEST_ROUND, PRICE_FEED_ADMINS, PRICE_FEED_ANSWERS,
+        PRICE_FEED_GOV, PRICE_FEED_GUARDIANS, PRICE_FEED_PAUSED, PRICE_FEED_PENDING_GOV,
+        PRICE_FEED_STATE, PRICE_FEED_UPDATED_AT,
     },
 };
 
 use semver::Version;
 
 // version info
-const CONTRACT_NAME: &str = "crates.io:{{project-name}}";
+const CONTRACT_NAME: &str = "crates.io:price_feed";
+// name stored by deployments instantiated before the name was fixed
+const LEGACY_CONTRACT_NAME: &str = "crates.io:{{project-name}}";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -40,7 +44,7 @@ pub fn instantiate(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
     let version: Version = CONTRACT_VERSION.parse().map_err(from_semver)?;
 
     // Current contract version
@@ -57,28 +61,32 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, C
     }
 
     // check contract type
-    if CONTRACT_NAME != stored_info.contract {
+    if CONTRACT_NAME != stored_info.contract && LEGACY_CONTRACT_NAME != stored_info.contract {
         return Err(ContractError::CannotMigrate {
             previous_contract: stored_info.contract,
         });
     }
 
-    Ok(Response::default())
+    for guardian in msg.guardians {
+        PRICE_FEED_GUARDIANS.save(deps.storage, guardian, &true)?;
+    }
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("previous_version", stored_info.version))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::SetAdmin { admin, status } => {
-            let gov = PRICE_FEED_GOV.load(deps.storage)?;
-            if gov != info.sender {
-                return Err(ContractError::Forbidden {});
-            }
+            check_gov(deps.as_ref(), &info.sender)?;
 
             PRICE_FEED_ADMINS.save(deps.storage, admin.clone(), &status)?;
             Ok(Response::new()
@@ -88,23 +96,86 @@ pub fn execute(
         }
         ExecuteMsg::SetLatestAnswer(answer) => {
             check_admin(deps.as_ref(), info.sender)?;
+            if is_paused(deps.as_ref())? {
+                return Err(ContractError::Paused {});
+            }
 
-            let mut new_round = Uint128::zero();
-            LATEST_ROUND.update(
-                deps.storage,
-                |mut state: PriceFeedState| -> Result<PriceFeedState, ContractError> {
-                    state.round_id = state.round_id.checked_add(Uint128::one()).unwrap();
-                    state.answer = answer;
-                    new_round = state.round_id;
-                    Ok(state)
-                },
-            )?;
+            let mut state: PriceFeedState = LATEST_ROUND
+                .may_load(deps.storage)?
+                .ok_or(ContractError::Uninitialized {})?;
+            state.round_id = state.round_id.checked_add(Uint128::one())?;
+            state.answer = answer;
+            let new_round = state.round_id;
+            LATEST_ROUND.save(deps.storage, &state)?;
 
             PRICE_FEED_ANSWERS.save(deps.storage, new_round.u128(), &answer)?;
+            PRICE_FEED_UPDATED_AT.save(
+                deps.storage,
+                new_round.u128(),
+                &Uint128::from(env.block.time.seconds()),
+            )?;
             Ok(Response::new()
                 .add_attribute("method", "set_latest_answer")
                 .add_attribute("answer", answer.to_string()))
         }
+        ExecuteMsg::SetGuardian { guardian, status } => {
+            check_gov(deps.as_ref(), &info.sender)?;
+
+            PRICE_FEED_GUARDIANS.save(deps.storage, guardian.clone(), &status)?;
+            Ok(Response::new()
+                .add_attribute("method", "set_guardian")
+                .add_attribute("guardian", guardian)
+                .add_attribute("status", status.to_string()))
+        }
+        ExecuteMsg::Pause {} => {
+            let is_guardian = PRICE_FEED_GUARDIANS
+                .may_load(deps.storage, info.sender.clone())?
+                .unwrap_or_default();
+            if !is_guardian {
+                check_gov(deps.as_ref(), &info.sender)?;
+            }
+            if is_paused(deps.as_ref())? {
+                return Err(ContractError::Paused {});
+            }
+
+            PRICE_FEED_PAUSED.save(deps.storage, &true)?;
+            Ok(Response::new()
+                .add_event(Event::new("paused").add_attribute("paused_by", info.sender))
+                .add_attribute("method", "pause"))
+        }
+        ExecuteMsg::Unpause {} => {
+            check_gov(deps.as_ref(), &info.sender)?;
+            if !is_paused(deps.as_ref())? {
+                return Err(ContractError::NotPaused {});
+            }
+
+            PRICE_FEED_PAUSED.save(deps.storage, &false)?;
+            Ok(Response::new()
+                .add_event(Event::new("unpaused").add_attribute("unpaused_by", info.sender))
+                .add_attribute("method", "unpause"))
+        }
+        ExecuteMsg::ProposeOwnership {
+            new_owner,
+            expires_at,
+        } => propose_ownership(deps, env, info.sender, new_owner, expires_at),
+        ExecuteMsg::AcceptOwnership {} => accept_ownership(deps, env, info.sender),
+        ExecuteMsg::CancelOwnershipTransfer {} => {
+            check_gov(deps.as_ref(), &info.sender)?;
+            if !PRICE_FEED_PENDING_GOV.exists(deps.storage) {
+                return Err(ContractError::NoPendingOwner {});
+            }
+
+            PRICE_FEED_PENDING_GOV.remove(deps.storage);
+            Ok(Response::new().add_attribute("method", "cancel_ownership_transfer"))
+        }
+        ExecuteMsg::RenounceOwnership {} => {
+            check_gov(deps.as_ref(), &info.sender)?;
+
+            // without gov no admin can be added or removed anymore
+            PRICE_FEED_GOV.remove(deps.storage);
+            PRICE_FEED_PENDING_GOV.remove(deps.storage);
+            Ok(Response::new().add_attribute("method", "renounce_ownership"))
+        }
     }
 }
 
@@ -114,17 +185,31 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::GetLatestRound => to_binary(&PRICE_FEED_STATE.load(deps.storage)?.round_id),
         QueryMsg::GetLatestAnswer => to_binary(&PRICE_FEED_STATE.load(deps.storage)?.answer),
         QueryMsg::GetRoundData { round_id } => {
-            let answer = PRICE_FEED_ANSWERS
-                .load(deps.storage, round_id.u128())
+            let answer = PRICE_FEED_ANSWERS.may_load(deps.storage, round_id.u128())?;
+            let updated_at = PRICE_FEED_UPDATED_AT
+                .may_load(deps.storage, round_id.u128())?
                 .unwrap_or_default();
+            let answered_in_round = if answer.is_some() {
+                round_id
+            } else {
+                Uint128::zero()
+            };
             to_binary(&GetRoundDataResult {
                 round_id,
-                answer,
-                started_at: Uint128::zero(),
-                updated_at: Uint128::zero(),
-                answered_in_round: Uint128::zero(),
+                answer: answer.unwrap_or_default(),
+                started_at: updated_at,
+                updated_at,
+                answered_in_round,
             })
         }
+        QueryMsg::Owner => to_binary(&PRICE_FEED_GOV.may_load(deps.storage)?),
+        QueryMsg::PendingOwner => to_binary(&PRICE_FEED_PENDING_GOV.may_load(deps.storage)?),
+        QueryMsg::Paused => to_binary(&is_paused(deps)?),
+        QueryMsg::IsGuardian { address } => to_binary(
+            &PRICE_FEED_GUARDIANS
+                .may_load(deps.storage, address)?
+                .unwrap_or_default(),
+        ),
     }
 }
 
@@ -140,6 +225,67 @@ pub fn check_admin(deps: Deps, sender: Addr) -> Result<(), ContractError> {
     }
 }
 
+pub fn check_gov(deps: Deps, sender: &Addr) -> Result<(), ContractError> {
+    if PRICE_FEED_GOV.may_load(deps.storage)?.as_ref() != Some(sender) {
+        return Err(ContractError::Forbidden {});
+    }
+
+    Ok(())
+}
+
+pub fn is_paused(deps: Deps) -> StdResult<bool> {
+    Ok(PRICE_FEED_PAUSED
+        .may_load(deps.storage)?
+        .unwrap_or_default())
+}
+
+fn propose_ownership(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    new_owner: Addr,
+    expires_at: Option<Uint64>,
+) -> Result<Response, ContractError> {
+    check_gov(deps.as_ref(), &sender)?;
+    if expires_at.is_some_and(|expires_at| expires_at.u64() <= env.block.time.seconds()) {
+        return Err(ContractError::InvalidOwnershipTransferExpiry {});
+    }
+
+    PRICE_FEED_PENDING_GOV.save(
+        deps.storage,
+        &PendingOwner {
+            new_owner: new_owner.clone(),
+            expires_at,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("method", "propose_ownership")
+        .add_attribute("new_owner", new_owner))
+}
+
+fn accept_ownership(deps: DepsMut, env: Env, sender: Addr) -> Result<Response, ContractError> {
+    let pending_owner = PRICE_FEED_PENDING_GOV
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingOwner {})?;
+    if pending_owner.new_owner != sender {
+        return Err(ContractError::Forbidden {});
+    }
+    if pending_owner
+        .expires_at
+        .is_some_and(|expires_at| expires_at.u64() <= env.block.time.seconds())
+    {
+        return Err(ContractError::OwnershipTransferExpired {});
+    }
+
+    PRICE_FEED_GOV.save(deps.storage, &sender)?;
+    PRICE_FEED_PENDING_GOV.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("method", "accept_ownership")
+        .add_attribute("owner", sender))
+}
+
 fn from_semver(err: semver::Error) -> StdError {
     StdError::generic_err(format!("Semver: {}", err))
 }
@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{OverflowError, StdError};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,6 +6,12 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("PriceFeed: {0}")]
+    Overflow(#[from] OverflowError),
+
+    #[error("PriceFeed: not initialized")]
+    Uninitialized {},
+
     #[error("PriceFeed: cannot migrate from {previous_contract}")]
     CannotMigrate { previous_contract: String },
 
@@ -14,4 +20,19 @@ pub enum ContractError {
 
     #[error("PriceFeed: forbidden")]
     Forbidden {},
+
+    #[error("PriceFeed: paused")]
+    Paused {},
+
+    #[error("PriceFeed: not paused")]
+    NotPaused {},
+
+    #[error("PriceFeed: no pending ownership transfer")]
+    NoPendingOwner {},
+
+    #[error("PriceFeed: ownership transfer expired")]
+    OwnershipTransferExpired {},
+
+    #[error("PriceFeed: ownership transfer expiry must be in the future")]
+    InvalidOwnershipTransferExpiry {},
 }
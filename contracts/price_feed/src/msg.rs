@@ -1,17 +1,42 @@
 use crate::query::GetRoundDataResult;
+use crate::state::PendingOwner;
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, Int256, Uint128};
+use cosmwasm_std::{Addr, Int256, Uint128, Uint64};
 
 #[cw_serde]
 pub struct InstantiateMsg {}
 
+/// `{}` keeps the payload of the first release working
 #[cw_serde]
-pub struct MigrateMsg {}
+#[derive(Default)]
+pub struct MigrateMsg {
+    /// registered as guardians, older deployments lack them
+    #[serde(default)]
+    pub guardians: Vec<Addr>,
+}
 
 #[cw_serde]
 pub enum ExecuteMsg {
-    SetAdmin { admin: Addr, status: bool },
+    SetAdmin {
+        admin: Addr,
+        status: bool,
+    },
     SetLatestAnswer(Int256),
+    /// Guardians can pause answer updates, only gov can unpause
+    SetGuardian {
+        guardian: Addr,
+        status: bool,
+    },
+    Pause {},
+    Unpause {},
+    /// Offers the gov role to `new_owner`, the transfer completes once accepted
+    ProposeOwnership {
+        new_owner: Addr,
+        expires_at: Option<Uint64>,
+    },
+    AcceptOwnership {},
+    CancelOwnershipTransfer {},
+    RenounceOwnership {},
 }
 
 #[cw_serde]
@@ -24,4 +49,18 @@ pub enum QueryMsg {
     GetLatestAnswer,
     #[returns(GetRoundDataResult)]
     GetRoundData { round_id: Uint128 },
+
+    /// Current gov, None once renounced
+    #[returns(Option<Addr>)]
+    Owner,
+
+    #[returns(Option<PendingOwner>)]
+    PendingOwner,
+
+    /// Whether `SetLatestAnswer` is currently rejected
+    #[returns(bool)]
+    Paused,
+
+    #[returns(bool)]
+    IsGuardian { address: Addr },
 }
@@ -1,12 +1,16 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Int256, Uint128};
+use cosmwasm_std::{Addr, Int256, Uint128, Uint64};
 use cw_storage_plus::{Item, Map};
 
 pub const PRICE_FEED_STATE: Item<PriceFeedState> = Item::new("price_feed_state");
 pub const LATEST_ROUND: Item<PriceFeedState> = Item::new("price_feed_state");
 pub const PRICE_FEED_ANSWERS: Map<u128, Int256> = Map::new("price_feed_answers");
+pub const PRICE_FEED_UPDATED_AT: Map<u128, Uint128> = Map::new("price_feed_updated_at");
 pub const PRICE_FEED_ADMINS: Map<Addr, bool> = Map::new("price_feed_admins");
 pub const PRICE_FEED_GOV: Item<Addr> = Item::new("price_feed_gov");
+pub const PRICE_FEED_GUARDIANS: Map<Addr, bool> = Map::new("price_feed_guardians");
+pub const PRICE_FEED_PAUSED: Item<bool> = Item::new("price_feed_paused");
+pub const PRICE_FEED_PENDING_GOV: Item<PendingOwner> = Item::new("price_feed_pending_gov");
 
 #[cw_serde]
 pub struct PriceFeedState {
@@ -22,3 +26,10 @@ impl Default for PriceFeedState {
         }
     }
 }
+
+#[cw_serde]
+pub struct PendingOwner {
+    pub new_owner: Addr,
+    /// seconds after which the proposal can no longer be accepted
+    pub expires_at: Option<Uint64>,
+}
@@ -1,10 +1,13 @@
 use crate::contract::execute;
 use crate::contract::instantiate;
+use crate::contract::migrate;
+use crate::contract::query;
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg};
-use crate::state::{PRICE_FEED_ADMINS, PRICE_FEED_GOV};
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::query::GetRoundDataResult;
+use crate::state::{PendingOwner, PriceFeedState, LATEST_ROUND, PRICE_FEED_ADMINS, PRICE_FEED_GOV};
 use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-use cosmwasm_std::Addr;
+use cosmwasm_std::{from_binary, Addr, Int256, Uint128, Uint64};
 
 #[test]
 fn test_set_latest_answer() {
@@ -23,8 +26,24 @@ fn test_set_latest_answer() {
 
     // Set latest answer by admin address
     let info = mock_info("admin", &[]);
-    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
     assert!(res.is_ok());
+
+    LATEST_ROUND
+        .save(
+            deps.as_mut().storage,
+            &PriceFeedState {
+                round_id: Uint128::MAX,
+                answer: 123.into(),
+            },
+        )
+        .unwrap();
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    assert!(matches!(res, Err(ContractError::Overflow(_))));
+
+    LATEST_ROUND.remove(deps.as_mut().storage);
+    let res = execute(deps.as_mut(), env, info, msg);
+    assert!(matches!(res, Err(ContractError::Uninitialized {})));
 }
 
 #[test]
@@ -72,3 +91,211 @@ fn test_set_admin() {
         .unwrap();
     assert!(admin_status);
 }
+
+#[test]
+fn test_get_round_data() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let info = mock_info("admin", &[]);
+
+    instantiate(deps.as_mut(), env.clone(), info.clone(), InstantiateMsg {}).unwrap();
+
+    let msg = ExecuteMsg::SetLatestAnswer(Int256::from(123i128));
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let round_id: Uint128 =
+        from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::GetLatestRound).unwrap()).unwrap();
+    assert_eq!(round_id, Uint128::one());
+
+    let round: GetRoundDataResult = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::GetRoundData { round_id },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(round.answer, Int256::from(123i128));
+    assert_eq!(round.updated_at, Uint128::from(env.block.time.seconds()));
+    assert_eq!(round.answered_in_round, round_id);
+
+    // unknown rounds are reported as never answered
+    let round: GetRoundDataResult = from_binary(
+        &query(
+            deps.as_ref(),
+            env,
+            QueryMsg::GetRoundData {
+                round_id: Uint128::new(2),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(round.answer, Int256::zero());
+    assert_eq!(round.answered_in_round, Uint128::zero());
+}
+
+#[test]
+fn test_ownership_transfer() {
+    let mut deps = mock_dependencies();
+    let mut env = mock_env();
+    let gov = mock_info("gov", &[]);
+    let new_gov = mock_info("new_gov", &[]);
+    instantiate(deps.as_mut(), env.clone(), gov.clone(), InstantiateMsg {}).unwrap();
+
+    let propose = ExecuteMsg::ProposeOwnership {
+        new_owner: new_gov.sender.clone(),
+        expires_at: Some(Uint64::new(env.block.time.seconds() + 60)),
+    };
+    let res = execute(deps.as_mut(), env.clone(), new_gov.clone(), propose.clone());
+    assert!(matches!(res, Err(ContractError::Forbidden {})));
+    execute(deps.as_mut(), env.clone(), gov.clone(), propose).unwrap();
+
+    let pending: Option<PendingOwner> =
+        from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::PendingOwner).unwrap()).unwrap();
+    assert_eq!(pending.unwrap().new_owner, new_gov.sender);
+
+    // the proposal can not be accepted once expired
+    let mut late_env = env.clone();
+    late_env.block.time = late_env.block.time.plus_seconds(60);
+    let res = execute(
+        deps.as_mut(),
+        late_env,
+        new_gov.clone(),
+        ExecuteMsg::AcceptOwnership {},
+    );
+    assert!(matches!(
+        res,
+        Err(ContractError::OwnershipTransferExpired {})
+    ));
+
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        new_gov.clone(),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap();
+    assert_eq!(
+        PRICE_FEED_GOV.load(deps.as_ref().storage).unwrap(),
+        new_gov.sender
+    );
+
+    // a renounced gov can not add admins anymore
+    env.block.time = env.block.time.plus_seconds(1);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        new_gov.clone(),
+        ExecuteMsg::RenounceOwnership {},
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        env,
+        new_gov,
+        ExecuteMsg::SetAdmin {
+            admin: Addr::unchecked("admin"),
+            status: true,
+        },
+    );
+    assert!(matches!(res, Err(ContractError::Forbidden {})));
+}
+
+#[test]
+fn test_pause() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let gov = mock_info("gov", &[]);
+    let guardian = mock_info("guardian", &[]);
+    instantiate(deps.as_mut(), env.clone(), gov.clone(), InstantiateMsg {}).unwrap();
+
+    let set_guardian = ExecuteMsg::SetGuardian {
+        guardian: guardian.sender.clone(),
+        status: true,
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        guardian.clone(),
+        set_guardian.clone(),
+    );
+    assert!(matches!(res, Err(ContractError::Forbidden {})));
+    execute(deps.as_mut(), env.clone(), gov.clone(), set_guardian).unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("random", &[]),
+        ExecuteMsg::Pause {},
+    );
+    assert!(matches!(res, Err(ContractError::Forbidden {})));
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        guardian.clone(),
+        ExecuteMsg::Pause {},
+    )
+    .unwrap();
+    assert_eq!(res.events[0].ty, "paused");
+    let paused: bool =
+        from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::Paused).unwrap()).unwrap();
+    assert!(paused);
+
+    // answers are frozen until gov unpauses
+    let msg = ExecuteMsg::SetLatestAnswer(123.into());
+    let res = execute(deps.as_mut(), env.clone(), gov.clone(), msg.clone());
+    assert!(matches!(res, Err(ContractError::Paused {})));
+    let res = execute(deps.as_mut(), env.clone(), guardian, ExecuteMsg::Unpause {});
+    assert!(matches!(res, Err(ContractError::Forbidden {})));
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        gov.clone(),
+        ExecuteMsg::Unpause {},
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        gov.clone(),
+        ExecuteMsg::Unpause {},
+    );
+    assert!(matches!(res, Err(ContractError::NotPaused {})));
+    execute(deps.as_mut(), env, gov, msg).unwrap();
+}
+
+#[test]
+fn test_migrate() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    cw2::set_contract_version(deps.as_mut().storage, "crates.io:{{project-name}}", "0.0.1")
+        .unwrap();
+
+    let msg = MigrateMsg {
+        guardians: vec![Addr::unchecked("guardian")],
+    };
+    migrate(deps.as_mut(), env.clone(), msg).unwrap();
+    let version = cw2::get_contract_version(deps.as_ref().storage).unwrap();
+    assert_eq!(version.contract, "crates.io:price_feed");
+    let is_guardian: bool = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::IsGuardian {
+                address: Addr::unchecked("guardian"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(is_guardian);
+    // the empty payload of earlier releases still parses
+    let msg: MigrateMsg = cosmwasm_std::from_slice(b"{}").unwrap();
+    migrate(deps.as_mut(), env.clone(), msg).unwrap();
+
+    cw2::set_contract_version(deps.as_mut().storage, "crates.io:fast_price_feed", "0.0.1").unwrap();
+    let res = migrate(deps.as_mut(), env, MigrateMsg::default());
+    assert!(matches!(res, Err(ContractError::CannotMigrate { .. })));
+}